@@ -0,0 +1,204 @@
+//! Annealed importance sampling driven by one quasirandom point per run.
+//!
+//! Annealed importance sampling (Neal, 2001) moves samples from a prior to a
+//! posterior through a ladder of tempered distributions
+//! `prior(x) * likelihood(x)^beta`, with `beta` rising from zero to one. Each
+//! run draws from the prior, then applies one Markov transition per rung,
+//! and its weight accumulates the likelihood ratio between consecutive
+//! rungs. The mean weight estimates the normalizing constant, the marginal
+//! likelihood used for Bayesian model comparison.
+//!
+//! Every uniform a run consumes comes from a single point of an `RSequence`,
+//! with a fixed layout: the first dimensions draw from the prior, and each
+//! transition gets its own block of dimensions after those. Run `i` uses
+//! point `i`, so every stage of the ladder, taken across runs, is fed evenly
+//! spread uniforms, which lowers the variance of the estimate compared to
+//! independent random numbers.
+
+use crate::distributions::Distribution;
+use crate::sequences::{RSequence, UniformSequence};
+
+/// An annealing ladder and the layout of its dimensions.
+///
+/// ```
+/// use quasirandom::ais::Annealer;
+/// use quasirandom::distributions::Normal;
+///
+/// // Prior N(0, 1), likelihood of one observation at 1 with unit noise
+/// let log_prior = |x: &f64| -x * x / 2.0;
+/// let log_likelihood = |x: &f64| -(x - 1.0) * (x - 1.0) / 2.0;
+///
+/// let annealer = Annealer::new(Annealer::linear_ladder(20), 2);
+/// let runs = annealer.run(
+///     4096,
+///     &Normal::standard(),
+///     log_likelihood,
+///     |&x: &f64, beta, u: &[f64]| {
+///         // One Metropolis step with a uniform proposal, invariant for the
+///         // tempered distribution of this rung
+///         let proposal = x + (2.0 * u[0] - 1.0);
+///         let log_ratio = log_prior(&proposal) + beta * log_likelihood(&proposal)
+///             - log_prior(&x)
+///             - beta * log_likelihood(&x);
+///         if u[1].ln() < log_ratio { proposal } else { x }
+///     },
+/// );
+///
+/// // The marginal likelihood of the observation, up to the constant of the
+/// // likelihood: the integral of N(x; 0, 1) exp(-(x - 1)^2 / 2)
+/// let exact = (0.5f64).sqrt() * (-0.25f64).exp();
+/// assert!((runs.log_normalizer() - exact.ln()).abs() < 0.02);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annealer {
+    betas: Vec<f64>,
+    step_dimension: usize,
+    seed: f64,
+}
+
+impl Annealer {
+    /// A ladder of inverse temperatures, rising from zero to one, and the
+    /// number of uniforms each transition consumes
+    pub fn new(betas: Vec<f64>, step_dimension: usize) -> Self {
+        assert!(betas.len() >= 2);
+        assert_eq!(betas[0], 0.0);
+        assert_eq!(betas[betas.len() - 1], 1.0);
+        assert!(betas.windows(2).all(|pair| pair[0] < pair[1]), "the ladder must rise");
+        Self {
+            betas,
+            step_dimension,
+            seed: 0.0,
+        }
+    }
+
+    /// `steps + 1` equally spaced inverse temperatures from zero to one
+    pub fn linear_ladder(steps: usize) -> Vec<f64> {
+        assert!(steps >= 1);
+        (0..=steps).map(|k| k as f64 / steps as f64).collect()
+    }
+
+    /// Inverse temperatures from zero to one, spaced geometrically from
+    /// `smallest` for the first positive rung, which suits likelihoods that
+    /// are sharply peaked compared to the prior
+    pub fn geometric_ladder(steps: usize, smallest: f64) -> Vec<f64> {
+        assert!(steps >= 1);
+        assert!(smallest > 0.0 && smallest < 1.0);
+        let mut betas: Vec<f64> = (0..steps).map(|k| smallest.powf(1.0 - k as f64 / (steps - 1).max(1) as f64)).collect();
+        betas.insert(0, 0.0);
+        *betas.last_mut().unwrap() = 1.0;
+        betas
+    }
+
+    /// The seed of the sequence, in `[0, 1)`
+    pub fn with_seed(mut self, seed: f64) -> Self {
+        assert!((0.0..1.0).contains(&seed));
+        self.seed = seed;
+        self
+    }
+
+    pub fn betas(&self) -> &[f64] {
+        &self.betas
+    }
+
+    /// The dimensions of the sequence a run consumes with a prior of
+    /// `prior_dimension` dimensions
+    pub fn dimension(&self, prior_dimension: usize) -> usize {
+        prior_dimension + (self.betas.len() - 1) * self.step_dimension
+    }
+
+    /// Performs `runs` runs.
+    ///
+    /// Each run draws `x` from `prior`, then for every rung `k` from one on
+    /// adds `(beta_k - beta_(k-1)) * log_likelihood(x)` to its log weight
+    /// and replaces `x` with `transition(x, beta_k, uniforms)`. The
+    /// transition must leave `prior(x) * likelihood(x)^beta_k` invariant.
+    pub fn run<D: Distribution>(
+        &self,
+        runs: usize,
+        prior: &D,
+        mut log_likelihood: impl FnMut(&D::Output) -> f64,
+        mut transition: impl FnMut(&D::Output, f64, &[f64]) -> D::Output,
+    ) -> WeightedRuns<D::Output> {
+        let prior_dimension = prior.dimension();
+        let mut sequence = RSequence::new(self.dimension(prior_dimension), self.seed);
+        let mut point = vec![0.0; sequence.dimension()];
+        let mut samples = Vec::with_capacity(runs);
+        let mut log_weights = Vec::with_capacity(runs);
+        for _ in 0..runs {
+            sequence.next_into(&mut point);
+            let (initial, steps) = point.split_at(prior_dimension);
+            let mut x = prior.sample(initial);
+            let mut log_weight = 0.0;
+            for (pair, uniforms) in self.betas.windows(2).zip(steps.chunks(self.step_dimension.max(1))) {
+                log_weight += (pair[1] - pair[0]) * log_likelihood(&x);
+                x = transition(&x, pair[1], &uniforms[..self.step_dimension]);
+            }
+            samples.push(x);
+            log_weights.push(log_weight);
+        }
+        WeightedRuns { samples, log_weights }
+    }
+}
+
+/// The final samples of a set of annealing runs and their log weights.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightedRuns<X> {
+    pub samples: Vec<X>,
+    pub log_weights: Vec<f64>,
+}
+
+impl<X> WeightedRuns<X> {
+    /// The log of the mean weight, which estimates the log of the normalizing
+    /// constant of `prior(x) * likelihood(x)`
+    pub fn log_normalizer(&self) -> f64 {
+        let max = self.log_weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if max == f64::NEG_INFINITY {
+            return max;
+        }
+        let sum: f64 = self.log_weights.iter().map(|w| (w - max).exp()).sum();
+        max + (sum / self.log_weights.len() as f64).ln()
+    }
+
+    /// The weights scaled to sum to one, for weighted posterior estimates
+    pub fn normalized_weights(&self) -> Vec<f64> {
+        let max = self.log_weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = self.log_weights.iter().map(|w| (w - max).exp()).collect();
+        let sum: f64 = weights.iter().sum();
+        weights.into_iter().map(|w| w / sum).collect()
+    }
+
+    /// Kish's effective sample size of the weights
+    pub fn effective_sample_size(&self) -> f64 {
+        1.0 / self.normalized_weights().iter().map(|w| w * w).sum::<f64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::Normal;
+
+    #[test]
+    fn estimates_the_marginal_likelihood() {
+        // Prior N(0, 1) and a sharp likelihood N(x; 2, 0.3^2), unnormalized:
+        // the evidence is 0.3 / sqrt(1.09) * exp(-4 / 2.18)
+        let log_likelihood = |x: &f64| -(x - 2.0) * (x - 2.0) / 0.18;
+        let log_target = |x: f64, beta: f64| -x * x / 2.0 + beta * log_likelihood(&x);
+        let annealer = Annealer::new(Annealer::geometric_ladder(30, 0.01), 2).with_seed(0.5);
+        assert_eq!(annealer.betas().len(), 31);
+        assert_eq!(annealer.dimension(1), 61);
+
+        let runs = annealer.run(16000, &Normal::standard(), log_likelihood, |&x: &f64, beta, u: &[f64]| {
+            let proposal = x + 0.5 * (2.0 * u[0] - 1.0);
+            if u[1].ln() < log_target(proposal, beta) - log_target(x, beta) { proposal } else { x }
+        });
+        let exact = (0.3 / 1.09f64.sqrt()).ln() - 4.0 / 2.18;
+        assert!((runs.log_normalizer() - exact).abs() < 0.05, "{} vs {}", runs.log_normalizer(), exact);
+
+        // The weighted samples follow the posterior, N(2 / 1.09, 0.09 / 1.09)
+        let weights = runs.normalized_weights();
+        let mean: f64 = runs.samples.iter().zip(&weights).map(|(x, w)| x * w).sum();
+        assert!((mean - 2.0 / 1.09).abs() < 0.02, "{}", mean);
+        assert!(runs.effective_sample_size() > 1000.0);
+    }
+}
@@ -0,0 +1,122 @@
+//! Exploration noise for multi-armed bandits and reinforcement learning.
+//!
+//! Epsilon-greedy agents make two kinds of random choice: whether to explore
+//! on a given step, and which arm to try when exploring (or how to break a tie
+//! between equally valued arms). Drawing both from low-discrepancy streams
+//! makes the exploration rate exact over any window of steps and spreads the
+//! exploratory pulls evenly over the arms from the start, instead of leaving
+//! some arms untried by bad luck.
+
+use crate::index_from_uniform;
+use crate::streams::StreamKey;
+use crate::Qrng;
+
+/// The random choices of an epsilon-greedy agent.
+///
+/// Whether to explore, which arm to explore, and how to break ties each come
+/// from their own substream of `key`, so they are decorrelated from one
+/// another and from other agents with different keys.
+///
+/// ```
+/// use quasirandom::bandit::EpsilonGreedy;
+/// use quasirandom::streams::StreamKey;
+///
+/// let mut agent = EpsilonGreedy::new(0.1, StreamKey::root(3));
+/// let mut estimates = [0.0; 4];
+/// let mut pulls = [0; 4];
+/// for _ in 0..1000 {
+///     let arm = agent.choose(&estimates);
+///     let reward = [0.2, 0.5, 0.4, 0.1][arm];
+///     pulls[arm] += 1;
+///     estimates[arm] += (reward - estimates[arm]) / pulls[arm] as f64;
+/// }
+/// assert!(pulls[1] > 850);
+/// ```
+#[derive(Debug, Clone)]
+pub struct EpsilonGreedy {
+    epsilon: f64,
+    explore: Qrng<f64>,
+    arms: Qrng<f64>,
+    ties: Qrng<f64>,
+}
+
+impl EpsilonGreedy {
+    pub fn new(epsilon: f64, key: StreamKey) -> Self {
+        assert!((0.0..=1.0).contains(&epsilon));
+        Self {
+            epsilon,
+            explore: Qrng::from_key(key.child(0)),
+            arms: Qrng::from_key(key.child(1)),
+            ties: Qrng::from_key(key.child(2)),
+        }
+    }
+
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    pub fn set_epsilon(&mut self, epsilon: f64) {
+        assert!((0.0..=1.0).contains(&epsilon));
+        self.epsilon = epsilon;
+    }
+
+    /// Picks an arm given the current value estimates: with probability
+    /// epsilon any arm, and otherwise one of the arms with the highest
+    /// estimate.
+    pub fn choose(&mut self, estimates: &[f64]) -> usize {
+        assert!(!estimates.is_empty());
+        if self.explore() {
+            return self.explore_arm(estimates.len());
+        }
+        let best = estimates.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let candidates: Vec<usize> = (0..estimates.len()).filter(|&arm| estimates[arm] == best).collect();
+        self.break_tie(&candidates)
+    }
+
+    /// Decides whether this step explores. Over any run of `n` steps the
+    /// number of explorations is within about one of `epsilon * n`.
+    pub fn explore(&mut self) -> bool {
+        self.explore.gen() < self.epsilon
+    }
+
+    /// Picks one of `arms` arms to explore, spreading picks evenly.
+    pub fn explore_arm(&mut self, arms: usize) -> usize {
+        assert!(arms > 0);
+        index_from_uniform(self.arms.gen(), arms)
+    }
+
+    /// Picks one of several equally good candidates, spreading picks evenly.
+    pub fn break_tie(&mut self, candidates: &[usize]) -> usize {
+        assert!(!candidates.is_empty());
+        if candidates.len() == 1 {
+            return candidates[0];
+        }
+        candidates[index_from_uniform(self.ties.gen(), candidates.len())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exploration_is_even() {
+        let mut agent = EpsilonGreedy::new(0.1, StreamKey::root(1));
+        let estimates = [1.0, 0.0, 0.0, 0.0, 0.0];
+        let mut pulls = [0; 5];
+        for _ in 0..2000 {
+            pulls[agent.choose(&estimates)] += 1;
+        }
+        // 200 explorations, 40 per arm, plus 1800 greedy pulls of arm 0
+        assert!((pulls[0] - 1840_i32).abs() <= 3, "{:?}", pulls);
+        assert!(pulls[1..].iter().all(|&count| (count - 40_i32).abs() <= 3), "{:?}", pulls);
+
+        // Ties are split evenly too
+        let mut agent = EpsilonGreedy::new(0.0, StreamKey::root(2));
+        let mut pulls = [0; 3];
+        for _ in 0..300 {
+            pulls[agent.choose(&[0.5, 0.5, 0.5])] += 1;
+        }
+        assert!(pulls.iter().all(|&count| (count - 100_i32).abs() <= 2), "{:?}", pulls);
+    }
+}
@@ -0,0 +1,262 @@
+//! Comparing backends on test integrands.
+//!
+//! With several sequences to choose from, the fair way to pick one is to
+//! integrate functions with known integrals and watch how fast the error
+//! falls. `Benchmark` does that for any `UniformSequence`: it runs a number
+//! of replicates, each with its own backend instance and test integrand
+//! (typically with randomized parameters), and reports the root mean square
+//! error at a list of sample counts.
+//!
+//! The standard suite is the Genz family of test integrands, available on
+//! their own as `Genz` and run with randomized parameters by
+//! `Benchmark::run_genz`.
+
+use std::fmt;
+
+use crate::reduce::CompensatedSum;
+use crate::sequences::UniformSequence;
+
+mod genz;
+
+pub use genz::{Genz, GenzFamily};
+
+/// A function on `[0, 1)^d` with a known integral
+pub trait TestIntegrand {
+    fn dimension(&self) -> usize;
+
+    fn evaluate(&self, x: &[f64]) -> f64;
+
+    /// The exact integral over the unit cube
+    fn exact(&self) -> f64;
+}
+
+impl<T: TestIntegrand + ?Sized> TestIntegrand for Box<T> {
+    fn dimension(&self) -> usize {
+        (**self).dimension()
+    }
+
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        (**self).evaluate(x)
+    }
+
+    fn exact(&self) -> f64 {
+        (**self).exact()
+    }
+}
+
+/// The settings of a benchmark run: the sample counts to measure at and the
+/// number of replicates to average over.
+///
+/// ```
+/// use quasirandom::benchmark::{Benchmark, TestIntegrand};
+/// use quasirandom::sequences::RSequence;
+///
+/// struct Product(f64);
+///
+/// impl TestIntegrand for Product {
+///     fn dimension(&self) -> usize {
+///         2
+///     }
+///
+///     fn evaluate(&self, x: &[f64]) -> f64 {
+///         (x[0] + self.0) * x[1]
+///     }
+///
+///     fn exact(&self) -> f64 {
+///         (0.5 + self.0) / 2.0
+///     }
+/// }
+///
+/// let report = Benchmark::new([100, 1000, 10000], 8).run(
+///     |replicate| RSequence::new(2, replicate as f64 / 8.0),
+///     |replicate| Product(replicate as f64),
+/// );
+/// assert!(report.measurements[2].rmse < report.measurements[0].rmse);
+/// println!("{}", report);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Benchmark {
+    sample_counts: Vec<u64>,
+    replicates: usize,
+}
+
+/// The outcome of a benchmark run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub measurements: Vec<Measurement>,
+}
+
+/// The error after a given number of samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub samples: u64,
+    /// The root mean square error over all replicates
+    pub rmse: f64,
+}
+
+impl Benchmark {
+    pub fn new(sample_counts: impl IntoIterator<Item = u64>, replicates: usize) -> Self {
+        let mut sample_counts: Vec<u64> = sample_counts.into_iter().collect();
+        sample_counts.sort_unstable();
+        sample_counts.dedup();
+        assert!(!sample_counts.is_empty());
+        assert!(sample_counts[0] > 0);
+        assert!(replicates > 0);
+        Self {
+            sample_counts,
+            replicates,
+        }
+    }
+
+    pub fn sample_counts(&self) -> &[u64] {
+        &self.sample_counts
+    }
+
+    pub fn replicates(&self) -> usize {
+        self.replicates
+    }
+
+    /// Runs every replicate, building its backend with `backend(replicate)`
+    /// and its integrand with `integrand(replicate)`.
+    ///
+    /// Each replicate takes successive points from its backend, so errors at
+    /// smaller sample counts are measured on prefixes of the same points.
+    /// Panics if a finite backend runs out before the largest sample count.
+    pub fn run<S: UniformSequence, I: TestIntegrand>(
+        &self,
+        mut backend: impl FnMut(usize) -> S,
+        mut integrand: impl FnMut(usize) -> I,
+    ) -> Report {
+        let mut squared_errors = vec![0.0; self.sample_counts.len()];
+        for replicate in 0..self.replicates {
+            let mut sequence = backend(replicate);
+            let integrand = integrand(replicate);
+            assert_eq!(sequence.dimension(), integrand.dimension());
+
+            let exact = integrand.exact();
+            let mut point = vec![0.0; sequence.dimension()];
+            let mut sum = CompensatedSum::new();
+            let mut samples = 0;
+            for (&count, squared_error) in self.sample_counts.iter().zip(&mut squared_errors) {
+                while samples < count {
+                    assert!(sequence.next_into(&mut point), "backend ran out of points");
+                    sum += integrand.evaluate(&point);
+                    samples += 1;
+                }
+                *squared_error += (sum.sum() / count as f64 - exact).powi(2);
+            }
+        }
+
+        let measurements = self
+            .sample_counts
+            .iter()
+            .zip(squared_errors)
+            .map(|(&samples, squared_error)| Measurement {
+                samples,
+                rmse: (squared_error / self.replicates as f64).sqrt(),
+            })
+            .collect();
+        Report { measurements }
+    }
+
+    /// Runs the standard suite for one Genz family in `dimension`
+    /// dimensions. Replicate `r` integrates `Genz::random` with seed `r` and
+    /// the family's standard difficulty.
+    pub fn run_genz<S: UniformSequence>(
+        &self,
+        family: GenzFamily,
+        dimension: usize,
+        backend: impl FnMut(usize) -> S,
+    ) -> Report {
+        self.run(backend, |replicate| {
+            Genz::random(family, dimension, family.standard_difficulty(), replicate as u64)
+        })
+    }
+}
+
+impl Report {
+    /// The least-squares slope of `log(rmse)` against `log(samples)`: about
+    /// -0.5 for Monte Carlo and approaching -1 for a good quasirandom backend
+    /// on a smooth integrand
+    pub fn convergence_rate(&self) -> f64 {
+        assert!(self.measurements.len() > 1);
+        let points: Vec<(f64, f64)> = self
+            .measurements
+            .iter()
+            .map(|m| ((m.samples as f64).ln(), m.rmse.ln()))
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        covariance / variance
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "samples     rmse")?;
+        for measurement in &self.measurements {
+            writeln!(f, "{:<12}{:.6e}", measurement.samples, measurement.rmse)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequences::RSequence;
+
+    /// Independent-looking points, standing in for Monte Carlo
+    struct Pseudorandom(u64);
+
+    impl UniformSequence for Pseudorandom {
+        fn dimension(&self) -> usize {
+            3
+        }
+
+        fn next_into(&mut self, out: &mut [f64]) -> bool {
+            for x in out {
+                self.0 += 1;
+                *x = crate::hash::to_unit(crate::hash::mix(self.0));
+            }
+            true
+        }
+    }
+
+    struct Sum(f64);
+
+    impl TestIntegrand for Sum {
+        fn dimension(&self) -> usize {
+            3
+        }
+
+        fn evaluate(&self, x: &[f64]) -> f64 {
+            x.iter().map(|x| (x + self.0).sin()).sum()
+        }
+
+        fn exact(&self) -> f64 {
+            3.0 * (self.0.cos() - (1.0 + self.0).cos())
+        }
+    }
+
+    #[test]
+    fn quasirandom_converges_faster() {
+        let benchmark = Benchmark::new([4096, 256, 1024, 16384], 16);
+        assert_eq!(benchmark.sample_counts(), [256, 1024, 4096, 16384]);
+
+        let integrand = |replicate: usize| Sum(replicate as f64 / 4.0);
+        let qmc = benchmark.run(|replicate| RSequence::new(3, replicate as f64 / 16.0), integrand);
+        let mc = benchmark.run(|replicate| Pseudorandom((replicate as u64) << 32), integrand);
+        assert!(qmc.convergence_rate() < -0.8, "{}", qmc);
+        assert!((mc.convergence_rate() + 0.5).abs() < 0.2, "{}", mc);
+        assert!(qmc.measurements[3].rmse < mc.measurements[3].rmse / 10.0);
+
+        let gaussian = benchmark.run_genz(GenzFamily::Gaussian, 3, |replicate| {
+            RSequence::new(3, replicate as f64 / 16.0)
+        });
+        assert!(gaussian.measurements[3].rmse < gaussian.measurements[0].rmse / 4.0, "{}", gaussian);
+    }
+}
@@ -0,0 +1,222 @@
+use std::f64::consts::PI;
+
+use super::TestIntegrand;
+use crate::distributions::erfc;
+use crate::hash::{combine, to_unit};
+
+/// The six test integrand families of Genz, "Testing Multidimensional
+/// Integration Routines" (1984).
+///
+/// In the formulas below, `a` is the difficulty vector (larger values give
+/// harder integrands) and `u` the affinity vector, which shifts features
+/// around the cube without changing the difficulty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GenzFamily {
+    /// `cos(2 pi u[0] + sum(a[i] x[i]))`
+    Oscillatory,
+    /// `prod(1 / (a[i]^-2 + (x[i] - u[i])^2))`
+    ProductPeak,
+    /// `(1 + sum(a[i] x[i]))^-(d + 1)`, with an integral computed from `2^d`
+    /// terms, so keep `d` below about 20
+    CornerPeak,
+    /// `exp(-sum(a[i]^2 (x[i] - u[i])^2))`
+    Gaussian,
+    /// `exp(-sum(a[i] |x[i] - u[i]|))`, which has kinks at `u`
+    Continuous,
+    /// `exp(sum(a[i] x[i]))` where `x[0] <= u[0]` and `x[1] <= u[1]`, and
+    /// zero elsewhere
+    Discontinuous,
+}
+
+impl GenzFamily {
+    pub const ALL: [GenzFamily; 6] = [
+        GenzFamily::Oscillatory,
+        GenzFamily::ProductPeak,
+        GenzFamily::CornerPeak,
+        GenzFamily::Gaussian,
+        GenzFamily::Continuous,
+        GenzFamily::Discontinuous,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GenzFamily::Oscillatory => "oscillatory",
+            GenzFamily::ProductPeak => "product peak",
+            GenzFamily::CornerPeak => "corner peak",
+            GenzFamily::Gaussian => "gaussian",
+            GenzFamily::Continuous => "continuous",
+            GenzFamily::Discontinuous => "discontinuous",
+        }
+    }
+
+    /// The sum of the difficulty vector used by `Genz::random` in the
+    /// standard suite, following the values commonly used in the literature
+    pub fn standard_difficulty(self) -> f64 {
+        match self {
+            GenzFamily::Oscillatory => 9.0,
+            GenzFamily::ProductPeak => 7.25,
+            GenzFamily::CornerPeak => 1.85,
+            GenzFamily::Gaussian => 7.03,
+            GenzFamily::Continuous => 20.4,
+            GenzFamily::Discontinuous => 4.3,
+        }
+    }
+}
+
+/// One member of a Genz family, with its exact integral.
+///
+/// ```
+/// use quasirandom::benchmark::{Genz, GenzFamily, TestIntegrand};
+///
+/// let gaussian = Genz::new(GenzFamily::Gaussian, vec![2.0, 3.0], vec![0.5, 0.25]);
+/// assert_eq!(gaussian.evaluate(&[0.5, 0.25]), 1.0);
+/// assert!((gaussian.exact() - 0.3772).abs() < 1e-4);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Genz {
+    family: GenzFamily,
+    difficulty: Vec<f64>,
+    affinity: Vec<f64>,
+}
+
+impl Genz {
+    /// Panics unless every difficulty is positive and every affinity is in
+    /// `[0, 1]`.
+    pub fn new(family: GenzFamily, difficulty: Vec<f64>, affinity: Vec<f64>) -> Self {
+        assert!(!difficulty.is_empty());
+        assert_eq!(difficulty.len(), affinity.len());
+        assert!(difficulty.iter().all(|&a| a > 0.0 && a.is_finite()));
+        assert!(affinity.iter().all(|u| (0.0..=1.0).contains(u)));
+        Self {
+            family,
+            difficulty,
+            affinity,
+        }
+    }
+
+    /// A family member with pseudorandom parameters derived from `seed`:
+    /// affinities uniform in `[0, 1)`, and difficulties uniform in `(0, 1)`
+    /// then scaled to sum to `total_difficulty`
+    pub fn random(family: GenzFamily, dimension: usize, total_difficulty: f64, seed: u64) -> Self {
+        assert!(dimension > 0);
+        let uniform = |stream: u64, i: usize| to_unit(combine(combine(seed, stream), i as u64));
+        let difficulty: Vec<f64> = (0..dimension).map(|i| 1.0 - uniform(0, i)).collect();
+        let scale = total_difficulty / difficulty.iter().sum::<f64>();
+        Self::new(
+            family,
+            difficulty.iter().map(|a| a * scale).collect(),
+            (0..dimension).map(|i| uniform(1, i)).collect(),
+        )
+    }
+
+    pub fn family(&self) -> GenzFamily {
+        self.family
+    }
+
+    pub fn difficulty(&self) -> &[f64] {
+        &self.difficulty
+    }
+
+    pub fn affinity(&self) -> &[f64] {
+        &self.affinity
+    }
+
+    fn pairs(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.difficulty.iter().copied().zip(self.affinity.iter().copied())
+    }
+}
+
+impl TestIntegrand for Genz {
+    fn dimension(&self) -> usize {
+        self.difficulty.len()
+    }
+
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        assert_eq!(x.len(), self.difficulty.len());
+        let dot = || x.iter().zip(&self.difficulty).map(|(x, a)| a * x).sum::<f64>();
+        match self.family {
+            GenzFamily::Oscillatory => (2.0 * PI * self.affinity[0] + dot()).cos(),
+            GenzFamily::ProductPeak => {
+                x.iter().zip(self.pairs()).map(|(x, (a, u))| 1.0 / (a.powi(-2) + (x - u).powi(2))).product()
+            }
+            GenzFamily::CornerPeak => (1.0 + dot()).powi(-(x.len() as i32 + 1)),
+            GenzFamily::Gaussian => {
+                (-x.iter().zip(self.pairs()).map(|(x, (a, u))| (a * (x - u)).powi(2)).sum::<f64>()).exp()
+            }
+            GenzFamily::Continuous => {
+                (-x.iter().zip(self.pairs()).map(|(x, (a, u))| a * (x - u).abs()).sum::<f64>()).exp()
+            }
+            GenzFamily::Discontinuous => {
+                if x.iter().zip(&self.affinity).take(2).any(|(x, u)| x > u) {
+                    0.0
+                } else {
+                    dot().exp()
+                }
+            }
+        }
+    }
+
+    fn exact(&self) -> f64 {
+        match self.family {
+            GenzFamily::Oscillatory => {
+                let phase = 2.0 * PI * self.affinity[0] + self.difficulty.iter().sum::<f64>() / 2.0;
+                phase.cos() * self.difficulty.iter().map(|a| 2.0 * (a / 2.0).sin() / a).product::<f64>()
+            }
+            GenzFamily::ProductPeak => self.pairs().map(|(a, u)| a * ((a * (1.0 - u)).atan() + (a * u).atan())).product(),
+            GenzFamily::CornerPeak => {
+                // Inclusion-exclusion over the corners of the cube
+                let d = self.difficulty.len();
+                let mut sum = 0.0;
+                for corner in 0..1u64 << d {
+                    let shift: f64 = (0..d).filter(|i| corner >> i & 1 == 1).map(|i| self.difficulty[i]).sum();
+                    let sign = if corner.count_ones() % 2 == 0 { 1.0 } else { -1.0 };
+                    sum += sign / (1.0 + shift);
+                }
+                let factorial: f64 = (1..=d).map(|k| k as f64).product();
+                sum / (factorial * self.difficulty.iter().product::<f64>())
+            }
+            GenzFamily::Gaussian => self
+                .pairs()
+                .map(|(a, u)| PI.sqrt() / (2.0 * a) * (erf(a * (1.0 - u)) + erf(a * u)))
+                .product(),
+            GenzFamily::Continuous => self
+                .pairs()
+                .map(|(a, u)| (2.0 - (-a * u).exp() - (-a * (1.0 - u)).exp()) / a)
+                .product(),
+            GenzFamily::Discontinuous => self
+                .pairs()
+                .enumerate()
+                .map(|(i, (a, u))| ((a * if i < 2 { u } else { 1.0 }).exp() - 1.0) / a)
+                .product(),
+        }
+    }
+}
+
+fn erf(x: f64) -> f64 {
+    1.0 - erfc(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequences::{RSequence, UniformSequence};
+
+    #[test]
+    fn exact_integrals() {
+        for family in GenzFamily::ALL {
+            for seed in 0..3 {
+                let genz = Genz::random(family, 3, family.standard_difficulty(), seed);
+                let mut sequence = RSequence::new(3, 0.5);
+                let mut point = [0.0; 3];
+                let n = 200_000;
+                let mut sum = 0.0;
+                for _ in 0..n {
+                    sequence.next_into(&mut point);
+                    sum += genz.evaluate(&point);
+                }
+                let error = (sum / n as f64 - genz.exact()).abs();
+                assert!(error < 5e-3 * genz.exact().abs().max(0.1), "{} {:?}", family.name(), genz);
+            }
+        }
+    }
+}
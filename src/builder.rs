@@ -0,0 +1,105 @@
+//! Configuring a `Qrng` option by option.
+//!
+//! `Qrng::new` only takes a seed. `Qrng::builder` starts from the same
+//! defaults and adds the other options of the generator one method at a
+//! time, so new options never change existing calls.
+
+use std::marker::PhantomData;
+
+use crate::streams::StreamKey;
+use crate::{to_fixed, Qrng, Quasirandom, State};
+
+/// Options for a `Qrng`, created by `Qrng::builder`.
+///
+/// ```
+/// use quasirandom::streams::StreamKey;
+/// use quasirandom::Qrng;
+///
+/// let mut qrng = Qrng::<(f64, f64)>::builder()
+///     .seed(0.25)
+///     .burn_in(100)
+///     .shift(StreamKey::root(7))
+///     .build();
+/// let (x, y) = qrng.gen();
+///
+/// // With only a seed, the same generator as `new`
+/// let mut plain = Qrng::<(f64, f64)>::builder().seed(0.25).build();
+/// assert_eq!(plain.gen(), Qrng::<(f64, f64)>::new(0.25).gen());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct QrngBuilder<T> {
+    seed: f64,
+    burn_in: u64,
+    shift: Option<StreamKey>,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<const N: usize, T: Quasirandom<State = State<N>>> Qrng<T> {
+    /// A builder with the defaults of `new(0.0)`
+    pub fn builder() -> QrngBuilder<T> {
+        QrngBuilder {
+            seed: 0.0,
+            burn_in: 0,
+            shift: None,
+            output: PhantomData,
+        }
+    }
+}
+
+impl<const N: usize, T: Quasirandom<State = State<N>>> QrngBuilder<T> {
+    /// The seed of `Qrng::new`, in `[0, 1)`
+    pub fn seed(mut self, seed: f64) -> Self {
+        assert!((0.0..1.0).contains(&seed));
+        self.seed = seed;
+        self
+    }
+
+    /// Skips the first `count` values, in constant time. `sample_at` counts
+    /// indices from the first value after them.
+    pub fn burn_in(mut self, count: u64) -> Self {
+        self.burn_in = count;
+        self
+    }
+
+    /// Offsets every dimension by the toroidal shift of `key`, as in
+    /// `Qrng::from_key`.
+    pub fn shift(mut self, key: StreamKey) -> Self {
+        self.shift = Some(key);
+        self
+    }
+
+    pub fn build(self) -> Qrng<T> {
+        let seed = to_fixed(self.seed);
+        let start = State::new(std::array::from_fn(|i| {
+            let shift = self.shift.map_or(0, |key| to_fixed(key.offset(i)));
+            seed.wrapping_mul(i as u64).wrapping_add(shift)
+        }));
+        // Jump past the burn-in, and count indices from there
+        let burnt = start.seek(self.burn_in);
+        Qrng { state: State::new(burnt.fixed) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_match_the_plain_constructors() {
+        let mut built = Qrng::<[f64; 3]>::builder().seed(0.6).burn_in(10).build();
+        let mut plain = Qrng::<[f64; 3]>::new(0.6);
+        for _ in 0..10 {
+            plain.gen();
+        }
+        for _ in 0..100 {
+            assert_eq!(built.gen(), plain.gen());
+        }
+
+        let key = StreamKey::path(1, &[2, 3]);
+        let mut built = Qrng::<(f64, u32)>::builder().shift(key).build();
+        let mut keyed = Qrng::<(f64, u32)>::from_key(key);
+        for _ in 0..100 {
+            assert_eq!(built.gen(), keyed.gen());
+        }
+    }
+}
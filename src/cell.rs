@@ -0,0 +1,85 @@
+//! Sharing one generator without `&mut`.
+//!
+//! Callback-heavy single-threaded code (GUI handlers, one closure per
+//! widget) often wants several closures to draw from the same generator,
+//! which `Qrng::gen` and its `&mut self` make awkward. `CellQrng` keeps the
+//! generator behind a `RefCell`, so a shared reference is enough.
+
+use std::cell::RefCell;
+
+use crate::{Qrng, Quasirandom};
+
+/// A `Qrng` that generates through a shared reference.
+///
+/// Values come out in exactly the order a plain `Qrng` would produce them,
+/// interleaved across all the places that share the generator. It is not
+/// `Sync`; share it across threads with a `Mutex<Qrng<T>>` instead.
+///
+/// ```
+/// use quasirandom::cell::CellQrng;
+/// use quasirandom::Qrng;
+///
+/// let qrng = CellQrng::new(Qrng::<(f64, f64)>::new(0.5));
+/// let on_click = || qrng.gen();
+/// let on_hover = || qrng.gen().0;
+/// let (x, y) = on_click();
+/// let x2 = on_hover();
+/// ```
+pub struct CellQrng<T: Quasirandom> {
+    qrng: RefCell<Qrng<T>>,
+}
+
+impl<T: Quasirandom> CellQrng<T> {
+    pub fn new(qrng: Qrng<T>) -> Self {
+        Self {
+            qrng: RefCell::new(qrng),
+        }
+    }
+
+    /// Generates the next value. Panics if called re-entrantly, i.e. from
+    /// within a `FromUniform` implementation that is itself being generated
+    /// by this generator.
+    pub fn gen(&self) -> T {
+        T::generate(&mut self.qrng.borrow_mut().state)
+    }
+
+    /// Swaps in a different generator, returning the old one.
+    pub fn replace(&self, qrng: Qrng<T>) -> Qrng<T> {
+        self.qrng.replace(qrng)
+    }
+
+    pub fn into_inner(self) -> Qrng<T> {
+        self.qrng.into_inner()
+    }
+}
+
+impl<T: Quasirandom> From<Qrng<T>> for CellQrng<T> {
+    fn from(qrng: Qrng<T>) -> Self {
+        Self::new(qrng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn shared_generation_matches_plain() {
+        let shared = Rc::new(CellQrng::new(Qrng::<[f64; 3]>::new(0.25)));
+        let handlers: Vec<Box<dyn Fn() -> [f64; 3]>> = (0..3)
+            .map(|_| {
+                let shared = shared.clone();
+                Box::new(move || shared.gen()) as Box<dyn Fn() -> [f64; 3]>
+            })
+            .collect();
+
+        let mut plain = Qrng::<[f64; 3]>::new(0.25);
+        for i in 0..30 {
+            assert_eq!(handlers[i % 3](), plain.gen());
+        }
+        drop(handlers);
+        let mut inner = Rc::try_unwrap(shared).ok().unwrap().into_inner();
+        assert_eq!(inner.gen(), plain.gen());
+    }
+}
@@ -0,0 +1,291 @@
+//! Choosing elements of slices, permutations, and subsets.
+//!
+//! Every choice made by one call, however many elements it picks, is decoded
+//! from a single value of a one-dimensional `Qrng`, the way an arithmetic
+//! decoder reads symbols. Because that value is spread evenly over `[0, 1)`,
+//! every outcome of a call is reached in its fair share of calls, and soon.
+
+use crate::hash::{combine, mix};
+use crate::{index_from_uniform, Qrng};
+
+/// Decodes a sequence of bounded choices from one uniform value.
+///
+/// A choice among `arity` options takes the top of the remaining fraction and
+/// keeps the rest, so the outcomes of all choices together partition `[0, 1)`
+/// into intervals of their probability. Once the 53 bits of the value can no
+/// longer tell the intervals apart, the remaining choices are pseudorandom.
+pub(crate) struct Decoder {
+    position: u64,
+    /// The number of distinguishable positions left
+    precision: f64,
+    hash: u64,
+    choices: u64,
+}
+
+impl Decoder {
+    pub(crate) fn new(uniform_value: f64) -> Self {
+        Self {
+            position: (uniform_value * 2f64.powi(64)) as u64,
+            precision: 2f64.powi(52),
+            hash: mix(uniform_value.to_bits()),
+            choices: 0,
+        }
+    }
+
+    /// Chooses an index below `arity`
+    pub(crate) fn index(&mut self, arity: usize) -> usize {
+        assert!(arity > 0);
+        if arity == 1 {
+            return 0;
+        }
+        let position = if self.precision >= 256.0 * arity as f64 {
+            self.precision /= arity as f64;
+            let product = self.position as u128 * arity as u128;
+            self.position = product as u64;
+            product
+        } else {
+            self.precision = 0.0;
+            self.choices += 1;
+            mix(combine(self.hash, self.choices)) as u128 * arity as u128
+        };
+        (position >> 64) as usize
+    }
+}
+
+impl Qrng<f64> {
+    /// Chooses an element of `slice`, which must not be empty. Over many
+    /// calls, each element is chosen in almost exactly its share of them.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// let mut qrng = Qrng::<f64>::new(0.0);
+    /// let colors = ["red", "green", "blue"];
+    /// let picks: Vec<&str> = (0..30).map(|_| *qrng.choose(&colors)).collect();
+    /// assert_eq!(picks.iter().filter(|&&color| color == "blue").count(), 10);
+    /// ```
+    pub fn choose<'a, T>(&mut self, slice: &'a [T]) -> &'a T {
+        assert!(!slice.is_empty());
+        &slice[index_from_uniform(self.gen(), slice.len())]
+    }
+
+    /// Chooses `amount` distinct elements of `slice`, in the order chosen.
+    ///
+    /// All choices are decoded from one generated value, so over many calls
+    /// every ordered selection appears in almost exactly its share of them,
+    /// as long as the number of possible selections stays well below `2^52`.
+    /// Choices beyond that precision are pseudorandom.
+    pub fn choose_multiple<'a, T>(&mut self, slice: &'a [T], amount: usize) -> Vec<&'a T> {
+        assert!(amount <= slice.len());
+        let indices = partial_shuffle(self.gen(), slice.len(), amount);
+        indices.into_iter().map(|i| &slice[i]).collect()
+    }
+
+    /// Returns a permutation of `0..n`.
+    ///
+    /// The whole permutation is decoded from one generated value, so over
+    /// many calls the permutations are covered far more evenly than by a
+    /// Fisher-Yates shuffle driven by a pseudorandom generator: for `n` up to
+    /// 13 every permutation appears in almost exactly its share of calls.
+    /// Beyond that, the leading elements are still balanced and the rest are
+    /// pseudorandom.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// // Each of the 6 orderings of three conditions is used 10 times, give
+    /// // or take one
+    /// let mut qrng = Qrng::<f64>::new(0.0);
+    /// let mut counts = std::collections::HashMap::new();
+    /// for _ in 0..60 {
+    ///     *counts.entry(qrng.permutation(3)).or_insert(0) += 1;
+    /// }
+    /// assert_eq!(counts.len(), 6);
+    /// assert!(counts.values().all(|&count| (9..=11).contains(&count)));
+    /// ```
+    pub fn permutation(&mut self, n: usize) -> Vec<usize> {
+        partial_shuffle(self.gen(), n, n)
+    }
+
+    /// Shuffles `slice` in place, decoding the whole shuffle from one
+    /// generated value like `permutation`, so successive shuffles of up to 13
+    /// elements use every ordering in almost exactly its share of calls.
+    /// Element `i` of the result is element `permutation(len)[i]` of the
+    /// original.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// // Counterbalance the order of four stimuli across 48 participants:
+    /// // each of the 24 orders is used twice, give or take one
+    /// let mut qrng = Qrng::<f64>::new(0.0);
+    /// let mut counts = std::collections::HashMap::new();
+    /// for _ in 0..48 {
+    ///     let mut stimuli = ["face", "house", "tool", "word"];
+    ///     qrng.shuffle(&mut stimuli);
+    ///     *counts.entry(stimuli).or_insert(0) += 1;
+    /// }
+    /// assert_eq!(counts.len(), 24);
+    /// assert!(counts.values().all(|&count| (1..=3).contains(&count)));
+    /// ```
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        let mut decoder = Decoder::new(self.gen());
+        let len = slice.len();
+        for i in 0..len {
+            slice.swap(i, i + decoder.index(len - i));
+        }
+    }
+
+    /// Returns `k` distinct indices below `n`, in increasing order.
+    ///
+    /// The subset is the one whose rank, in lexicographic order, the
+    /// generated value selects, so over many calls every subset appears in
+    /// almost exactly its share of calls as long as there are fewer than
+    /// `2^52` subsets. With more, the subset is decoded like `choose_multiple`
+    /// and only balanced in its leading choices.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// // Hold out 2 of 6 folds per round: the 15 possible pairs are used
+    /// // equally often
+    /// let mut qrng = Qrng::<f64>::new(0.0);
+    /// let mut counts = std::collections::HashMap::new();
+    /// for _ in 0..150 {
+    ///     *counts.entry(qrng.sample_indices(6, 2)).or_insert(0) += 1;
+    /// }
+    /// assert_eq!(counts.len(), 15);
+    /// assert!(counts.values().all(|&count| (9..=11).contains(&count)));
+    /// ```
+    pub fn sample_indices(&mut self, n: usize, k: usize) -> Vec<usize> {
+        assert!(k <= n);
+        let uniform_value = self.gen();
+        let Some(subsets) = binomial(n, k) else {
+            let mut indices = partial_shuffle(uniform_value, n, k);
+            indices.sort_unstable();
+            return indices;
+        };
+
+        // Unrank: skip past the subsets starting with each smaller index
+        let mut rank = index_from_uniform(uniform_value, subsets as usize) as u64;
+        let mut indices = Vec::with_capacity(k);
+        for i in 0..n {
+            if indices.len() == k {
+                break;
+            }
+            let remaining = k - indices.len();
+            let starting_here = binomial(n - i - 1, remaining - 1).unwrap();
+            if rank < starting_here {
+                indices.push(i);
+            } else {
+                rank -= starting_here;
+            }
+        }
+        indices
+    }
+}
+
+/// The binomial coefficient `n` choose `k`, if it is below `2^52`
+fn binomial(n: usize, k: usize) -> Option<u64> {
+    let k = k.min(n - k) as u128;
+    let mut result = 1u128;
+    for i in 0..k {
+        // Exact at every step, since the product of i + 1 consecutive integers
+        // is divisible by (i + 1)!
+        result = result * (n as u128 - i) / (i + 1);
+        if result >= 1 << 52 {
+            return None;
+        }
+    }
+    Some(result as u64)
+}
+
+/// The first `amount` entries of a Fisher-Yates shuffle of `0..len`, decoded
+/// from `uniform_value`
+fn partial_shuffle(uniform_value: f64, len: usize, amount: usize) -> Vec<usize> {
+    let mut decoder = Decoder::new(uniform_value);
+    let mut indices: Vec<usize> = (0..len).collect();
+    for i in 0..amount {
+        let j = i + decoder.index(len - i);
+        indices.swap(i, j);
+    }
+    indices.truncate(amount);
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn selections_are_balanced() {
+        let mut qrng = Qrng::<f64>::new(0.5);
+        let mut counts = [0; 5];
+        for _ in 0..1000 {
+            counts[*qrng.choose(&[0, 1, 2, 3, 4])] += 1;
+        }
+        assert!(counts.iter().all(|&count| (count - 200_i32).abs() <= 1), "{:?}", counts);
+
+        // There are 5 * 4 = 20 ordered pairs, each chosen 100 times out of
+        // 2000, give or take one
+        let mut pairs = HashMap::new();
+        for _ in 0..2000 {
+            let pair = qrng.choose_multiple(&[0, 1, 2, 3, 4], 2);
+            assert_ne!(pair[0], pair[1]);
+            *pairs.entry((*pair[0], *pair[1])).or_insert(0) += 1;
+        }
+        assert_eq!(pairs.len(), 20);
+        assert!(pairs.values().all(|&count| (count - 100_i32).abs() <= 2), "{:?}", pairs);
+
+        // Selections larger than the precision are still distinct
+        let many: Vec<usize> = (0..100).collect();
+        let mut chosen = qrng.choose_multiple(&many, 100);
+        chosen.sort();
+        assert!(chosen.iter().enumerate().all(|(i, &&x)| i == x));
+    }
+
+    #[test]
+    fn permutations_are_balanced() {
+        let mut qrng = Qrng::<f64>::new(0.25);
+        let mut counts = HashMap::new();
+        for _ in 0..2400 {
+            *counts.entry(qrng.permutation(4)).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 24);
+        assert!(counts.values().all(|&count| (count - 100_i32).abs() <= 2), "{:?}", counts);
+
+        // Shuffling applies the permutation the same value would select
+        let mut shuffled = qrng.clone();
+        let permutation = qrng.permutation(6);
+        let mut letters = ['a', 'b', 'c', 'd', 'e', 'f'];
+        shuffled.shuffle(&mut letters);
+        assert!(permutation.iter().zip(letters).all(|(&i, letter)| letter == (b'a' + i as u8) as char));
+
+        let mut long = qrng.permutation(1000);
+        long.sort();
+        assert_eq!(long, (0..1000).collect::<Vec<_>>());
+        assert!(qrng.permutation(0).is_empty());
+    }
+
+    #[test]
+    fn subsets_are_balanced() {
+        // 10 choose 3 = 120 subsets, each drawn 10 times out of 1200
+        let mut qrng = Qrng::<f64>::new(0.75);
+        let mut counts = HashMap::new();
+        for _ in 0..1200 {
+            let subset = qrng.sample_indices(10, 3);
+            assert!(subset.windows(2).all(|pair| pair[0] < pair[1]));
+            *counts.entry(subset).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 120);
+        assert!(counts.values().all(|&count| (count - 10_i32).abs() <= 1), "{:?}", counts);
+
+        assert_eq!(binomial(60, 30), None);
+        let large = qrng.sample_indices(1000, 500);
+        assert_eq!(large.len(), 500);
+        assert!(large.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(qrng.sample_indices(5, 5), [0, 1, 2, 3, 4]);
+        assert!(qrng.sample_indices(5, 0).is_empty());
+    }
+}
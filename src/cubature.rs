@@ -0,0 +1,194 @@
+//! Fixed node sets for integrating functions on the unit sphere.
+//!
+//! A sequence mapped onto the sphere with `warp::to_unit_sphere` can always
+//! be extended, but for a known budget a cubature rule does much better: a
+//! rule of degree `t` integrates every polynomial of degree up to `t` in the
+//! coordinates exactly, so smooth integrands such as BRDF lobes and radiation
+//! patterns converge very quickly with the degree.
+
+use std::f64::consts::PI;
+
+use crate::reduce::CompensatedSum;
+
+/// A cubature rule on the unit sphere: nodes with weights summing to one.
+///
+/// ```
+/// use quasirandom::cubature::SphereRule;
+///
+/// // The integral of z^2 over the sphere is 4 pi / 3
+/// let rule = SphereRule::lebedev(5);
+/// assert_eq!(rule.len(), 14);
+/// let integral = rule.integrate(|[_, _, z]| z * z);
+/// assert!((integral - 4.0 * std::f64::consts::PI / 3.0).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SphereRule {
+    nodes: Vec<[f64; 3]>,
+    weights: Vec<f64>,
+    degree: u32,
+}
+
+impl SphereRule {
+    /// The smallest built-in spherical `strength`-design: a set of points
+    /// with equal weights that integrates polynomials of degree up to
+    /// `strength` exactly. These are the vertices of the tetrahedron (up to
+    /// strength 2), the octahedron (3), and the icosahedron (5), so
+    /// `strength` must be at most 5; `lebedev` goes further with unequal
+    /// weights.
+    pub fn t_design(strength: u32) -> Self {
+        assert!(strength <= 5, "spherical designs are only built in up to strength 5");
+        let golden = (1.0 + 5f64.sqrt()) / 2.0;
+        let (nodes, degree) = match strength {
+            0..=2 => {
+                let nodes = vec![[1.0, 1.0, 1.0], [1.0, -1.0, -1.0], [-1.0, 1.0, -1.0], [-1.0, -1.0, 1.0]];
+                (nodes, 2)
+            }
+            3 => (orbit([1.0, 0.0, 0.0]), 3),
+            _ => (orbit_even([0.0, 1.0, golden]), 5),
+        };
+        let nodes: Vec<[f64; 3]> = nodes.into_iter().map(normalize).collect();
+        let weights = vec![1.0 / nodes.len() as f64; nodes.len()];
+        Self { nodes, weights, degree }
+    }
+
+    /// The smallest built-in Lebedev rule of at least the given degree, which
+    /// must be at most 11. Lebedev rules are invariant under the symmetries
+    /// of the octahedron and use close to the fewest points possible: 6, 14,
+    /// 26, 38, and 50 points for degrees 3, 5, 7, 9, and 11.
+    pub fn lebedev(degree: u32) -> Self {
+        assert!(degree <= 11, "Lebedev rules are only built in up to degree 11");
+        let axes = [1.0, 0.0, 0.0];
+        let edges = [0.0, 1.0, 1.0];
+        let corners = [1.0, 1.0, 1.0];
+        let (orbits, degree): (Vec<([f64; 3], f64)>, u32) = match degree {
+            0..=3 => (vec![(axes, 1.0 / 6.0)], 3),
+            4..=5 => (vec![(axes, 1.0 / 15.0), (corners, 3.0 / 40.0)], 5),
+            6..=7 => (vec![(axes, 1.0 / 21.0), (edges, 4.0 / 105.0), (corners, 9.0 / 280.0)], 7),
+            8..=9 => {
+                // p^2 = (3 - sqrt(3)) / 6 and q^2 = (3 + sqrt(3)) / 6
+                let p = ((3.0 - 3f64.sqrt()) / 6.0).sqrt();
+                let q = ((3.0 + 3f64.sqrt()) / 6.0).sqrt();
+                (vec![(axes, 1.0 / 105.0), (corners, 9.0 / 280.0), ([p, q, 0.0], 1.0 / 35.0)], 9)
+            }
+            _ => (
+                vec![
+                    (axes, 4.0 / 315.0),
+                    (edges, 64.0 / 2835.0),
+                    (corners, 27.0 / 1280.0),
+                    ([1.0, 1.0, 3.0], 14641.0 / 725760.0),
+                ],
+                11,
+            ),
+        };
+        let mut nodes = vec![];
+        let mut weights = vec![];
+        for (generator, weight) in orbits {
+            for node in orbit(generator) {
+                nodes.push(normalize(node));
+                weights.push(weight);
+            }
+        }
+        Self { nodes, weights, degree }
+    }
+
+    /// The number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The highest degree of polynomial the rule integrates exactly
+    pub fn degree(&self) -> u32 {
+        self.degree
+    }
+
+    /// The nodes, as unit vectors
+    pub fn nodes(&self) -> &[[f64; 3]] {
+        &self.nodes
+    }
+
+    /// The weight of each node, summing to one
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// Approximates the integral of `f` over the sphere (with respect to
+    /// solid angle, so a constant one integrates to `4 pi`)
+    pub fn integrate(&self, mut f: impl FnMut([f64; 3]) -> f64) -> f64 {
+        let terms = self.nodes.iter().zip(&self.weights).map(|(&node, weight)| weight * f(node));
+        let mean = terms.sum::<CompensatedSum>().sum();
+        4.0 * PI * mean
+    }
+}
+
+/// Every distinct point obtained by permuting the coordinates of `point` and
+/// flipping their signs
+fn orbit(point: [f64; 3]) -> Vec<[f64; 3]> {
+    let permutations = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+    collect_signed(permutations.iter().map(|p| p.map(|i| point[i])))
+}
+
+/// Like `orbit`, but only with the cyclic permutations of the coordinates
+fn orbit_even(point: [f64; 3]) -> Vec<[f64; 3]> {
+    let permutations = [[0, 1, 2], [1, 2, 0], [2, 0, 1]];
+    collect_signed(permutations.iter().map(|p| p.map(|i| point[i])))
+}
+
+fn collect_signed(points: impl Iterator<Item = [f64; 3]>) -> Vec<[f64; 3]> {
+    let mut orbit: Vec<[f64; 3]> = vec![];
+    for point in points {
+        for signs in 0..8 {
+            let signed: [f64; 3] = std::array::from_fn(|i| if signs >> i & 1 == 1 { -point[i] } else { point[i] });
+            if !orbit.contains(&signed) {
+                orbit.push(signed);
+            }
+        }
+    }
+    orbit
+}
+
+fn normalize(point: [f64; 3]) -> [f64; 3] {
+    let length = point.iter().map(|x| x * x).sum::<f64>().sqrt();
+    point.map(|x| x / length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The mean of `x^a y^b z^c` over the sphere: zero if any exponent is
+    /// odd, and otherwise `(a-1)!! (b-1)!! (c-1)!! / (a+b+c+1)!!`
+    fn monomial_mean([a, b, c]: [i32; 3]) -> f64 {
+        if a % 2 == 1 || b % 2 == 1 || c % 2 == 1 {
+            return 0.0;
+        }
+        let double_factorial = |n: i32| (1..=n).rev().step_by(2).map(f64::from).product::<f64>();
+        double_factorial(a - 1) * double_factorial(b - 1) * double_factorial(c - 1) / double_factorial(a + b + c + 1)
+    }
+
+    #[test]
+    fn rules_are_exact_up_to_their_degree() {
+        let rules = (0..=5)
+            .map(SphereRule::t_design)
+            .chain((0..=11).map(SphereRule::lebedev));
+        for rule in rules {
+            assert!((rule.weights().iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            let degree = rule.degree() as i32;
+            for a in 0..=degree {
+                for b in 0..=degree - a {
+                    for c in 0..=degree - a - b {
+                        let integral = rule.integrate(|[x, y, z]| x.powi(a) * y.powi(b) * z.powi(c));
+                        let exact = 4.0 * PI * monomial_mean([a, b, c]);
+                        assert!((integral - exact).abs() < 1e-12, "{:?} {:?}", rule, [a, b, c]);
+                    }
+                }
+            }
+        }
+        let sizes: Vec<usize> = [3, 5, 7, 9, 11].map(|degree| SphereRule::lebedev(degree).len()).to_vec();
+        assert_eq!(sizes, [6, 14, 26, 38, 50]);
+        assert_eq!(SphereRule::t_design(5).len(), 12);
+    }
+}
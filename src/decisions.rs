@@ -0,0 +1,155 @@
+//! Streams of discrete decisions that explore a decision tree evenly.
+//!
+//! Randomized search algorithms (backtracking solvers, fuzzers, schedulers)
+//! make a sequence of bounded choices on each run, and the number of options
+//! at each choice may depend on the earlier ones. `Explorer` gives every run
+//! a `DecisionStream` whose choices are decoded from one point of a
+//! low-discrepancy sequence, the way an arithmetic decoder reads symbols, so
+//! successive runs spread over the tree of decisions systematically rather
+//! than at random.
+
+use crate::hash::{combine, mix};
+
+/// Produces one `DecisionStream` per run.
+///
+/// A stream's first `depth` real decisions (those with two or more options)
+/// are read from the run's point in the van der Corput sequence, digitally
+/// shifted by the seed. The decisions made so far pin down an interval of
+/// `[0, 1)` whose length is the probability of that prefix, and the sequence
+/// puts a point in every such interval early: any prefix of probability `p`
+/// within the first `depth` decisions is reached within the first `4 / p`
+/// runs (`1 / p` if every arity is a power of two), whatever the arities.
+/// Decisions beyond `depth` are pseudorandom. Since the point has 64 bits,
+/// the guarantee only holds while the product of the arities stays below
+/// `2^64`.
+///
+/// ```
+/// use quasirandom::decisions::Explorer;
+///
+/// // Randomized backtracking over three variables with domains of 3, 2, and
+/// // 4 values: each of the 24 assignments has probability 1/24, so the first
+/// // 96 runs start from every one of them
+/// let mut explorer = Explorer::new(32, 1);
+/// let mut starts = std::collections::HashSet::new();
+/// for _ in 0..96 {
+///     let mut stream = explorer.next_stream();
+///     starts.insert([stream.decide(3), stream.decide(2), stream.decide(4)]);
+/// }
+/// assert_eq!(starts.len(), 24);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explorer {
+    depth: u32,
+    seed: u64,
+    runs: u64,
+}
+
+/// The decisions of a single run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecisionStream {
+    /// The unread fraction of the run's point, as a 64-bit fixed-point number
+    position: u64,
+    depth: u32,
+    decisions: u32,
+    hash: u64,
+}
+
+impl Explorer {
+    pub fn new(depth: u32, seed: u64) -> Self {
+        Self {
+            depth,
+            seed,
+            runs: 0,
+        }
+    }
+
+    /// The number of streams produced so far
+    pub fn runs(&self) -> u64 {
+        self.runs
+    }
+
+    pub fn next_stream(&mut self) -> DecisionStream {
+        let stream = self.stream(self.runs);
+        self.runs += 1;
+        stream
+    }
+
+    /// The stream of run `run`, for replaying a run without the ones before
+    /// it
+    pub fn stream(&self, run: u64) -> DecisionStream {
+        DecisionStream {
+            position: run.reverse_bits() ^ mix(self.seed),
+            depth: self.depth,
+            decisions: 0,
+            hash: combine(self.seed, run),
+        }
+    }
+}
+
+impl DecisionStream {
+    /// Chooses one of `arity` options, returning an index below `arity`.
+    /// A decision with a single option consumes nothing.
+    pub fn decide(&mut self, arity: usize) -> usize {
+        assert!(arity > 0);
+        if arity == 1 {
+            return 0;
+        }
+        let product = if self.decisions < self.depth {
+            // Read the decision off the top of the position, keeping the rest
+            let product = self.position as u128 * arity as u128;
+            self.position = product as u64;
+            product
+        } else {
+            mix(combine(self.hash, self.decisions as u64)) as u128 * arity as u128
+        };
+        self.decisions += 1;
+        (product >> 64) as usize
+    }
+
+    pub fn decide_bool(&mut self) -> bool {
+        self.decide(2) == 1
+    }
+
+    /// Decides the order in which to try `arity` options when backtracking:
+    /// the chosen option first, then the others in cyclic order. Only the
+    /// first option is spread evenly across runs.
+    pub fn order(&mut self, arity: usize) -> impl Iterator<Item = usize> {
+        let first = self.decide(arity);
+        (0..arity).map(move |i| (first + i) % arity)
+    }
+
+    /// The number of real decisions made so far, not counting decisions with
+    /// a single option
+    pub fn decisions(&self) -> u32 {
+        self.decisions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_are_covered() {
+        // Arities depend on earlier decisions: a tree of 21 leaves with
+        // probabilities between 1/60 and 1/6, all reached within 240 runs
+        let explorer = Explorer::new(8, 5);
+        let leaf = |run| {
+            let mut stream = explorer.stream(run);
+            let first = stream.decide(3);
+            let second = stream.decide(first + 2);
+            let third = if second == 0 { stream.decide(5) } else { 0 };
+            (first, second, third)
+        };
+        let mut seen = std::collections::HashSet::new();
+        for run in 0..240 {
+            seen.insert(leaf(run));
+        }
+        assert_eq!(seen.len(), 5 + 1 + 5 + 2 + 5 + 3);
+
+        let mut stream = explorer.stream(3);
+        let order: Vec<usize> = stream.order(4).collect();
+        assert_eq!(order.len(), 4);
+        assert!((0..4).all(|i| order.contains(&i)));
+    }
+}
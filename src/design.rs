@@ -0,0 +1,150 @@
+//! Experimental designs: sets of parameter vectors to evaluate.
+
+use std::ops::Range;
+
+use crate::sequences::{RSequence, UniformSequence};
+use crate::warp::simplex_barycentric;
+
+/// Returns `n` parameter vectors spread evenly over the box with the given
+/// side ranges, one range per parameter.
+///
+/// Unlike a grid, the design works for any `n` and every parameter takes `n`
+/// distinct values. Its points are the first `n` of a low-discrepancy
+/// sequence, so every prefix is itself evenly spread: a sweep cut short
+/// still covers the whole box, just more coarsely, and extending a sweep to
+/// a larger `n` reuses every point already evaluated.
+///
+/// ```
+/// use quasirandom::design::sweep;
+///
+/// let design = sweep(&[0.001..0.1, 16.0..256.0], 20);
+/// assert_eq!(design.len(), 20);
+/// for point in &design {
+///     let (learning_rate, width) = (point[0], point[1]);
+///     assert!((0.001..0.1).contains(&learning_rate) && (16.0..256.0).contains(&width));
+/// }
+/// assert_eq!(sweep(&[0.001..0.1, 16.0..256.0], 10), design[..10]);
+/// ```
+pub fn sweep(ranges: &[Range<f64>], n: usize) -> Vec<Vec<f64>> {
+    assert!(!ranges.is_empty());
+    assert!(ranges.iter().all(|range| range.start <= range.end));
+    let mut sequence = RSequence::new(ranges.len(), 0.0);
+    let mut point = vec![0.0; ranges.len()];
+    (0..n)
+        .map(|_| {
+            sequence.next_into(&mut point);
+            point
+                .iter()
+                .zip(ranges)
+                .map(|(x, range)| range.start + x * (range.end - range.start))
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns `n` weight vectors for `objectives` objectives, spread evenly over
+/// the simplex of nonnegative weights summing to one, for scalarization-based
+/// multi-objective optimization such as MOEA/D.
+///
+/// The first `objectives` vectors (or all of them, for smaller `n`) are the
+/// unit vectors, which weight one objective alone and pin down the ends of
+/// the front. The rest are a low-discrepancy sequence mapped uniformly onto
+/// the simplex, so, unlike the simplex-lattice design, any `n` works, and
+/// every prefix covers the simplex evenly.
+///
+/// ```
+/// use quasirandom::design::{simplex_weights, weight_neighbors};
+///
+/// let weights = simplex_weights(3, 100);
+/// assert_eq!(weights[1], [0.0, 1.0, 0.0]);
+/// assert!(weights.iter().all(|w| (w.iter().sum::<f64>() - 1.0).abs() < 1e-12));
+///
+/// // Each subproblem mates with the subproblems of its 10 closest weights
+/// let neighbors = weight_neighbors(&weights, 10);
+/// assert_eq!(neighbors[42][0], 42);
+/// ```
+pub fn simplex_weights(objectives: usize, n: usize) -> Vec<Vec<f64>> {
+    assert!(objectives > 0);
+    let mut weights: Vec<Vec<f64>> = (0..objectives.min(n))
+        .map(|i| (0..objectives).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+    if objectives == 1 {
+        weights.resize(n, vec![1.0]);
+        return weights;
+    }
+    let mut sequence = RSequence::new(objectives - 1, 0.0);
+    let mut point = vec![0.0; objectives - 1];
+    while weights.len() < n {
+        sequence.next_into(&mut point);
+        let mut weight = vec![0.0; objectives];
+        simplex_barycentric(&point, &mut weight);
+        weights.push(weight);
+    }
+    weights
+}
+
+/// For each weight vector, the indices of the `t` closest weight vectors by
+/// Euclidean distance, itself first, as used to define the neighborhoods of
+/// MOEA/D.
+pub fn weight_neighbors(weights: &[Vec<f64>], t: usize) -> Vec<Vec<usize>> {
+    assert!(t <= weights.len());
+    let distance = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum::<f64>();
+    (0..weights.len())
+        .map(|i| {
+            let mut order: Vec<usize> = (0..weights.len()).collect();
+            // Ties are broken by index, but the vector itself always comes first
+            order.sort_by(|&a, &b| {
+                let key = |j: usize| (j != i, distance(&weights[i], &weights[j]));
+                let (x, y) = (key(a), key(b));
+                x.0.cmp(&y.0).then(x.1.total_cmp(&y.1)).then(a.cmp(&b))
+            });
+            order.truncate(t);
+            order
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_cover_the_box() {
+        let design = sweep(&[-1.0..1.0, 0.0..10.0], 400);
+        for n in [36, 144, 400] {
+            // Every cell of a k x k grid with k^2 = n / 4 holds a few points
+            let k = ((n / 4) as f64).sqrt() as usize;
+            let mut cells = vec![0; k * k];
+            for point in &design[..n] {
+                let i = ((point[0] + 1.0) / 2.0 * k as f64) as usize;
+                let j = (point[1] / 10.0 * k as f64) as usize;
+                cells[i * k + j] += 1;
+            }
+            assert!(cells.iter().all(|&count| (2..=6).contains(&count)), "{} {:?}", n, cells);
+        }
+    }
+
+    #[test]
+    fn weights_cover_the_simplex() {
+        let weights = simplex_weights(3, 603);
+        assert_eq!(weights[..3], [vec![1.0, 0.0, 0.0], vec![0.0, 1.0, 0.0], vec![0.0, 0.0, 1.0]]);
+        // Split the triangle into the 4 congruent triangles through the edge
+        // midpoints: each gets a quarter of the 600 interior weights
+        let mut parts = [0; 4];
+        for w in &weights[3..] {
+            assert!(w.iter().all(|&x| x >= 0.0) && (w.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            let part = w.iter().position(|&x| x > 0.5).unwrap_or(3);
+            parts[part] += 1;
+        }
+        assert!(parts.iter().all(|&count| (count - 150_i32).abs() <= 6), "{:?}", parts);
+
+        assert_eq!(simplex_weights(2, 1), [vec![1.0, 0.0]]);
+        assert_eq!(simplex_weights(1, 2), [vec![1.0], vec![1.0]]);
+
+        let neighbors = weight_neighbors(&weights[..50], 5);
+        for (i, list) in neighbors.iter().enumerate() {
+            assert_eq!(list.len(), 5);
+            assert_eq!(list[0], i);
+        }
+    }
+}
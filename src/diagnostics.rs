@@ -0,0 +1,355 @@
+//! Checking whether an integrand suits quasi-Monte Carlo.
+//!
+//! Quasirandom points beat random ones when most of an integrand's variance
+//! comes from a few coordinates, and those coordinates are the ones the
+//! sequence distributes best, usually the first. `EffectiveDimension`
+//! estimates where the variance lies from a pilot run, so problems can be
+//! reordered before the real one. `ErrorBound` turns the point set of a
+//! finished run into a guaranteed bound on its integration error.
+
+use std::fmt;
+
+use crate::reduce::CompensatedSum;
+use crate::sequences::{RSequence, UniformSequence};
+
+/// Total importance below which a coordinate is not worth warning about
+const IMPORTANT: f64 = 0.01;
+
+/// An estimate of how an integrand's variance is spread over its
+/// coordinates, from a variance (ANOVA) decomposition.
+///
+/// ```
+/// use quasirandom::diagnostics::EffectiveDimension;
+///
+/// // Nearly all the variance is in the last of five coordinates
+/// let estimate = EffectiveDimension::estimate(5, 4096, |x| x[0] + 10.0 * x[4]);
+/// assert_eq!(estimate.truncation_dimension(0.99), 5);
+/// assert_eq!(estimate.suggested_order()[0], 4);
+/// for warning in estimate.warnings(2) {
+///     println!("{}", warning);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectiveDimension {
+    /// The mean of the integrand over the pilot samples
+    pub mean: f64,
+    /// The variance of the integrand
+    pub variance: f64,
+    /// The total Sobol' index of each coordinate: the fraction of the variance
+    /// that involves it, alone or together with others
+    pub total_indices: Vec<f64>,
+    /// `leading_indices[s]` is the fraction of the variance explained by the
+    /// first `s + 1` coordinates alone
+    pub leading_indices: Vec<f64>,
+}
+
+/// A sign that an integrand's variance lies where a quasirandom sequence
+/// distributes points poorly
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Warning {
+    /// A coordinate outside the well-distributed leading ones carries a
+    /// significant share of the variance
+    ImportantTrailingCoordinate { coordinate: usize, total_index: f64 },
+    /// Explaining 99% of the variance takes more leading coordinates than are
+    /// well distributed
+    TruncationDimension { needed: usize, well_distributed: usize },
+}
+
+impl EffectiveDimension {
+    /// Estimates the decomposition of `integrand` on `[0, 1)^dimension` from
+    /// `samples` pilot points, evaluating it `samples * (2 * dimension + 2)`
+    /// times.
+    pub fn estimate(dimension: usize, samples: usize, mut integrand: impl FnMut(&[f64]) -> f64) -> Self {
+        assert!(dimension > 0);
+        assert!(samples > 1);
+        // Two independent points per sample, from one sequence of twice the
+        // dimension
+        let mut sequence = RSequence::new(2 * dimension, 0.5);
+        let mut both = vec![0.0; 2 * dimension];
+        let mut mixed = vec![0.0; dimension];
+
+        let (mut sum, mut sum_squares) = (CompensatedSum::new(), CompensatedSum::new());
+        let mut total = vec![CompensatedSum::new(); dimension];
+        let mut complement = vec![CompensatedSum::new(); dimension];
+        for _ in 0..samples {
+            sequence.next_into(&mut both);
+            let (a, b) = both.split_at(dimension);
+            let fa = integrand(a);
+            let fb = integrand(b);
+            sum += fa;
+            sum += fb;
+            sum_squares += fa * fa;
+            sum_squares += fb * fb;
+
+            // Jansen's estimators: changing coordinate j alone, then changing
+            // every coordinate after the first s + 1
+            for j in 0..dimension {
+                mixed.copy_from_slice(a);
+                mixed[j] = b[j];
+                total[j] += (fa - integrand(&mixed)).powi(2);
+
+                mixed.copy_from_slice(a);
+                mixed[j + 1..].copy_from_slice(&b[j + 1..]);
+                complement[j] += (fa - integrand(&mixed)).powi(2);
+            }
+        }
+
+        let n = 2.0 * samples as f64;
+        let mean = sum.sum() / n;
+        let variance = (sum_squares.sum() / n - mean * mean).max(0.0);
+        let normalize = |x: f64| {
+            if variance > 0.0 {
+                (x / (2.0 * samples as f64 * variance)).clamp(0.0, 1.0)
+            } else {
+                0.0
+            }
+        };
+        Self {
+            mean,
+            variance,
+            total_indices: total.iter().map(|x| normalize(x.sum())).collect(),
+            leading_indices: complement.iter().map(|x| 1.0 - normalize(x.sum())).collect(),
+        }
+    }
+
+    /// The smallest number of leading coordinates that together explain at
+    /// least `fraction` of the variance
+    pub fn truncation_dimension(&self, fraction: f64) -> usize {
+        assert!((0.0..=1.0).contains(&fraction));
+        self.leading_indices
+            .iter()
+            .position(|&index| index >= fraction)
+            .map_or(self.leading_indices.len(), |s| s + 1)
+    }
+
+    /// The coordinates ordered from most to least important, which is the
+    /// order to assign them to a sequence's coordinates
+    pub fn suggested_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.total_indices.len()).collect();
+        order.sort_by(|&i, &j| self.total_indices[j].total_cmp(&self.total_indices[i]));
+        order
+    }
+
+    /// Lists the ways the variance falls outside the first
+    /// `well_distributed` coordinates. A coordinate counts as important if it
+    /// is involved in at least 1% of the variance.
+    pub fn warnings(&self, well_distributed: usize) -> Vec<Warning> {
+        let mut warnings: Vec<Warning> = self
+            .total_indices
+            .iter()
+            .enumerate()
+            .skip(well_distributed)
+            .filter(|(_, &total_index)| total_index >= IMPORTANT)
+            .map(|(coordinate, &total_index)| Warning::ImportantTrailingCoordinate {
+                coordinate,
+                total_index,
+            })
+            .collect();
+        let needed = self.truncation_dimension(1.0 - IMPORTANT);
+        if needed > well_distributed {
+            warnings.push(Warning::TruncationDimension {
+                needed,
+                well_distributed,
+            });
+        }
+        warnings
+    }
+}
+
+/// A deterministic bound on the error of an equal-weight quadrature, from the
+/// Koksma-Hlawka inequality: `|mean - integral| <= variation * discrepancy`,
+/// where `variation` is the integrand's variation in the sense of Hardy and
+/// Krause and `discrepancy` is the star discrepancy of the points.
+///
+/// The discrepancy is not computed exactly, which is infeasible beyond a few
+/// dimensions, but bounded from above on a grid, so the bound stays rigorous
+/// as long as the variation supplied is an upper bound too. It is usually
+/// very pessimistic compared to the actual error.
+///
+/// ```
+/// use quasirandom::diagnostics::ErrorBound;
+/// use quasirandom::Qrng;
+///
+/// // f(x, y) = x * y has Hardy-Krause variation 3
+/// let mut qrng = Qrng::<[f64; 2]>::new(0.0);
+/// let points: Vec<[f64; 2]> = (0..1000).map(|_| qrng.gen()).collect();
+/// let mean = points.iter().map(|[x, y]| x * y).sum::<f64>() / 1000.0;
+/// let bound = ErrorBound::new(&points, 3.0, 64);
+/// assert!((mean - 0.25).abs() <= bound.bound);
+/// println!("{}", bound);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorBound {
+    /// The number of points
+    pub samples: usize,
+    /// An upper bound on the star discrepancy of the points
+    pub discrepancy: f64,
+    /// The variation of the integrand, as supplied
+    pub variation: f64,
+    /// The bound on the integration error, `variation * discrepancy`
+    pub bound: f64,
+}
+
+impl ErrorBound {
+    /// Bounds the error of averaging an integrand with Hardy-Krause variation
+    /// at most `variation` over `points`, computing the discrepancy on a grid
+    /// with `resolution` divisions per axis (see `star_discrepancy_bound`).
+    pub fn new<P: AsRef<[f64]>>(points: &[P], variation: f64, resolution: usize) -> Self {
+        assert!(variation >= 0.0);
+        let discrepancy = star_discrepancy_bound(points, resolution);
+        Self {
+            samples: points.len(),
+            discrepancy,
+            variation,
+            bound: variation * discrepancy,
+        }
+    }
+}
+
+/// An upper bound on the star discrepancy of `points` in `[0, 1)^d`: the
+/// largest difference, over boxes `[0, x)`, between the fraction of points in
+/// the box and its volume.
+///
+/// Every box lies between two corners of a grid with `resolution` divisions
+/// per axis, which bounds its count and volume, so the result exceeds the
+/// true discrepancy by at most about `d / resolution`. It takes
+/// `O(n + d * (resolution + 1)^d)` time and memory.
+pub fn star_discrepancy_bound<P: AsRef<[f64]>>(points: &[P], resolution: usize) -> f64 {
+    assert!(!points.is_empty());
+    assert!(resolution > 0);
+    let dimension = points[0].as_ref().len();
+    assert!(dimension > 0);
+    let side = resolution + 1;
+    let corners = (0..dimension).fold(1usize, |corners, _| {
+        corners.checked_mul(side).expect("grid is too large")
+    });
+
+    // counts[g] is the number of points below the grid corner g / resolution,
+    // first binned by cell and then summed along each axis
+    let mut counts = vec![0u64; corners];
+    for point in points {
+        let point = point.as_ref();
+        assert_eq!(point.len(), dimension);
+        let corner = point.iter().rev().fold(0, |corner, &x| {
+            assert!((0.0..1.0).contains(&x));
+            let cell = ((x * resolution as f64) as usize).min(resolution - 1);
+            corner * side + cell + 1
+        });
+        counts[corner] += 1;
+    }
+    let mut stride = 1;
+    for _ in 0..dimension {
+        for corner in 0..corners {
+            if corner / stride % side > 0 {
+                counts[corner] += counts[corner - stride];
+            }
+        }
+        stride *= side;
+    }
+
+    // A box whose corner lies in the cell with upper corner b and lower corner
+    // a holds at most count(b) and at least count(a) points
+    let n = points.len() as f64;
+    let volume = |mut corner: usize| {
+        (0..dimension).fold(1.0, |volume, _| {
+            let coordinate = corner % side;
+            corner /= side;
+            volume * coordinate as f64 / resolution as f64
+        })
+    };
+    let lower = (0..dimension).fold(0, |lower, _| lower * side + 1);
+    let mut discrepancy = 0.0f64;
+    for upper in 0..corners {
+        if (0..dimension).any(|axis| (upper / side.pow(axis as u32)).is_multiple_of(side)) {
+            continue;
+        }
+        let lower = upper - lower;
+        let excess = counts[upper] as f64 / n - volume(lower);
+        let deficit = volume(upper) - counts[lower] as f64 / n;
+        discrepancy = discrepancy.max(excess).max(deficit);
+    }
+    discrepancy.min(1.0)
+}
+
+impl fmt::Display for ErrorBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "integration error at most {:.3e} (Koksma-Hlawka: variation {:.3e} times star discrepancy at most {:.3e} of {} points)",
+            self.bound, self.variation, self.discrepancy, self.samples
+        )
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Warning::ImportantTrailingCoordinate {
+                coordinate,
+                total_index,
+            } => write!(
+                f,
+                "coordinate {} is involved in {:.1}% of the variance but is not among the well-distributed ones",
+                coordinate,
+                100.0 * total_index
+            ),
+            Warning::TruncationDimension {
+                needed,
+                well_distributed,
+            } => write!(
+                f,
+                "explaining 99% of the variance takes {} leading coordinates, but only {} are well distributed",
+                needed, well_distributed
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn additive_integrand() {
+        // Variances 1, 4, 0, 0 (times 1/12) for the four coordinates
+        let estimate = EffectiveDimension::estimate(4, 4096, |x| x[0] + 2.0 * x[1]);
+        assert!((estimate.mean - 1.5).abs() < 1e-3);
+        assert!((estimate.variance - 5.0 / 12.0).abs() < 1e-2);
+        for (index, expected) in estimate.total_indices.iter().zip([0.2, 0.8, 0.0, 0.0]) {
+            assert!((index - expected).abs() < 0.02, "{:?}", estimate);
+        }
+        assert_eq!(estimate.truncation_dimension(0.99), 2);
+        assert_eq!(estimate.suggested_order()[..2], [1, 0]);
+        assert!(estimate.warnings(2).is_empty());
+        assert_eq!(estimate.warnings(1).len(), 2);
+    }
+
+    #[test]
+    fn discrepancy_bounds() {
+        // The centered one-dimensional grid has star discrepancy 1 / (2n)
+        let points: Vec<[f64; 1]> = (0..100).map(|i| [(i as f64 + 0.5) / 100.0]).collect();
+        for resolution in [10, 100, 1000] {
+            let bound = star_discrepancy_bound(&points, resolution);
+            assert!((0.005..=0.005 + 1.0 / resolution as f64 + 1e-12).contains(&bound), "{}", bound);
+        }
+
+        // All points in a corner: the box [0, 1/2)^3 holds every point
+        let points = vec![vec![0.1, 0.2, 0.3]; 10];
+        let bound = star_discrepancy_bound(&points, 8);
+        assert!((0.875..=1.0).contains(&bound));
+
+        let report = ErrorBound::new(&points, 2.0, 8);
+        assert_eq!(report.samples, 10);
+        assert_eq!(report.bound, 2.0 * report.discrepancy);
+    }
+
+    #[test]
+    fn interaction() {
+        // Only the product of coordinates 0 and 2 varies
+        let estimate = EffectiveDimension::estimate(3, 4096, |x| (x[0] - 0.5) * (x[2] - 0.5));
+        assert!((estimate.total_indices[0] - 1.0).abs() < 0.05);
+        assert!(estimate.total_indices[1] < 0.01);
+        assert!(estimate.leading_indices[1] < 0.05);
+        assert_eq!(estimate.truncation_dimension(0.99), 3);
+    }
+}
@@ -0,0 +1,119 @@
+//! Comparing the streams of two generator configurations.
+//!
+//! Changing a seed, a burn-in or a stream key changes a generator's output,
+//! and reviewers want to know exactly how. Since point `n` of a `Qrng` is
+//! `start + n * alpha` in fixed point, two generators of the same type
+//! differ by the same fixed-point offset at every index. `diff` uses that to
+//! prove when two streams are identical forever, or the same stream shifted
+//! by some number of indices, and finds the first index where the generated
+//! values differ.
+
+use crate::{Qrng, Quasirandom, State};
+
+/// How the streams of two generators relate, from `diff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamDiff {
+    /// The first index, counted from construction as in `sample_at`, where
+    /// the two generators produce different values, if there is one among
+    /// those compared
+    pub first_difference: Option<u64>,
+    /// Whether the streams agree at every index, not only those compared
+    pub identical: bool,
+    /// When the second stream is the first one shifted: the second
+    /// generator produces at index `n` what the first does at `n + offset`,
+    /// for every `n`
+    pub offset: Option<i64>,
+}
+
+/// Compares the streams of `a` and `b` from construction.
+///
+/// Whether they are identical, or shifted copies, holds for every index.
+/// Otherwise the values are compared one index at a time, up to `limit`
+/// indices, since values of coarse types like `u8` can agree for a while
+/// even when the points differ.
+///
+/// ```
+/// use quasirandom::diff::diff;
+/// use quasirandom::Qrng;
+///
+/// let old = Qrng::<(f64, f64)>::builder().seed(0.25).build();
+/// let new = Qrng::<(f64, f64)>::builder().seed(0.25).burn_in(100).build();
+/// let report = diff(&old, &new, 1000);
+/// assert_eq!(report.first_difference, Some(0));
+/// assert_eq!(report.offset, Some(100));
+///
+/// let same = Qrng::<(f64, f64)>::new(0.25);
+/// assert!(diff(&old, &same, 0).identical);
+/// ```
+pub fn diff<const N: usize, T>(a: &Qrng<T>, b: &Qrng<T>, limit: u64) -> StreamDiff
+where
+    T: Quasirandom<State = State<N>> + PartialEq,
+{
+    let (start_a, start_b) = (a.state.seek(0).fixed, b.state.seek(0).fixed);
+    if start_a == start_b {
+        return StreamDiff {
+            first_difference: None,
+            identical: true,
+            offset: Some(0),
+        };
+    }
+    let difference: [u64; N] = std::array::from_fn(|d| start_b[d].wrapping_sub(start_a[d]));
+    StreamDiff {
+        first_difference: (0..limit).find(|&index| a.sample_at(index) != b.sample_at(index)),
+        identical: false,
+        offset: index_offset(difference, State::<N>::ALPHAS),
+    }
+}
+
+/// The `k`, nearest zero, with `difference = k * alphas` modulo 2^64 in
+/// every dimension
+fn index_offset<const N: usize>(difference: [u64; N], alphas: [u64; N]) -> Option<i64> {
+    // Solve in the first dimension: alpha = 2^t * odd, so k is determined
+    // modulo 2^(64 - t), and the other dimensions pick among the candidates
+    let twos = alphas[0].trailing_zeros();
+    if difference[0].trailing_zeros() < twos || twos > 16 {
+        return None;
+    }
+    let odd = alphas[0] >> twos;
+    let mut inverse = odd;
+    for _ in 0..5 {
+        inverse = inverse.wrapping_mul(2u64.wrapping_sub(odd.wrapping_mul(inverse)));
+    }
+    let base = (difference[0] >> twos).wrapping_mul(inverse);
+    let base = if twos == 0 { base } else { base & (u64::MAX >> twos) };
+    (0..1u64 << twos)
+        .map(|j| base.wrapping_add(j << (64 - twos.max(1))) as i64)
+        .filter(|&k| (0..N).all(|d| (k as u64).wrapping_mul(alphas[d]) == difference[d]))
+        .min_by_key(|k| k.unsigned_abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streams::StreamKey;
+
+    #[test]
+    fn reports_offsets_and_first_differences() {
+        let base = Qrng::<[f64; 3]>::builder().seed(0.5).burn_in(1000).build();
+        for burn_in in [0, 999, 1001, 1 << 40] {
+            let other = Qrng::<[f64; 3]>::builder().seed(0.5).burn_in(burn_in).build();
+            let report = diff(&base, &other, 10);
+            assert_eq!(report.offset, Some(burn_in as i64 - 1000));
+            assert_eq!(report.first_difference, Some(0));
+            assert!(!report.identical);
+        }
+
+        // Another key moves every point by an unrelated shift
+        let keyed = Qrng::<[f64; 3]>::builder().seed(0.5).shift(StreamKey::root(1)).build();
+        assert_eq!(diff(&base, &keyed, 10).offset, None);
+
+        // Coarse values agree for a while before the first difference
+        let a = Qrng::<(u8, bool)>::builder().seed(0.5).build();
+        let b = Qrng::<(u8, bool)>::builder().seed(0.5 + 1e-5).build();
+        let report = diff(&a, &b, 1_000_000);
+        let first = report.first_difference.unwrap();
+        assert!(first > 0);
+        assert_ne!(a.sample_at(first), b.sample_at(first));
+        assert!((0..first).all(|i| a.sample_at(i) == b.sample_at(i)));
+    }
+}
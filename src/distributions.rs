@@ -0,0 +1,1590 @@
+//! Distributions sampled by transforming quasirandom uniforms.
+//!
+//! Rejection sampling consumes a varying number of uniforms per sample, which
+//! breaks the correspondence between sequence dimensions and sample
+//! coordinates that quasirandom sequences rely on. Every distribution here
+//! instead consumes a fixed number of uniforms per sample.
+
+use std::f64::consts::{PI, SQRT_2};
+
+use crate::warp::{is_unit, Warp};
+
+/// A distribution that maps a fixed number of uniforms in `[0, 1)` to a sample.
+///
+/// Feed it from a `Qrng` whose tuple arity matches `dimension()`:
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::distributions::{Distribution, VonMisesFisher};
+///
+/// let vmf = VonMisesFisher::new([0.0, 0.0, 1.0], 10.0);
+/// let mut qrng = Qrng::<(f64, f64)>::new(0.5);
+/// let (u, v) = qrng.gen();
+/// let [x, y, z] = vmf.sample(&[u, v]);
+/// assert!((x * x + y * y + z * z - 1.0).abs() < 1e-12);
+/// ```
+pub trait Distribution {
+    type Output;
+
+    /// The number of uniforms consumed by each call to `sample`
+    fn dimension(&self) -> usize;
+
+    /// Map the first `dimension()` values of `uniforms` to a sample
+    fn sample(&self, uniforms: &[f64]) -> Self::Output;
+}
+
+impl<T> Distribution for Box<dyn Distribution<Output = T>> {
+    type Output = T;
+
+    fn dimension(&self) -> usize {
+        (**self).dimension()
+    }
+
+    fn sample(&self, uniforms: &[f64]) -> T {
+        (**self).sample(uniforms)
+    }
+}
+
+/// A univariate distribution whose CDF can be evaluated and inverted.
+///
+/// Every `InvertibleCdf` is a `Distribution` that consumes a single uniform
+/// and maps it through `inverse_cdf`, so it preserves the low discrepancy of
+/// the input sequence.
+pub trait InvertibleCdf {
+    /// The probability that a sample is `<= x`
+    fn cdf(&self, x: f64) -> f64;
+
+    /// The smallest `x` with `cdf(x) >= p`, for `p` in `[0, 1]`
+    fn inverse_cdf(&self, p: f64) -> f64;
+
+    /// The probability that a sample is `> x`. Override it where
+    /// `1 - cdf(x)` loses precision in the upper tail.
+    fn sf(&self, x: f64) -> f64 {
+        1.0 - self.cdf(x)
+    }
+
+    /// The `x` with `sf(x) = q`, for `q` in `[0, 1]`
+    fn inverse_sf(&self, q: f64) -> f64 {
+        self.inverse_cdf(1.0 - q)
+    }
+}
+
+impl<D: InvertibleCdf> Distribution for D {
+    type Output = f64;
+
+    fn dimension(&self) -> usize {
+        1
+    }
+
+    fn sample(&self, uniforms: &[f64]) -> f64 {
+        self.inverse_cdf(uniforms[0])
+    }
+}
+
+/// The normal distribution with the given mean and standard deviation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Normal {
+    mean: f64,
+    std_dev: f64,
+}
+
+impl Normal {
+    pub fn new(mean: f64, std_dev: f64) -> Self {
+        assert!(std_dev > 0.0);
+        Self { mean, std_dev }
+    }
+
+    /// The normal distribution with mean 0 and standard deviation 1
+    pub fn standard() -> Self {
+        Self::new(0.0, 1.0)
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        self.std_dev
+    }
+}
+
+impl InvertibleCdf for Normal {
+    fn cdf(&self, x: f64) -> f64 {
+        standard_normal_cdf((x - self.mean) / self.std_dev)
+    }
+
+    /// Returns negative infinity for `p = 0`
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        self.mean + self.std_dev * standard_normal_inverse_cdf(p)
+    }
+
+    fn sf(&self, x: f64) -> f64 {
+        standard_normal_cdf((self.mean - x) / self.std_dev)
+    }
+
+    /// Returns infinity for `q = 0`
+    fn inverse_sf(&self, q: f64) -> f64 {
+        self.mean - self.std_dev * standard_normal_inverse_cdf(q)
+    }
+}
+
+/// The exponential distribution with the given rate, such as the time between
+/// arrivals of a Poisson process
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::distributions::{Distribution, Exponential};
+///
+/// // Customers arrive at a rate of 4 per minute
+/// let interarrival = Exponential::new(4.0);
+/// let mut qrng = Qrng::<f64>::new(0.5);
+/// let mean = (0..1000).map(|_| interarrival.sample(&[qrng.gen()])).sum::<f64>() / 1000.0;
+/// assert!((mean - 0.25).abs() < 0.01);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Exponential {
+    rate: f64,
+}
+
+impl Exponential {
+    pub fn new(rate: f64) -> Self {
+        assert!(rate > 0.0);
+        Self { rate }
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+}
+
+impl InvertibleCdf for Exponential {
+    fn cdf(&self, x: f64) -> f64 {
+        if x <= 0.0 {
+            0.0
+        } else {
+            -(-self.rate * x).exp_m1()
+        }
+    }
+
+    /// Returns infinity for `p = 1`
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        -(-p).ln_1p() / self.rate
+    }
+
+    fn sf(&self, x: f64) -> f64 {
+        (-self.rate * x.max(0.0)).exp()
+    }
+
+    /// Returns infinity for `q = 0`
+    fn inverse_sf(&self, q: f64) -> f64 {
+        -q.ln() / self.rate
+    }
+}
+
+/// The Weibull distribution with the given scale and shape. A shape of one
+/// gives the exponential distribution with rate `1 / scale`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weibull {
+    scale: f64,
+    shape: f64,
+}
+
+impl Weibull {
+    pub fn new(scale: f64, shape: f64) -> Self {
+        assert!(scale > 0.0);
+        assert!(shape > 0.0);
+        Self { scale, shape }
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn shape(&self) -> f64 {
+        self.shape
+    }
+}
+
+impl InvertibleCdf for Weibull {
+    fn cdf(&self, x: f64) -> f64 {
+        if x <= 0.0 {
+            0.0
+        } else {
+            -(-(x / self.scale).powf(self.shape)).exp_m1()
+        }
+    }
+
+    /// Returns infinity for `p = 1`
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        self.scale * (-(-p).ln_1p()).powf(self.shape.recip())
+    }
+
+    fn sf(&self, x: f64) -> f64 {
+        (-(x.max(0.0) / self.scale).powf(self.shape)).exp()
+    }
+
+    /// Returns infinity for `q = 0`
+    fn inverse_sf(&self, q: f64) -> f64 {
+        self.scale * (-q.ln()).powf(self.shape.recip())
+    }
+}
+
+/// The gamma distribution with the given shape and scale.
+///
+/// Sampled by inverting the regularized incomplete gamma function with
+/// Halley's method, so each sample consumes exactly one uniform.
+///
+/// ```
+/// use quasirandom::distributions::{Gamma, InvertibleCdf};
+///
+/// let gamma = Gamma::new(2.5, 2.0);
+/// let x = gamma.inverse_cdf(0.7);
+/// assert!((gamma.cdf(x) - 0.7).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gamma {
+    shape: f64,
+    scale: f64,
+    ln_gamma_shape: f64,
+}
+
+impl Gamma {
+    pub fn new(shape: f64, scale: f64) -> Self {
+        assert!(shape > 0.0);
+        assert!(scale > 0.0);
+        Self {
+            shape,
+            scale,
+            ln_gamma_shape: ln_gamma(shape),
+        }
+    }
+
+    pub fn shape(&self) -> f64 {
+        self.shape
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+}
+
+impl InvertibleCdf for Gamma {
+    fn cdf(&self, x: f64) -> f64 {
+        regularized_gamma(self.shape, x / self.scale, self.ln_gamma_shape)
+    }
+
+    /// Returns infinity for `p = 1`
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        if p <= 0.0 {
+            return 0.0;
+        }
+        if p >= 1.0 {
+            return f64::INFINITY;
+        }
+        let a = self.shape;
+        // Start from the Wilson-Hilferty approximation for large shapes, and
+        // from the leading term of the series near zero for small ones
+        let mut x = if a > 1.0 {
+            let z = standard_normal_inverse_cdf(p);
+            (a * (1.0 - 1.0 / (9.0 * a) + z / (3.0 * a.sqrt())).powi(3)).max(1e-3)
+        } else {
+            let t = 1.0 - a * (0.253 + a * 0.12);
+            if p < t {
+                (p / t).powf(a.recip())
+            } else {
+                1.0 - (-(p - t) / (1.0 - t)).ln_1p()
+            }
+        };
+        for _ in 0..20 {
+            if x <= 0.0 {
+                return 0.0;
+            }
+            let error = regularized_gamma(a, x, self.ln_gamma_shape) - p;
+            let density = ((a - 1.0) * x.ln() - x - self.ln_gamma_shape).exp();
+            if density == 0.0 {
+                break;
+            }
+            let u = error / density;
+            let step = u / (1.0 - 0.5 * (u * ((a - 1.0) / x - 1.0)).min(1.0));
+            x -= step;
+            if x <= 0.0 {
+                x = 0.5 * (x + step);
+            }
+            if step.abs() < 1e-14 * x {
+                break;
+            }
+        }
+        self.scale * x
+    }
+}
+
+/// The beta distribution on `[0, 1]` with the given shape parameters.
+///
+/// Sampled by inverting the regularized incomplete beta function with
+/// Halley's method, so each sample consumes exactly one uniform.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beta {
+    alpha: f64,
+    beta: f64,
+    ln_beta: f64,
+}
+
+impl Beta {
+    pub fn new(alpha: f64, beta: f64) -> Self {
+        assert!(alpha > 0.0);
+        assert!(beta > 0.0);
+        Self {
+            alpha,
+            beta,
+            ln_beta: ln_gamma(alpha) + ln_gamma(beta) - ln_gamma(alpha + beta),
+        }
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    pub fn beta(&self) -> f64 {
+        self.beta
+    }
+}
+
+impl InvertibleCdf for Beta {
+    fn cdf(&self, x: f64) -> f64 {
+        regularized_beta(self.alpha, self.beta, x, self.ln_beta)
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        if p <= 0.0 {
+            return 0.0;
+        }
+        if p >= 1.0 {
+            return 1.0;
+        }
+        let (a, b) = (self.alpha, self.beta);
+        // Initial guesses from Numerical Recipes: a normal approximation when
+        // both shapes are at least one, the behavior at the ends otherwise
+        let mut x = if a >= 1.0 && b >= 1.0 {
+            // The upper-tail normal quantile
+            let z = -standard_normal_inverse_cdf(p);
+            let lambda = (z * z - 3.0) / 6.0;
+            let h = 2.0 / (1.0 / (2.0 * a - 1.0) + 1.0 / (2.0 * b - 1.0));
+            let w = z * (lambda + h).sqrt() / h
+                - (1.0 / (2.0 * b - 1.0) - 1.0 / (2.0 * a - 1.0)) * (lambda + 5.0 / 6.0 - 2.0 / (3.0 * h));
+            a / (a + b * (2.0 * w).exp())
+        } else {
+            let t = (a * (a / (a + b)).ln()).exp() / a;
+            let u = (b * (b / (a + b)).ln()).exp() / b;
+            let w = t + u;
+            if p < t / w {
+                (a * w * p).powf(a.recip())
+            } else {
+                1.0 - (b * w * (1.0 - p)).powf(b.recip())
+            }
+        };
+        for _ in 0..20 {
+            if x <= 0.0 || x >= 1.0 {
+                break;
+            }
+            let error = regularized_beta(a, b, x, self.ln_beta) - p;
+            let density = ((a - 1.0) * x.ln() + (b - 1.0) * (-x).ln_1p() - self.ln_beta).exp();
+            if density == 0.0 {
+                break;
+            }
+            let u = error / density;
+            let step = u / (1.0 - 0.5 * (u * ((a - 1.0) / x - (b - 1.0) / (1.0 - x))).min(1.0));
+            x -= step;
+            if x <= 0.0 {
+                x = 0.5 * (x + step);
+            }
+            if x >= 1.0 {
+                x = 0.5 * (x + step + 1.0);
+            }
+            if step.abs() < 1e-14 * x {
+                break;
+            }
+        }
+        x.clamp(0.0, 1.0)
+    }
+}
+
+/// Restricts a distribution to the interval `[lo, hi]`.
+///
+/// Samples are produced by inverting the CDF over the restricted range, so
+/// every uniform maps to a sample inside the interval and no samples are
+/// rejected. Either bound may be infinite.
+///
+/// ```
+/// use quasirandom::distributions::{Distribution, Normal, Truncated};
+///
+/// let positive = Truncated::new(Normal::standard(), 0.0, f64::INFINITY);
+/// assert!(positive.sample(&[0.01]) > 0.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Truncated<D> {
+    distribution: D,
+    lo: f64,
+    hi: f64,
+    /// Whether the interval starts above the median, where probabilities
+    /// are measured with `sf` instead of `cdf` to keep their precision
+    upper_tail: bool,
+    /// `cdf(lo)`, or `sf(lo)` in the upper tail
+    start: f64,
+    mass: f64,
+}
+
+impl<D: InvertibleCdf> Truncated<D> {
+    /// The interval must contain a nonzero amount of probability mass.
+    pub fn new(distribution: D, lo: f64, hi: f64) -> Self {
+        assert!(lo < hi);
+        let upper_tail = distribution.cdf(lo) > 0.5;
+        let (start, mass) = if upper_tail {
+            let sf_lo = distribution.sf(lo);
+            (sf_lo, sf_lo - distribution.sf(hi))
+        } else {
+            let cdf_lo = distribution.cdf(lo);
+            (cdf_lo, distribution.cdf(hi) - cdf_lo)
+        };
+        assert!(mass > 0.0);
+        Self {
+            distribution,
+            lo,
+            hi,
+            upper_tail,
+            start,
+            mass,
+        }
+    }
+
+    pub fn bounds(&self) -> (f64, f64) {
+        (self.lo, self.hi)
+    }
+
+    pub fn inner(&self) -> &D {
+        &self.distribution
+    }
+}
+
+impl<D: InvertibleCdf> InvertibleCdf for Truncated<D> {
+    fn cdf(&self, x: f64) -> f64 {
+        let below = if self.upper_tail {
+            self.start - self.distribution.sf(x)
+        } else {
+            self.distribution.cdf(x) - self.start
+        };
+        (below / self.mass).clamp(0.0, 1.0)
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        let x = if self.upper_tail {
+            self.distribution.inverse_sf(self.start - p * self.mass)
+        } else {
+            self.distribution.inverse_cdf(self.start + p * self.mass)
+        };
+        x.clamp(self.lo, self.hi)
+    }
+}
+
+/// A distribution given by a table of its CDF, sampled by interpolating the
+/// inverse linearly between the knots.
+///
+/// This turns any univariate target into a one-uniform sampler: tabulate an
+/// inverse CDF that is too slow to call per sample with `from_fn`, or an
+/// empirical or measured CDF with `from_table`. The interpolated inverse is
+/// monotone, so evenly spread uniforms stay evenly spread.
+///
+/// ```
+/// use quasirandom::distributions::{Distribution, InverseCdf, InvertibleCdf};
+///
+/// // The triangular distribution on [0, 2], from its inverse CDF
+/// let inverse = |p: f64| if p < 0.5 { (2.0 * p).sqrt() } else { 2.0 - (2.0 - 2.0 * p).sqrt() };
+/// let triangular = InverseCdf::from_fn(inverse, 1000);
+/// assert_eq!(triangular.sample(&[0.5]), 1.0);
+/// assert!((triangular.cdf(0.5) - 0.125).abs() < 1e-3);
+///
+/// // Half the mass at exactly 1, the rest spread evenly over [2, 3]
+/// let measured = InverseCdf::from_table(&[(1.0, 0.0), (1.0, 0.5), (2.0, 0.5), (3.0, 1.0)]);
+/// assert_eq!(measured.sample(&[0.25]), 1.0);
+/// assert_eq!(measured.sample(&[0.75]), 2.5);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct InverseCdf {
+    values: Vec<f64>,
+    probabilities: Vec<f64>,
+}
+
+impl InverseCdf {
+    /// Tabulates the inverse CDF `f` at `resolution + 1` evenly spaced
+    /// probabilities from zero to one. `f` must be non-decreasing and finite
+    /// at both ends, so truncate unbounded distributions first.
+    pub fn from_fn(f: impl Fn(f64) -> f64, resolution: usize) -> Self {
+        assert!(resolution > 0);
+        let table: Vec<(f64, f64)> = (0..=resolution)
+            .map(|i| {
+                let p = i as f64 / resolution as f64;
+                (f(p), p)
+            })
+            .collect();
+        Self::from_table(&table)
+    }
+
+    /// Uses `(x, cdf(x))` pairs as the knots, in order. Both coordinates
+    /// must be non-decreasing, with probabilities from zero to one. A repeated
+    /// `x` with rising probability is an atom, and a repeated probability
+    /// with rising `x` a gap with no mass.
+    pub fn from_table(points: &[(f64, f64)]) -> Self {
+        assert!(points.len() >= 2);
+        assert!(points.iter().all(|(x, p)| x.is_finite() && (0.0..=1.0).contains(p)));
+        assert!(points.windows(2).all(|pair| pair[0].0 <= pair[1].0 && pair[0].1 <= pair[1].1), "the table must be non-decreasing");
+        assert_eq!(points[0].1, 0.0);
+        assert_eq!(points[points.len() - 1].1, 1.0);
+        let (values, probabilities) = points.iter().copied().unzip();
+        Self { values, probabilities }
+    }
+
+    /// The knots, as `(x, cdf(x))` pairs
+    pub fn table(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.values.iter().copied().zip(self.probabilities.iter().copied())
+    }
+}
+
+impl InvertibleCdf for InverseCdf {
+    fn cdf(&self, x: f64) -> f64 {
+        let last = self.values.len() - 1;
+        if x < self.values[0] {
+            return 0.0;
+        }
+        if x >= self.values[last] {
+            return 1.0;
+        }
+        // The last knot at or below x, and the next one, which is above it
+        let i = self.values.partition_point(|&v| v <= x) - 1;
+        let t = (x - self.values[i]) / (self.values[i + 1] - self.values[i]);
+        self.probabilities[i] + t * (self.probabilities[i + 1] - self.probabilities[i])
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        let last = self.values.len() - 1;
+        let i = self.probabilities.partition_point(|&q| q <= p).clamp(1, last) - 1;
+        let width = self.probabilities[i + 1] - self.probabilities[i];
+        if width == 0.0 {
+            return self.values[i + 1];
+        }
+        let t = ((p - self.probabilities[i]) / width).min(1.0);
+        self.values[i] + t * (self.values[i + 1] - self.values[i])
+    }
+}
+
+/// A weighted mixture of component distributions.
+///
+/// The first uniform chooses the component by inverting the cumulative
+/// weights, so components are selected in proportion to their weights with
+/// the same evenness as the underlying sequence. The remaining uniforms are
+/// passed to the chosen component. The dimension is therefore one more than
+/// the largest component dimension, whichever component is chosen.
+///
+/// Components of different types can be mixed by boxing them as
+/// `Box<dyn Distribution<Output = T>>`.
+///
+/// ```
+/// use quasirandom::distributions::{Distribution, Mixture, Normal};
+///
+/// let bimodal = Mixture::new(vec![
+///     (0.3, Normal::new(-2.0, 0.5)),
+///     (0.7, Normal::new(3.0, 1.0)),
+/// ]);
+/// assert_eq!(bimodal.dimension(), 2);
+/// assert!(bimodal.sample(&[0.1, 0.5]) < 0.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Mixture<D> {
+    components: Vec<D>,
+    index: WeightedIndex,
+    dimension: usize,
+}
+
+impl<D: Distribution> Mixture<D> {
+    /// Weights do not need to be normalized, but must be nonnegative with a
+    /// positive sum.
+    pub fn new(components: impl IntoIterator<Item = (f64, D)>) -> Self {
+        let (weights, components): (Vec<f64>, Vec<D>) = components.into_iter().unzip();
+        let index = WeightedIndex::new(&weights);
+        let dimension = 1 + components.iter().map(D::dimension).max().unwrap_or(0);
+
+        Self { components, index, dimension }
+    }
+
+    pub fn components(&self) -> &[D] {
+        &self.components
+    }
+
+    /// The index of the component chosen by the uniform `u`
+    pub fn component_index(&self, u: f64) -> usize {
+        self.index.index(u)
+    }
+}
+
+/// Chooses an index with probability proportional to its weight.
+///
+/// A single uniform is mapped through the cumulative weights, so the mapping
+/// is monotone: evenly spread uniforms choose each index in almost exactly
+/// its share of draws, in the same order as the uniforms. (An alias table
+/// would take constant time per draw but shuffles that order, losing the
+/// even spread.) Choosing takes `O(log n)` time.
+///
+/// ```
+/// use quasirandom::distributions::WeightedIndex;
+/// use quasirandom::Qrng;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// enum Weather {
+///     Sun,
+///     Rain,
+///     Snow,
+/// }
+///
+/// let weather = [Weather::Sun, Weather::Rain, Weather::Snow];
+/// let index = WeightedIndex::new(&[6.0, 3.0, 1.0]);
+/// let mut qrng = Qrng::<f64>::new(0.0);
+/// let days: Vec<Weather> = (0..100).map(|_| weather[index.index(qrng.gen())]).collect();
+/// assert_eq!(days.iter().filter(|&&day| day == Weather::Snow).count(), 10);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightedIndex {
+    cumulative: Vec<f64>,
+}
+
+impl WeightedIndex {
+    /// Weights do not need to be normalized, but must be nonnegative with a
+    /// positive sum. Indices with zero weight are never chosen.
+    pub fn new(weights: &[f64]) -> Self {
+        assert!(!weights.is_empty());
+        assert!(weights.iter().all(|&w| w >= 0.0 && w.is_finite()));
+        let total = weights.iter().sum::<f64>();
+        assert!(total > 0.0);
+
+        let mut cumulative = Vec::with_capacity(weights.len());
+        let mut sum = 0.0;
+        for w in weights {
+            sum += w;
+            cumulative.push(sum / total);
+        }
+        Self { cumulative }
+    }
+
+    /// The number of indices
+    pub fn len(&self) -> usize {
+        self.cumulative.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cumulative.is_empty()
+    }
+
+    /// The probability of choosing `index`
+    pub fn probability(&self, index: usize) -> f64 {
+        self.cumulative[index] - if index == 0 { 0.0 } else { self.cumulative[index - 1] }
+    }
+
+    /// The index chosen by the uniform `u`
+    pub fn index(&self, u: f64) -> usize {
+        self.cumulative.partition_point(|&c| c <= u).min(self.cumulative.len() - 1)
+    }
+}
+
+impl Distribution for WeightedIndex {
+    type Output = usize;
+
+    fn dimension(&self) -> usize {
+        1
+    }
+
+    fn sample(&self, uniforms: &[f64]) -> usize {
+        self.index(uniforms[0])
+    }
+}
+
+impl<D: Distribution> Distribution for Mixture<D> {
+    type Output = D::Output;
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn sample(&self, uniforms: &[f64]) -> D::Output {
+        let component = &self.components[self.component_index(uniforms[0])];
+        component.sample(&uniforms[1..])
+    }
+}
+
+/// The Poisson distribution with the given mean, such as the number of events
+/// in an interval.
+///
+/// Like the other discrete distributions here, it is sampled by looking the
+/// uniform up in a table of the CDF, built on construction over every count
+/// with non-negligible probability. Each sample consumes one uniform, and the
+/// mapping is monotone, so evenly spread uniforms give counts in almost
+/// exactly their expected proportions.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::distributions::{Distribution, Poisson};
+///
+/// let infections = Poisson::new(2.5);
+/// let mut qrng = Qrng::<f64>::new(0.5);
+/// let total: u64 = (0..1000).map(|_| infections.sample(&[qrng.gen()])).sum();
+/// assert!((total as f64 / 1000.0 - 2.5).abs() < 0.01);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Poisson {
+    mean: f64,
+    table: CountTable,
+}
+
+impl Poisson {
+    /// The largest supported mean. The table grows with the square root of
+    /// the mean, and the log-probabilities lose precision as it grows.
+    pub const MAX_MEAN: f64 = 1e6;
+
+    /// The mean must be positive and at most `MAX_MEAN`.
+    pub fn new(mean: f64) -> Self {
+        assert!(mean > 0.0 && mean <= Self::MAX_MEAN);
+        let table = CountTable::new(mean, mean.sqrt(), u64::MAX, |k| {
+            (k * mean.ln() - mean - ln_gamma(k + 1.0)).exp()
+        });
+        Self { mean, table }
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+}
+
+impl Distribution for Poisson {
+    type Output = u64;
+
+    fn dimension(&self) -> usize {
+        1
+    }
+
+    fn sample(&self, uniforms: &[f64]) -> u64 {
+        self.table.count(uniforms[0])
+    }
+}
+
+/// The binomial distribution: the number of successes in `trials`
+/// independent trials that each succeed with probability `probability`.
+/// Sampled by table lookup like `Poisson`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binomial {
+    trials: u64,
+    probability: f64,
+    table: CountTable,
+}
+
+impl Binomial {
+    /// The largest supported number of trials, for the same reasons as
+    /// `Poisson::MAX_MEAN`
+    pub const MAX_TRIALS: u64 = 10_000_000;
+
+    /// At most `MAX_TRIALS` trials, with a probability in `[0, 1]`.
+    pub fn new(trials: u64, probability: f64) -> Self {
+        assert!(trials <= Self::MAX_TRIALS);
+        assert!((0.0..=1.0).contains(&probability));
+        let n = trials as f64;
+        let mean = n * probability;
+        let ln_n_factorial = ln_gamma(n + 1.0);
+        let table = CountTable::new(mean, (mean * (1.0 - probability)).sqrt(), trials, |k| {
+            if probability == 0.0 || probability == 1.0 {
+                // Every trial fails, or every trial succeeds
+                return if k == mean { 1.0 } else { 0.0 };
+            }
+            (ln_n_factorial - ln_gamma(k + 1.0) - ln_gamma(n - k + 1.0)
+                + k * probability.ln()
+                + (n - k) * (-probability).ln_1p())
+            .exp()
+        });
+        Self {
+            trials,
+            probability,
+            table,
+        }
+    }
+
+    pub fn trials(&self) -> u64 {
+        self.trials
+    }
+
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+}
+
+impl Distribution for Binomial {
+    type Output = u64;
+
+    fn dimension(&self) -> usize {
+        1
+    }
+
+    fn sample(&self, uniforms: &[f64]) -> u64 {
+        self.table.count(uniforms[0])
+    }
+}
+
+/// The geometric distribution: the number of failures before the first
+/// success, when each trial succeeds with probability `probability`.
+///
+/// Its CDF inverts in closed form, so no table is needed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geometric {
+    probability: f64,
+}
+
+impl Geometric {
+    pub fn new(probability: f64) -> Self {
+        assert!(probability > 0.0 && probability <= 1.0);
+        Self { probability }
+    }
+
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+}
+
+impl Distribution for Geometric {
+    type Output = u64;
+
+    fn dimension(&self) -> usize {
+        1
+    }
+
+    fn sample(&self, uniforms: &[f64]) -> u64 {
+        if self.probability == 1.0 {
+            return 0;
+        }
+        // The smallest k with 1 - (1 - p)^(k + 1) > u
+        ((-uniforms[0]).ln_1p() / (-self.probability).ln_1p()).floor() as u64
+    }
+}
+
+/// A CDF table over the counts within a wide margin of the mean
+#[derive(Debug, Clone, PartialEq)]
+struct CountTable {
+    first: u64,
+    index: WeightedIndex,
+}
+
+impl CountTable {
+    /// Tabulates `pmf` over the counts within 12 standard deviations (and at
+    /// least 12) of `mean`, up to `max`. The probability outside is below
+    /// `1e-20`.
+    fn new(mean: f64, std_dev: f64, max: u64, pmf: impl Fn(f64) -> f64) -> Self {
+        let margin = 12.0 * std_dev + 12.0;
+        let first = (mean - margin).max(0.0).floor() as u64;
+        let last = ((mean + margin).ceil() as u64).min(max);
+        let weights: Vec<f64> = (first..=last).map(|k| pmf(k as f64)).collect();
+        Self {
+            first,
+            index: WeightedIndex::new(&weights),
+        }
+    }
+
+    fn count(&self, u: f64) -> u64 {
+        self.first + self.index.index(u) as u64
+    }
+}
+
+/// The Dirichlet distribution over weight vectors on the probability simplex,
+/// with one concentration parameter per weight.
+///
+/// Samples are built by stick breaking: each weight takes a Beta-distributed
+/// fraction of what the earlier weights left over. Each fraction is sampled by
+/// inversion, so `k` weights consume exactly `k - 1` uniforms and evenly
+/// spread uniforms give evenly spread weight vectors. With every
+/// concentration equal to one, the weights are uniform on the simplex.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::distributions::{Dirichlet, Distribution};
+///
+/// // Portfolio weights over four assets
+/// let dirichlet = Dirichlet::new(vec![1.0; 4]);
+/// assert_eq!(dirichlet.dimension(), 3);
+/// let mut qrng = Qrng::<[f64; 3]>::new(0.5);
+/// let weights = dirichlet.sample(&qrng.gen());
+/// assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dirichlet {
+    concentrations: Vec<f64>,
+    /// The distribution of each weight's share of what the earlier ones left
+    fractions: Vec<Beta>,
+}
+
+impl Dirichlet {
+    /// There must be at least two concentrations, all positive.
+    pub fn new(concentrations: Vec<f64>) -> Self {
+        assert!(concentrations.len() >= 2);
+        assert!(concentrations.iter().all(|&alpha| alpha > 0.0));
+        // Summing the rest rather than subtracting from the total keeps small
+        // concentrations from cancelling to zero
+        let fractions = (0..concentrations.len() - 1)
+            .map(|i| Beta::new(concentrations[i], concentrations[i + 1..].iter().sum()))
+            .collect();
+        Self { concentrations, fractions }
+    }
+
+    pub fn concentrations(&self) -> &[f64] {
+        &self.concentrations
+    }
+}
+
+impl Distribution for Dirichlet {
+    type Output = Vec<f64>;
+
+    fn dimension(&self) -> usize {
+        self.fractions.len()
+    }
+
+    fn sample(&self, uniforms: &[f64]) -> Vec<f64> {
+        let mut weights = Vec::with_capacity(self.concentrations.len());
+        let mut remaining = 1.0;
+        for (fraction, &u) in self.fractions.iter().zip(uniforms) {
+            let weight = remaining * fraction.inverse_cdf(u);
+            weights.push(weight);
+            remaining -= weight;
+        }
+        weights.push(remaining.max(0.0));
+        weights
+    }
+}
+
+/// The von Mises–Fisher distribution on the unit sphere in three dimensions.
+///
+/// Directions cluster around `mean` with a tightness controlled by
+/// `concentration` (κ). A concentration of zero is the uniform distribution on
+/// the sphere. Sampling is done by exact inversion and consumes 2 uniforms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VonMisesFisher {
+    mean: [f64; 3],
+    concentration: f64,
+}
+
+impl VonMisesFisher {
+    /// `mean` does not need to be normalized, but must be nonzero.
+    pub fn new(mean: [f64; 3], concentration: f64) -> Self {
+        let length = (mean[0] * mean[0] + mean[1] * mean[1] + mean[2] * mean[2]).sqrt();
+        assert!(length > 0.0);
+        assert!(concentration >= 0.0);
+        Self {
+            mean: mean.map(|x| x / length),
+            concentration,
+        }
+    }
+
+    pub fn mean(&self) -> [f64; 3] {
+        self.mean
+    }
+
+    pub fn concentration(&self) -> f64 {
+        self.concentration
+    }
+}
+
+impl Distribution for VonMisesFisher {
+    type Output = [f64; 3];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn sample(&self, uniforms: &[f64]) -> [f64; 3] {
+        let (u, v) = (uniforms[0], uniforms[1]);
+        let kappa = self.concentration;
+
+        // Cosine of the angle to the mean direction, by inverting its CDF
+        let w = if kappa == 0.0 {
+            2.0 * u - 1.0
+        } else {
+            let w = 1.0 + (u + (1.0 - u) * (-2.0 * kappa).exp()).ln() / kappa;
+            w.clamp(-1.0, 1.0)
+        };
+        let r = (1.0 - w * w).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let local = [r * phi.cos(), r * phi.sin(), w];
+
+        let [t, b] = orthonormal_basis(self.mean);
+        let n = self.mean;
+        [
+            local[0] * t[0] + local[1] * b[0] + local[2] * n[0],
+            local[0] * t[1] + local[1] * b[1] + local[2] * n[1],
+            local[0] * t[2] + local[1] * b[2] + local[2] * n[2],
+        ]
+    }
+}
+
+/// The density per steradian is
+/// `kappa / (2 pi (1 - exp(-2 kappa))) * exp(kappa (mean . x - 1))`.
+impl Warp for VonMisesFisher {
+    type Output = [f64; 3];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; 3], f64) {
+        let direction = self.sample(u);
+        (direction, self.pdf(&direction))
+    }
+
+    fn pdf(&self, direction: &[f64; 3]) -> f64 {
+        if !is_unit(direction) {
+            return 0.0;
+        }
+        let kappa = self.concentration;
+        if kappa == 0.0 {
+            return 1.0 / (4.0 * PI);
+        }
+        let cos = self.mean.iter().zip(direction).map(|(a, b)| a * b).sum::<f64>();
+        kappa / (2.0 * PI * -(-2.0 * kappa).exp_m1()) * (kappa * (cos - 1.0)).exp()
+    }
+}
+
+/// Two unit vectors completing the unit vector `n` to an orthonormal basis
+/// (Duff et al., "Building an Orthonormal Basis, Revisited")
+fn orthonormal_basis(n: [f64; 3]) -> [[f64; 3]; 2] {
+    let sign = 1.0_f64.copysign(n[2]);
+    let a = -1.0 / (sign + n[2]);
+    let b = n[0] * n[1] * a;
+    [
+        [1.0 + sign * n[0] * n[0] * a, sign * b, -sign * n[0]],
+        [b, sign + n[1] * n[1] * a, -n[1]],
+    ]
+}
+
+/// The complementary error function, accurate to a few ulps.
+///
+/// Uses the Maclaurin series of `erf` near zero, where it converges quickly
+/// without cancellation, and a continued fraction for `erfc` elsewhere.
+pub(crate) fn erfc(x: f64) -> f64 {
+    if x.is_nan() {
+        return x;
+    }
+    if x < 0.0 {
+        return 2.0 - erfc(-x);
+    }
+    if x < 1.0 {
+        let x2 = x * x;
+        let mut term = x;
+        let mut sum = x;
+        let mut n = 0.0;
+        while term > sum * 1e-17 {
+            n += 1.0;
+            term *= 2.0 * x2 / (2.0 * n + 1.0);
+            sum += term;
+        }
+        return 1.0 - 2.0 / PI.sqrt() * (-x2).exp() * sum;
+    }
+    if x > 27.0 {
+        return 0.0;
+    }
+
+    // erfc(x) = 2x exp(-x^2) / sqrt(pi) / (2x^2 + 1 - 1*2 / (2x^2 + 5 - 3*4 / (2x^2 + 9 - ...)))
+    // evaluated with the modified Lentz algorithm
+    let tiny = 1e-300;
+    let x2 = x * x;
+    let mut f = 2.0 * x2 + 1.0;
+    let mut c = f;
+    let mut d = 0.0;
+    for n in 1..500 {
+        let n = n as f64;
+        let a = -(2.0 * n - 1.0) * (2.0 * n);
+        let b = 2.0 * x2 + 1.0 + 4.0 * n;
+        d = b + a * d;
+        if d.abs() < tiny {
+            d = tiny;
+        }
+        c = b + a / c;
+        if c.abs() < tiny {
+            c = tiny;
+        }
+        d = d.recip();
+        let delta = c * d;
+        f *= delta;
+        if (delta - 1.0).abs() < 1e-16 {
+            break;
+        }
+    }
+    2.0 * x * (-x2).exp() / PI.sqrt() / f
+}
+
+/// The logarithm of the gamma function for positive arguments, from the
+/// Lanczos approximation with `g = 7`
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8,
+        771.323_428_777_653_1, -176.615_029_162_140_6, 12.507_343_278_686_905,
+        -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6, 1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection formula
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (i, c) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// The regularized lower incomplete gamma function `P(a, x)`, given
+/// `ln_gamma(a)`. Uses the series for `x < a + 1` and a continued fraction
+/// for the upper function elsewhere.
+fn regularized_gamma(a: f64, x: f64, ln_gamma_a: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x.is_infinite() {
+        return 1.0;
+    }
+    let prefactor = (a * x.ln() - x - ln_gamma_a).exp();
+    if x < a + 1.0 {
+        let mut term = a.recip();
+        let mut sum = term;
+        let mut n = a;
+        while term.abs() > sum.abs() * 1e-17 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+        }
+        return (prefactor * sum).min(1.0);
+    }
+
+    // Q(a, x) = prefactor / (x + 1 - a - 1 (1 - a) / (x + 3 - a - 2 (2 - a) / ...)),
+    // evaluated with the modified Lentz algorithm
+    let tiny = 1e-300;
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / tiny;
+    let mut d = b.recip();
+    let mut f = d;
+    for n in 1..1000 {
+        let n = n as f64;
+        let an = -n * (n - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < tiny {
+            d = tiny;
+        }
+        c = b + an / c;
+        if c.abs() < tiny {
+            c = tiny;
+        }
+        d = d.recip();
+        let delta = c * d;
+        f *= delta;
+        if (delta - 1.0).abs() < 1e-16 {
+            break;
+        }
+    }
+    (1.0 - prefactor * f).max(0.0)
+}
+
+/// The regularized incomplete beta function `I_x(a, b)`, given the logarithm
+/// of the beta function `B(a, b)`. The continued fraction converges quickly
+/// below the mean, so above it the symmetry `I_x(a, b) = 1 - I_(1-x)(b, a)`
+/// is used.
+fn regularized_beta(a: f64, b: f64, x: f64, ln_beta: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let prefactor = (a * x.ln() + b * (-x).ln_1p() - ln_beta).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        prefactor * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - prefactor * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// The continued fraction for `I_x(a, b)`, evaluated with the modified Lentz
+/// algorithm
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    let tiny = 1e-300;
+    let clamp = |v: f64| if v.abs() < tiny { tiny } else { v };
+    let mut c = 1.0;
+    let mut d = clamp(1.0 - (a + b) * x / (a + 1.0)).recip();
+    let mut f = d;
+    for m in 1..1000 {
+        let m = m as f64;
+        // Even step
+        let numerator = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = clamp(1.0 + numerator * d).recip();
+        c = clamp(1.0 + numerator / c);
+        f *= d * c;
+        // Odd step
+        let numerator = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = clamp(1.0 + numerator * d).recip();
+        c = clamp(1.0 + numerator / c);
+        let delta = d * c;
+        f *= delta;
+        if (delta - 1.0).abs() < 1e-16 {
+            break;
+        }
+    }
+    f
+}
+
+pub(crate) fn standard_normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / SQRT_2)
+}
+
+/// Acklam's rational approximation, refined to full precision with a step
+/// of Halley's method
+pub(crate) fn standard_normal_inverse_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e+01, 2.209460984245205e+02, -2.759285104469687e+02,
+        1.38357751867269e+02, -3.066479806614716e+01, 2.506628277459239e+00,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e+01, 1.615858368580409e+02, -1.556989798598866e+02,
+        6.680131188771972e+01, -1.328068155288572e+01,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-03, -3.223964580411365e-01, -2.400758277161838e+00,
+        -2.549732539343734e+00, 4.374664141464968e+00, 2.938163982698783e+00,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-03, 3.224671290700398e-01, 2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return f64::INFINITY;
+    }
+
+    let x = if p < P_LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        let q = (-2.0 * (1.0 - p).ln()).sqrt();
+        -(((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    let e = standard_normal_cdf(x) - p;
+    let u = e * (2.0 * PI).sqrt() * (x * x / 2.0).exp();
+    x - u / (1.0 + x * u / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Qrng;
+
+    #[test]
+    fn normal_accuracy() {
+        let erfc_values = [
+            (0.1, 0.8875370839817152),
+            (0.5, 0.4795001221869535),
+            (1.0, 0.15729920705028513),
+            (1.5, 0.033894853524689274),
+            (2.5, 0.0004069520174449589),
+            (4.0, 1.541725790028002e-08),
+            (6.0, 2.1519736712498916e-17),
+            (20.0, 5.3958656116079005e-176),
+        ];
+        for &(x, expected) in &erfc_values {
+            assert!((erfc(x) / expected - 1.0).abs() < 1e-14, "erfc({})", x);
+        }
+
+        let quantiles: [(f64, f64); 5] = [
+            (1e-10, -6.361340902404056),
+            (0.001, -3.090232306167813),
+            (0.3, -0.5244005127080407),
+            (0.9, 1.2815515655446008),
+            (0.999999, 4.753424308817089),
+        ];
+        let normal = Normal::standard();
+        for &(p, expected) in &quantiles {
+            // The quantile is only as precise as `p` itself
+            let density = (-expected * expected / 2.0).exp() / (2.0 * PI).sqrt();
+            let tolerance = 1e-14 + 4.0 * f64::EPSILON / density;
+            assert!((normal.inverse_cdf(p) - expected).abs() < tolerance, "quantile {}", p);
+        }
+    }
+
+    #[test]
+    fn exponential_and_weibull() {
+        let exponential = Exponential::new(2.0);
+        let weibull = Weibull::new(3.0, 2.0);
+        for &p in &[0.0, 1e-9, 0.1, 0.5, 0.9, 0.999_999] {
+            assert!((exponential.cdf(exponential.inverse_cdf(p)) - p).abs() < 1e-12);
+            assert!((weibull.cdf(weibull.inverse_cdf(p)) - p).abs() < 1e-12);
+        }
+        assert_eq!(exponential.cdf(-1.0), 0.0);
+        assert_eq!(Weibull::new(0.5, 1.0).inverse_cdf(0.3), exponential.inverse_cdf(0.3));
+
+        // The Weibull mean is scale * gamma(1 + 1 / shape) = 3 * sqrt(pi) / 2
+        let mut qrng = Qrng::<f64>::new(0.0);
+        let n = 100_000;
+        let mean = (0..n).map(|_| weibull.sample(&[qrng.gen()])).sum::<f64>() / n as f64;
+        assert!((mean - 1.5 * PI.sqrt()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn gamma_and_beta_inversion() {
+        // ln(gamma(n)) = ln((n - 1)!) and gamma(1/2) = sqrt(pi)
+        assert!((ln_gamma(10.0) - 362_880f64.ln()).abs() < 1e-12);
+        assert!((ln_gamma(0.5) - PI.sqrt().ln()).abs() < 1e-12);
+
+        let probabilities = [1e-9, 1e-4, 0.01, 0.2, 0.5, 0.8, 0.99, 0.9999];
+        for shape in [0.1, 0.5, 1.0, 2.5, 30.0, 500.0] {
+            let gamma = Gamma::new(shape, 2.0);
+            for p in probabilities {
+                let x = gamma.inverse_cdf(p);
+                assert!((gamma.cdf(x) - p).abs() < 1e-10 * p.max(1e-3), "{} {} {}", shape, p, x);
+            }
+        }
+        // Shape one is the exponential distribution
+        assert!((Gamma::new(1.0, 0.5).inverse_cdf(0.3) - Exponential::new(2.0).inverse_cdf(0.3)).abs() < 1e-12);
+
+        for (a, b) in [(0.2, 0.3), (0.5, 5.0), (1.0, 1.0), (2.0, 2.0), (3.0, 0.7), (40.0, 60.0)] {
+            let beta = Beta::new(a, b);
+            for p in probabilities {
+                let x = beta.inverse_cdf(p);
+                assert!((0.0..=1.0).contains(&x));
+                // Near 1, neighboring floats can differ a lot in probability
+                let next = f64::from_bits(x.to_bits() + 1).min(1.0);
+                let tolerance = 1e-10 * p.max(1e-3) + 2.0 * (beta.cdf(next) - beta.cdf(x));
+                assert!((beta.cdf(x) - p).abs() < tolerance, "{} {} {} {}", a, b, p, x);
+            }
+        }
+        assert!((Beta::new(1.0, 1.0).inverse_cdf(0.37) - 0.37).abs() < 1e-12);
+        assert!((Beta::new(2.0, 2.0).cdf(0.5) - 0.5).abs() < 1e-14);
+
+        // Sample means
+        let mut qrng = Qrng::<f64>::new(0.0);
+        let n = 20_000;
+        let gamma = Gamma::new(3.0, 2.0);
+        let beta = Beta::new(2.0, 6.0);
+        let (mut gamma_sum, mut beta_sum) = (0.0, 0.0);
+        for _ in 0..n {
+            let u = qrng.gen();
+            gamma_sum += gamma.sample(&[u]);
+            beta_sum += beta.sample(&[u]);
+        }
+        assert!((gamma_sum / n as f64 - 6.0).abs() < 1e-2);
+        assert!((beta_sum / n as f64 - 0.25).abs() < 1e-3);
+    }
+
+    #[test]
+    fn dirichlet_moments() {
+        // Weight i has mean alpha_i / total and variance
+        // mean_i (1 - mean_i) / (total + 1)
+        let alphas = [0.5, 2.0, 1.5, 4.0];
+        let total: f64 = alphas.iter().sum();
+        let dirichlet = Dirichlet::new(alphas.to_vec());
+        let mut qrng = Qrng::<[f64; 3]>::new(0.0);
+        let n = 20_000;
+        let mut sums = [0.0; 4];
+        let mut sum_squares = [0.0; 4];
+        for _ in 0..n {
+            let weights = dirichlet.sample(&qrng.gen());
+            assert!(weights.iter().all(|&w| w >= 0.0));
+            assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            for i in 0..4 {
+                sums[i] += weights[i];
+                sum_squares[i] += weights[i] * weights[i];
+            }
+        }
+        for i in 0..4 {
+            let mean = alphas[i] / total;
+            let variance = mean * (1.0 - mean) / (total + 1.0);
+            assert!((sums[i] / n as f64 - mean).abs() < 1e-3);
+            assert!((sum_squares[i] / n as f64 - mean * mean - variance).abs() < 1e-3);
+        }
+
+        // The tiny concentrations would vanish from a running total
+        let lopsided = Dirichlet::new(vec![1.0, 1e-17, 1e-20]);
+        let weights = lopsided.sample(&[0.5, 0.5]);
+        assert!(weights.iter().all(|&w| w >= 0.0));
+        assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn discrete_frequencies() {
+        fn check(distribution: &impl Distribution<Output = u64>, pmf: impl Fn(u64) -> f64) {
+            let mut qrng = Qrng::<f64>::new(0.0);
+            let n = 100_000;
+            let mut counts = std::collections::HashMap::new();
+            for _ in 0..n {
+                *counts.entry(distribution.sample(&[qrng.gen()])).or_insert(0) += 1;
+            }
+            for k in 0..40 {
+                let frequency = *counts.get(&k).unwrap_or(&0) as f64 / n as f64;
+                assert!((frequency - pmf(k)).abs() < 1e-4, "{} {} {}", k, frequency, pmf(k));
+            }
+        }
+        let factorial = |k: u64| (1..=k).map(|i| i as f64).product::<f64>();
+        check(&Poisson::new(3.5), |k| 3.5f64.powi(k as i32) * (-3.5f64).exp() / factorial(k));
+        check(&Binomial::new(20, 0.3), |k| {
+            if k > 20 {
+                return 0.0;
+            }
+            factorial(20) / factorial(k) / factorial(20 - k) * 0.3f64.powi(k as i32) * 0.7f64.powi(20 - k as i32)
+        });
+        check(&Geometric::new(0.2), |k| 0.2 * 0.8f64.powi(k as i32));
+
+        assert_eq!(Binomial::new(10, 1.0).sample(&[0.3]), 10);
+        assert_eq!(Binomial::new(10, 0.0).sample(&[0.3]), 0);
+        let large = Poisson::new(Poisson::MAX_MEAN);
+        assert!((large.sample(&[0.5]) as f64 - 1e6).abs() < 2.0);
+        let many = Binomial::new(Binomial::MAX_TRIALS, 0.5);
+        assert!((many.sample(&[0.5]) as f64 - 5e6).abs() < 2.0);
+        assert!(std::panic::catch_unwind(|| Poisson::new(Poisson::MAX_MEAN * 1.5)).is_err());
+        assert!(std::panic::catch_unwind(|| Binomial::new(Binomial::MAX_TRIALS + 1, 0.5)).is_err());
+    }
+
+    #[test]
+    fn truncated_normal() {
+        let half = Truncated::new(Normal::standard(), 0.0, f64::INFINITY);
+        let narrow = Truncated::new(Normal::new(1.0, 2.0), -0.5, 0.25);
+        let mut qrng = Qrng::<f64>::new(0.0);
+        let n = 100_000;
+        let mut sum = 0.0;
+        for _ in 0..n {
+            let u = qrng.gen();
+            let x = half.sample(&[u]);
+            assert!(x >= 0.0);
+            sum += x;
+            let y = narrow.sample(&[u]);
+            assert!((-0.5..=0.25).contains(&y));
+        }
+        assert!((sum / n as f64 - (2.0 / PI).sqrt()).abs() < 1e-4);
+
+        // Far in the upper tail, 1 - cdf would cancel to zero
+        let tail = Truncated::new(Normal::standard(), 9.0, f64::INFINITY);
+        let window = Truncated::new(Normal::new(1.0, 2.0), 25.0, 26.0);
+        let mut previous = 9.0;
+        for p in [0.0, 1e-6, 0.25, 0.5, 0.75, 0.999] {
+            let x = tail.inverse_cdf(p);
+            assert!(x >= previous, "{} at {}", x, p);
+            assert!((tail.cdf(x) - p).abs() < 1e-9, "{} at {}", tail.cdf(x), p);
+            previous = x;
+            let y = window.inverse_cdf(p);
+            assert!((25.0..=26.0).contains(&y));
+            assert!((window.cdf(y) - p).abs() < 1e-9);
+        }
+        // The tail beyond a is close to an exponential with rate a
+        assert!((tail.inverse_cdf(0.5) - 9.0 - 2f64.ln() / 9.0).abs() < 2e-3);
+        assert!(Truncated::new(Exponential::new(1.0), 50.0, 60.0).sample(&[0.5]) > 50.0);
+    }
+
+    #[test]
+    fn mixture_weights() {
+        let components: Vec<(f64, Box<dyn Distribution<Output = f64>>)> = vec![
+            (1.0, Box::new(Normal::new(-5.0, 1.0))),
+            (3.0, Box::new(Truncated::new(Normal::new(4.0, 1.0), 3.0, 5.0))),
+        ];
+        let mixture = Mixture::new(components);
+        assert_eq!(mixture.dimension(), 2);
+
+        let mut qrng = Qrng::<(f64, f64)>::new(0.0);
+        let n = 10_000;
+        let mut positive = 0;
+        let mut sum = 0.0;
+        for _ in 0..n {
+            let (u, v) = qrng.gen();
+            let x = mixture.sample(&[u, v]);
+            if x > 0.0 {
+                positive += 1;
+            }
+            sum += x;
+        }
+        assert!((positive as f64 / n as f64 - 0.75).abs() < 1e-3);
+        assert!((sum / n as f64 - (0.25 * -5.0 + 0.75 * 4.0)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn weighted_index_frequencies() {
+        let weights = [2.0, 0.0, 5.0, 1.0];
+        let index = WeightedIndex::new(&weights);
+        assert_eq!(index.len(), 4);
+        assert!((index.probability(2) - 0.625).abs() < 1e-12);
+
+        let mut qrng = Qrng::<f64>::new(0.0);
+        let n = 8000;
+        let mut counts = [0; 4];
+        for _ in 0..n {
+            counts[index.sample(&[qrng.gen()])] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        for (count, weight) in counts.iter().zip(weights) {
+            assert!((*count as f64 - n as f64 * weight / 8.0).abs() <= 2.0, "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn von_mises_fisher_mean_resultant_length() {
+        let mean = [1.0, -2.0, 0.5];
+        for &kappa in &[0.0, 1.0, 5.0, 50.0] {
+            let vmf = VonMisesFisher::new(mean, kappa);
+            let mut qrng = Qrng::<(f64, f64)>::new(0.2);
+            let n = 20_000;
+            let mut sum = [0.0; 3];
+            for _ in 0..n {
+                let (u, v) = qrng.gen();
+                let x = vmf.sample(&[u, v]);
+                for i in 0..3 {
+                    sum[i] += x[i];
+                }
+            }
+            let dot = (0..3).map(|i| sum[i] * vmf.mean()[i]).sum::<f64>() / n as f64;
+            let expected = if kappa == 0.0 { 0.0 } else { 1.0 / kappa.tanh() - 1.0 / kappa };
+            assert!((dot - expected).abs() < 1e-3, "kappa {}: {} vs {}", kappa, dot, expected);
+        }
+    }
+
+    #[test]
+    fn inverse_cdf_tables_interpolate() {
+        // Tabulating a known inverse CDF converges to it
+        let exponential = Truncated::new(Exponential::new(1.0), 0.0, 10.0);
+        let table = InverseCdf::from_fn(|p| exponential.inverse_cdf(p), 4096);
+        let mut qrng = Qrng::<f64>::new(0.5);
+        for _ in 0..1000 {
+            let p = qrng.gen();
+            assert!((exponential.cdf(table.inverse_cdf(p)) - p).abs() < 1e-4);
+            let x = table.inverse_cdf(p);
+            assert!((table.cdf(x) - p).abs() < 1e-9);
+        }
+        assert_eq!(table.inverse_cdf(1.0), exponential.inverse_cdf(1.0));
+
+        // Atoms and gaps
+        let table = InverseCdf::from_table(&[(0.0, 0.0), (1.0, 0.2), (1.0, 0.6), (4.0, 0.6), (5.0, 1.0)]);
+        assert_eq!(table.inverse_cdf(0.1), 0.5);
+        assert_eq!(table.inverse_cdf(0.2), 1.0);
+        assert_eq!(table.inverse_cdf(0.59), 1.0);
+        assert_eq!(table.inverse_cdf(0.6), 4.0);
+        assert_eq!(table.inverse_cdf(0.8), 4.5);
+        assert_eq!(table.cdf(1.0), 0.6);
+        assert_eq!(table.cdf(2.0), 0.6);
+        assert_eq!(table.cdf(-1.0), 0.0);
+        assert_eq!(table.cdf(5.0), 1.0);
+        assert_eq!(table.table().count(), 5);
+    }
+}
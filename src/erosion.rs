@@ -0,0 +1,88 @@
+//! Seeding droplets for hydraulic-erosion simulations.
+//!
+//! Particle-based erosion drops thousands of water droplets per iteration at
+//! random spots on a heightmap. With a pseudorandom generator the start
+//! positions clump within each batch, and the extra droplets in a clump carve
+//! visible streaks. `DropletSeeder` takes consecutive runs of a 2D `Qrng`
+//! instead, so every batch covers the map evenly on its own and all batches
+//! together keep covering it evenly.
+
+use crate::Qrng;
+
+/// Produces batches of droplet start positions on a `width` by `height` map.
+///
+/// ```
+/// use quasirandom::erosion::DropletSeeder;
+///
+/// let mut seeder = DropletSeeder::new(512, 512, 0.5);
+/// for _ in 0..10 {
+///     for [x, y] in seeder.next_batch(4096) {
+///         let (cell_x, cell_y) = (x as usize, y as usize);
+///         assert!(cell_x < 512 && cell_y < 512);
+///         // ... simulate a droplet starting at (x, y)
+///     }
+/// }
+/// assert_eq!(seeder.batches(), 10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DropletSeeder {
+    qrng: Qrng<[f64; 2]>,
+    width: f64,
+    height: f64,
+    batches: u64,
+}
+
+impl DropletSeeder {
+    pub fn new(width: usize, height: usize, seed: f64) -> Self {
+        assert!(width > 0);
+        assert!(height > 0);
+        Self {
+            qrng: Qrng::<[f64; 2]>::new(seed),
+            width: width as f64,
+            height: height as f64,
+            batches: 0,
+        }
+    }
+
+    /// Returns the start positions of the next `count` droplets, with `x` in
+    /// `[0, width)` and `y` in `[0, height)`.
+    pub fn next_batch(&mut self, count: usize) -> Vec<[f64; 2]> {
+        let mut batch = vec![[0.0; 2]; count];
+        self.fill_batch(&mut batch);
+        batch
+    }
+
+    /// Like `next_batch`, but writes the positions into an existing buffer.
+    pub fn fill_batch(&mut self, batch: &mut [[f64; 2]]) {
+        for position in batch {
+            let [u, v] = self.qrng.gen();
+            *position = [u * self.width, v * self.height];
+        }
+        self.batches += 1;
+    }
+
+    /// The number of batches produced so far
+    pub fn batches(&self) -> u64 {
+        self.batches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_batch_covers_the_map() {
+        // Each batch of 1024 droplets puts 16 in each of the 64 blocks of a
+        // 64x64 map, give or take a few
+        let mut seeder = DropletSeeder::new(64, 64, 0.0);
+        for _ in 0..4 {
+            let mut blocks = [0; 64];
+            for [x, y] in seeder.next_batch(1024) {
+                assert!((0.0..64.0).contains(&x) && (0.0..64.0).contains(&y));
+                blocks[(y as usize / 8) * 8 + x as usize / 8] += 1;
+            }
+            assert!(blocks.iter().all(|&count| (count - 16_i32).abs() <= 3), "{:?}", blocks);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+//! The sequence in arbitrary-precision fixed point, with the `exact` feature.
+//!
+//! `Qrng` and `RSequence` keep their state in 64-bit fixed point, so the
+//! recurrence itself is exact, but the generating constants are irrational
+//! and rounded to 64 bits, and the rounding error grows linearly with the
+//! index. `ExactSequence` computes the constants to any number of 64-bit
+//! words, straight from their defining polynomial, and advances with the
+//! same wrapping additions at that width. Its points agree with the true
+//! sequence to within `error_bound`, which makes it a reference for
+//! validating the fast generators and for studying discrepancy at sample
+//! counts where 64 bits of constant are no longer enough.
+//!
+//! It is far slower than `RSequence`, and meant for checking rather than
+//! production use.
+
+use crate::sequences::{Capabilities, UniformSequence};
+use crate::{from_fixed, to_fixed};
+
+/// The recurrence of `RSequence` with generating constants of `words * 64`
+/// bits.
+///
+/// Every coordinate is a fraction of `2^(64 * words)`, stored as words from
+/// most to least significant. The `f64` outputs are the top 53 bits, as in
+/// `Qrng`.
+///
+/// ```
+/// use quasirandom::exact::ExactSequence;
+/// use quasirandom::sequences::{RSequence, UniformSequence};
+///
+/// // Compare a million points of the fast generator with 256-bit arithmetic
+/// let mut exact = ExactSequence::new(2, 0.0, 4);
+/// let mut fast = RSequence::new(2, 0.0);
+/// let (mut a, mut b) = ([0.0; 2], [0.0; 2]);
+/// let mut worst: f64 = 0.0;
+/// for _ in 0..1_000_000 {
+///     exact.next_into(&mut a);
+///     fast.next_into(&mut b);
+///     for d in 0..2 {
+///         let difference = (a[d] - b[d]).abs();
+///         worst = worst.max(difference.min(1.0 - difference));
+///     }
+/// }
+/// assert!(worst < 1e-7);
+/// assert!(exact.error_bound() < 1e-70);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExactSequence {
+    words: usize,
+    alphas: Vec<Vec<u64>>,
+    start: Vec<Vec<u64>>,
+    state: Vec<Vec<u64>>,
+    index: u64,
+}
+
+impl ExactSequence {
+    /// Starts every dimension `i` at `i * seed`, modulo one, like
+    /// `RSequence::new`, with `words` 64-bit words of fraction.
+    pub fn new(dimension: usize, seed: f64, words: usize) -> Self {
+        assert!(dimension > 0);
+        assert!(words > 0);
+        assert!(seed >= 0.0);
+        assert!(seed < 1.0);
+        let mut seed_words = vec![0; words];
+        seed_words[0] = to_fixed(seed);
+        let start: Vec<Vec<u64>> = (0..dimension as u64)
+            .map(|i| {
+                let mut x = seed_words.clone();
+                mul_small(&mut x, i);
+                x
+            })
+            .collect();
+        Self {
+            words,
+            alphas: alphas(dimension, words),
+            state: start.clone(),
+            start,
+            index: 0,
+        }
+    }
+
+    /// The number of 64-bit words of fraction
+    pub fn words(&self) -> usize {
+        self.words
+    }
+
+    /// The generating constant of dimension `d`, most significant word first
+    pub fn alpha(&self, d: usize) -> &[u64] {
+        &self.alphas[d]
+    }
+
+    /// Coordinate `d` of the last point, most significant word first
+    pub fn coordinate(&self, d: usize) -> &[u64] {
+        &self.state[d]
+    }
+
+    /// The number of points generated so far
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Moves to just after point `index - 1`, so the next point is number
+    /// `index`. The state is exactly the one reached by stepping.
+    pub fn set_index(&mut self, index: u64) {
+        for ((x, start), alpha) in self.state.iter_mut().zip(&self.start).zip(&self.alphas) {
+            x.copy_from_slice(alpha);
+            mul_small(x, index);
+            add(x, start);
+        }
+        self.index = index;
+    }
+
+    /// A bound on the distance, modulo one, between every coordinate of the
+    /// last point and that of the sequence with the true irrational
+    /// constants. Each constant is within `2^(-64 * words)` of its true
+    /// value, so the bound grows by that much per point.
+    pub fn error_bound(&self) -> f64 {
+        self.index as f64 * 2f64.powi(-64 * self.words as i32)
+    }
+}
+
+impl UniformSequence for ExactSequence {
+    fn dimension(&self) -> usize {
+        self.state.len()
+    }
+
+    fn next_into(&mut self, out: &mut [f64]) -> bool {
+        assert_eq!(out.len(), self.state.len());
+        for ((x, alpha), out) in self.state.iter_mut().zip(&self.alphas).zip(out) {
+            add(x, alpha);
+            *out = from_fixed(x[0]);
+        }
+        self.index += 1;
+        true
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_dimension: None,
+            progressive: true,
+            scrambling: false,
+            random_access: true,
+        }
+    }
+}
+
+/// The generating constants `g^1, ..., g^dimension`, rounded down to `words`
+/// words, where `g` is the root in `(0, 1)` of `g^dimension * (1 + g) = 1`,
+/// the inverse of the root of `x^(dimension + 1) = x + 1`.
+///
+/// The root is found bit by bit with one guard word, which absorbs the
+/// truncation of the products.
+fn alphas(dimension: usize, words: usize) -> Vec<Vec<u64>> {
+    let wide = words + 1;
+    let mut root = vec![0; wide];
+    for bit in 0..64 * wide {
+        root[bit / 64] |= 1 << (63 - bit % 64);
+        // g^dimension * (1 + g) below one means the root is at least g
+        let mut power = root.clone();
+        for _ in 1..dimension {
+            power = mul(&power, &root);
+        }
+        let mut sum = mul(&power, &root);
+        if add(&mut sum, &power) {
+            root[bit / 64] &= !(1 << (63 - bit % 64));
+        }
+    }
+    let mut power = root.clone();
+    (0..dimension)
+        .map(|k| {
+            if k > 0 {
+                power = mul(&power, &root);
+            }
+            power[..words].to_vec()
+        })
+        .collect()
+}
+
+/// Adds `b` to `a` modulo one, returning whether the sum wrapped
+fn add(a: &mut [u64], b: &[u64]) -> bool {
+    let mut carry = false;
+    for (a, &b) in a.iter_mut().zip(b).rev() {
+        let (sum, first) = a.overflowing_add(b);
+        let (sum, second) = sum.overflowing_add(carry as u64);
+        *a = sum;
+        carry = first || second;
+    }
+    carry
+}
+
+/// Multiplies `a` by `n` modulo one
+fn mul_small(a: &mut [u64], n: u64) {
+    let mut carry = 0;
+    for a in a.iter_mut().rev() {
+        let product = *a as u128 * n as u128 + carry;
+        *a = product as u64;
+        carry = product >> 64;
+    }
+}
+
+/// The product of two fractions of the same width, rounded down
+fn mul(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let n = a.len();
+    // The full product, least significant word first
+    let mut full = vec![0u64; 2 * n];
+    for (i, &a) in a.iter().rev().enumerate() {
+        let mut carry = 0u128;
+        for (j, &b) in b.iter().rev().enumerate() {
+            let sum = a as u128 * b as u128 + full[i + j] as u128 + carry;
+            full[i + j] = sum as u64;
+            carry = sum >> 64;
+        }
+        full[i + n] = carry as u64;
+    }
+    full[n..].iter().rev().copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CONSTANTS;
+
+    #[test]
+    fn constants_and_steps_are_exact() {
+        // The golden ratio conjugate, (sqrt(5) - 1) / 2, to 128 bits
+        let golden = ExactSequence::new(1, 0.0, 2);
+        assert_eq!(golden.alpha(0), [0x9e37_79b9_7f4a_7c15, 0xf39c_c060_5ced_c834]);
+
+        // Wider constants extend narrower ones
+        let wide = ExactSequence::new(5, 0.0, 4);
+        let narrow = ExactSequence::new(5, 0.0, 1);
+        for (d, constant) in CONSTANTS[4][..5].iter().enumerate() {
+            assert_eq!(wide.alpha(d)[0], narrow.alpha(d)[0]);
+            assert!((from_fixed(wide.alpha(d)[0]) - constant).abs() < 1e-13);
+        }
+
+        // Jumping lands exactly where stepping does
+        let mut stepped = ExactSequence::new(3, 0.75, 3);
+        let mut point = [0.0; 3];
+        for _ in 0..1000 {
+            stepped.next_into(&mut point);
+        }
+        let mut jumped = ExactSequence::new(3, 0.75, 3);
+        jumped.set_index(1000);
+        assert_eq!(jumped, stepped);
+        assert_eq!(stepped.error_bound(), 1000.0 * 2f64.powi(-192));
+    }
+}
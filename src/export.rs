@@ -0,0 +1,386 @@
+//! Writing point sets to disk.
+//!
+//! Point sets with billions of points do not fit in memory, so everything here
+//! streams points from a `UniformSequence` straight to files.
+//!
+//! `write_chunked` splits the points over several binary files and records
+//! their layout in a plain-text manifest. With the `mmap` feature,
+//! `write_mmap` fills a single memory-mapped file instead.
+//!
+//! Smaller point sets can be collected into a `PointSet` and written as CSV
+//! or as a NumPy `.npy` file, for analysis in pandas or matplotlib.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::sequences::UniformSequence;
+
+/// The name of the manifest file written by `write_chunked`
+pub const MANIFEST_FILE_NAME: &str = "manifest.txt";
+
+/// Describes a point set written by `write_chunked`.
+///
+/// Every chunk file holds its points back to back, each point as
+/// `dimension` little-endian `f64`s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub dimension: usize,
+    pub points: u64,
+    pub points_per_chunk: u64,
+    pub chunks: Vec<Chunk>,
+}
+
+/// One binary file of a chunked point set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// The file name, relative to the manifest's directory
+    pub file_name: String,
+    /// The sequence index of the first point in the file
+    pub first_index: u64,
+    pub points: u64,
+}
+
+impl Manifest {
+    /// Reads `manifest.txt` from `directory`.
+    pub fn read(directory: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(directory.as_ref().join(MANIFEST_FILE_NAME))?;
+        let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, format!("bad manifest line: {}", line));
+
+        let mut manifest = Manifest {
+            dimension: 0,
+            points: 0,
+            points_per_chunk: 0,
+            chunks: vec![],
+        };
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |i: usize| -> io::Result<u64> {
+                fields.get(i).and_then(|f| f.parse().ok()).ok_or_else(|| invalid(&line))
+            };
+            match fields.first() {
+                Some(&"format") | Some(&"encoding") | None => {}
+                Some(&"dimension") => manifest.dimension = number(1)? as usize,
+                Some(&"points") => manifest.points = number(1)?,
+                Some(&"points_per_chunk") => manifest.points_per_chunk = number(1)?,
+                Some(&"chunk") => manifest.chunks.push(Chunk {
+                    file_name: fields.get(1).ok_or_else(|| invalid(&line))?.to_string(),
+                    first_index: number(2)?,
+                    points: number(3)?,
+                }),
+                Some(_) => return Err(invalid(&line)),
+            }
+        }
+        Ok(manifest)
+    }
+
+    fn write(&self, directory: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(directory.join(MANIFEST_FILE_NAME))?);
+        writeln!(out, "format quasirandom-points 1")?;
+        writeln!(out, "encoding f64-le row-major")?;
+        writeln!(out, "dimension {}", self.dimension)?;
+        writeln!(out, "points {}", self.points)?;
+        writeln!(out, "points_per_chunk {}", self.points_per_chunk)?;
+        for chunk in &self.chunks {
+            writeln!(out, "chunk {} {} {}", chunk.file_name, chunk.first_index, chunk.points)?;
+        }
+        out.flush()
+    }
+}
+
+/// Streams `count` points from `sequence` into binary files of at most
+/// `points_per_chunk` points each inside `directory`, which is created if
+/// needed, and writes a manifest describing them.
+///
+/// Stops early if a finite sequence runs out of points; the manifest records
+/// how many were written. Only one point is held in memory at a time.
+pub fn write_chunked<S: UniformSequence + ?Sized>(
+    sequence: &mut S,
+    count: u64,
+    points_per_chunk: u64,
+    directory: impl AsRef<Path>,
+) -> io::Result<Manifest> {
+    assert!(points_per_chunk > 0);
+    let directory = directory.as_ref();
+    std::fs::create_dir_all(directory)?;
+
+    let dimension = sequence.dimension();
+    let mut point = vec![0.0; dimension];
+    let mut manifest = Manifest {
+        dimension,
+        points: 0,
+        points_per_chunk,
+        chunks: vec![],
+    };
+
+    'chunks: while manifest.points < count {
+        let file_name = format!("points-{:06}.bin", manifest.chunks.len());
+        let mut out = BufWriter::new(File::create(directory.join(&file_name))?);
+        let mut chunk = Chunk {
+            file_name,
+            first_index: manifest.points,
+            points: 0,
+        };
+        while chunk.points < points_per_chunk && manifest.points < count {
+            if !sequence.next_into(&mut point) {
+                out.flush()?;
+                if chunk.points > 0 {
+                    manifest.chunks.push(chunk);
+                } else {
+                    std::fs::remove_file(directory.join(&chunk.file_name))?;
+                }
+                break 'chunks;
+            }
+            for x in &point {
+                out.write_all(&x.to_le_bytes())?;
+            }
+            chunk.points += 1;
+            manifest.points += 1;
+        }
+        out.flush()?;
+        manifest.chunks.push(chunk);
+    }
+
+    manifest.write(directory)?;
+    Ok(manifest)
+}
+
+/// Points held in memory, row-major, for writing to common analysis formats.
+///
+/// ```no_run
+/// use quasirandom::export::PointSet;
+/// use quasirandom::sequences::RSequence;
+///
+/// let points = PointSet::from_sequence(&mut RSequence::new(2, 0.5), 1000);
+/// points.write_npy("points.npy")?;
+/// points.write_csv("points.csv")?;
+/// // In Python: numpy.load("points.npy") or pandas.read_csv("points.csv")
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointSet {
+    dimension: usize,
+    values: Vec<f64>,
+}
+
+impl PointSet {
+    /// Takes up to `count` points from `sequence`, fewer if a finite
+    /// sequence runs out.
+    pub fn from_sequence<S: UniformSequence + ?Sized>(sequence: &mut S, count: usize) -> Self {
+        let dimension = sequence.dimension();
+        let mut values = vec![0.0; count * dimension];
+        let mut rows = 0;
+        for row in values.chunks_exact_mut(dimension) {
+            if !sequence.next_into(row) {
+                break;
+            }
+            rows += 1;
+        }
+        values.truncate(rows * dimension);
+        Self { dimension, values }
+    }
+
+    /// Copies `points`, which must all have length `dimension`.
+    pub fn from_points<P: AsRef<[f64]>>(dimension: usize, points: &[P]) -> Self {
+        assert!(dimension > 0);
+        let mut values = Vec::with_capacity(points.len() * dimension);
+        for point in points {
+            assert_eq!(point.as_ref().len(), dimension);
+            values.extend_from_slice(point.as_ref());
+        }
+        Self { dimension, values }
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// The number of points
+    pub fn len(&self) -> usize {
+        self.values.len() / self.dimension
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Point `index`
+    pub fn point(&self, index: usize) -> &[f64] {
+        &self.values[index * self.dimension..(index + 1) * self.dimension]
+    }
+
+    /// Writes a header line `x0,x1,...` and then one point per line, with
+    /// every value in its shortest round-trip form.
+    pub fn to_csv(&self, mut out: impl Write) -> io::Result<()> {
+        let header: Vec<String> = (0..self.dimension).map(|i| format!("x{}", i)).collect();
+        writeln!(out, "{}", header.join(","))?;
+        for point in self.values.chunks_exact(self.dimension) {
+            let fields: Vec<String> = point.iter().map(|x| format!("{:?}", x)).collect();
+            writeln!(out, "{}", fields.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// Writes an `.npy` file (format version 1.0) holding an array of shape
+    /// `(len, dimension)` of little-endian `f64`s.
+    pub fn to_npy(&self, mut out: impl Write) -> io::Result<()> {
+        let mut header = format!(
+            "{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {}), }}",
+            self.len(),
+            self.dimension
+        );
+        // The magic string, version, and header length take 10 bytes, and
+        // the header is padded with spaces and a newline to align the data
+        // to 64 bytes
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+        out.write_all(b"\x93NUMPY\x01\x00")?;
+        out.write_all(&(header.len() as u16).to_le_bytes())?;
+        out.write_all(header.as_bytes())?;
+        for x in &self.values {
+            out.write_all(&x.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.to_csv(&mut out)?;
+        out.flush()
+    }
+
+    pub fn write_npy(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.to_npy(&mut out)?;
+        out.flush()
+    }
+}
+
+/// Fills a single file with `count` points from `sequence`, through a memory
+/// map, as back-to-back little-endian `f64`s. The file is created (or
+/// truncated) with its final size up front.
+///
+/// Returns the number of points written, which is less than `count` only if
+/// a finite sequence runs out; the file is then truncated to fit.
+#[cfg(feature = "mmap")]
+pub fn write_mmap<S: UniformSequence + ?Sized>(
+    sequence: &mut S,
+    count: u64,
+    path: impl AsRef<Path>,
+) -> io::Result<u64> {
+    let dimension = sequence.dimension();
+    let point_bytes = dimension as u64 * 8;
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    file.set_len(count * point_bytes)?;
+    if count == 0 {
+        return Ok(0);
+    }
+
+    // SAFETY: the file was just created by us and is not expected to be
+    // modified by other processes while it is mapped.
+    let mut map = unsafe { memmap2::MmapMut::map_mut(&file)? };
+    let mut point = vec![0.0; dimension];
+    let mut written = 0;
+    for bytes in map.chunks_exact_mut(point_bytes as usize) {
+        if !sequence.next_into(&mut point) {
+            break;
+        }
+        for (out, x) in bytes.chunks_exact_mut(8).zip(&point) {
+            out.copy_from_slice(&x.to_le_bytes());
+        }
+        written += 1;
+    }
+    map.flush()?;
+    drop(map);
+    if written < count {
+        file.set_len(written * point_bytes)?;
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequences::{Hammersley, RSequence};
+
+    #[test]
+    fn chunked_round_trip() {
+        let directory = std::env::temp_dir().join(format!("quasirandom-chunked-{}", std::process::id()));
+        let manifest = write_chunked(&mut RSequence::new(3, 0.5), 1000, 300, &directory).unwrap();
+        assert_eq!(manifest, Manifest::read(&directory).unwrap());
+        assert_eq!(manifest.chunks.len(), 4);
+        assert_eq!(manifest.chunks[3].points, 100);
+
+        let mut expected = RSequence::new(3, 0.5);
+        let mut point = [0.0; 3];
+        for chunk in &manifest.chunks {
+            let bytes = std::fs::read(directory.join(&chunk.file_name)).unwrap();
+            assert_eq!(bytes.len() as u64, chunk.points * 24);
+            for x in bytes.chunks_exact(24) {
+                expected.next_into(&mut point);
+                for (i, coordinate) in x.chunks_exact(8).enumerate() {
+                    assert_eq!(f64::from_le_bytes(coordinate.try_into().unwrap()), point[i]);
+                }
+            }
+        }
+
+        let manifest = write_chunked(&mut Hammersley::points(600, 2), 1000, 300, &directory).unwrap();
+        assert_eq!(manifest.points, 600);
+        assert_eq!(manifest.chunks.len(), 2);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_matches_sequence() {
+        let path = std::env::temp_dir().join(format!("quasirandom-mmap-{}.bin", std::process::id()));
+        assert_eq!(write_mmap(&mut Hammersley::points(50, 2), 80, &path).unwrap(), 50);
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 50 * 16);
+
+        let mut expected = Hammersley::points(50, 2);
+        let mut point = [0.0; 2];
+        for x in bytes.chunks_exact(16) {
+            expected.next_into(&mut point);
+            assert_eq!(f64::from_le_bytes(x[8..].try_into().unwrap()), point[1]);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn csv_and_npy() {
+        let points = PointSet::from_sequence(&mut Hammersley::points(5, 3), 10);
+        assert_eq!(points.len(), 5);
+        assert_eq!(points.point(1), [0.2, 0.5, 1.0 / 3.0]);
+
+        let mut csv = vec![];
+        points.to_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "x0,x1,x2");
+        assert_eq!(lines[2], "0.2,0.5,0.3333333333333333");
+        assert_eq!(lines.len(), 6);
+
+        let mut npy = vec![];
+        points.to_npy(&mut npy).unwrap();
+        assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+        let header_length = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+        assert_eq!((10 + header_length) % 64, 0);
+        let header = std::str::from_utf8(&npy[10..10 + header_length]).unwrap();
+        assert!(header.contains("'shape': (5, 3)") && header.ends_with('\n'));
+        let data = &npy[10 + header_length..];
+        assert_eq!(data.len(), 5 * 3 * 8);
+        assert_eq!(f64::from_le_bytes(data[24..32].try_into().unwrap()), 0.2);
+
+        let copied = PointSet::from_points(3, &[[0.0, 0.0, 0.0], [0.2, 0.5, 1.0 / 3.0]]);
+        assert_eq!(copied.point(1), points.point(1));
+    }
+}
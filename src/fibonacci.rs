@@ -0,0 +1,322 @@
+//! Fibonacci (golden-spiral) point sets.
+//!
+//! For a known number of points, the golden spiral spaces directions on the
+//! sphere more evenly than any prefix of a sequence: the points sit at
+//! equally spaced heights, and successive points turn by the golden angle
+//! around the axis so that no two line up.
+//!
+//! `TorusLattice` and `SphereLattice` are the indexed forms of the Fibonacci
+//! lattices on the unit torus and the sphere. Besides mapping an index to its
+//! point, they find the index of the lattice point nearest to any point,
+//! which turns a lattice into a lookup structure: binning directions,
+//! snapping to the nearest probe, or quantizing normals to an index.
+
+use std::f64::consts::PI;
+
+use crate::CONSTANTS;
+
+/// `n` evenly spaced unit vectors on the golden spiral.
+///
+/// Point `i` sits at height `z = 1 - (2i + 1) / n`, which splits the sphere
+/// into bands of equal area, and at the azimuth `2 pi (seed + i / phi)`
+/// modulo `2 pi`. Different seeds in `[0, 1)` rotate the whole set about the
+/// `z` axis, e.g. to decorrelate neighboring pixels.
+///
+/// ```
+/// use quasirandom::fibonacci::sphere_points;
+///
+/// // Ambient occlusion: the fraction of 256 directions above the horizon
+/// // that are not blocked by a wall at x = 0.5
+/// let directions = sphere_points(256, 0.0);
+/// let upper: Vec<_> = directions.iter().filter(|d| d[2] > 0.0).collect();
+/// assert_eq!(upper.len(), 128);
+/// let open = upper.iter().filter(|d| d[0] < 0.5).count() as f64 / 128.0;
+/// assert!(open > 0.5 && open < 1.0);
+/// ```
+pub fn sphere_points(n: usize, seed: f64) -> Vec<[f64; 3]> {
+    assert!((0.0..1.0).contains(&seed));
+    let alpha = CONSTANTS[0][0];
+    (0..n)
+        .map(|i| {
+            let z = 1.0 - (2 * i + 1) as f64 / n as f64;
+            let r = (1.0 - z * z).max(0.0).sqrt();
+            let phi = 2.0 * PI * (seed + i as f64 * alpha).fract();
+            [r * phi.cos(), r * phi.sin(), z]
+        })
+        .collect()
+}
+
+/// The Fibonacci lattice on the unit torus `[0, 1)^2`: the `F(k)` points
+/// `(i / F(k), frac(i F(k - 1) / F(k)))`, the same points as
+/// `sequences::Lattice::fibonacci(k)`.
+///
+/// ```
+/// use quasirandom::fibonacci::TorusLattice;
+///
+/// let lattice = TorusLattice::new(20);
+/// assert_eq!(lattice.len(), 6765);
+/// let point = lattice.point(1234);
+/// assert_eq!(lattice.nearest([point[0] + 1e-4, point[1] - 1e-4]), 1234);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TorusLattice {
+    count: u64,
+    step: u64,
+    /// A reduced basis of the lattice scaled by `count`, which includes the
+    /// periodic copies of the points
+    basis: [[i64; 2]; 2],
+}
+
+impl TorusLattice {
+    /// The lattice with `F(k)` points, where `F(1) = F(2) = 1`. `k` must be
+    /// between 2 and 60.
+    pub fn new(k: u32) -> Self {
+        assert!((2..=60).contains(&k));
+        let (mut previous, mut current) = (1u64, 1u64);
+        for _ in 2..k {
+            (previous, current) = (current, previous + current);
+        }
+        Self {
+            count: current,
+            step: previous,
+            basis: reduce([[1, previous as i64], [0, current as i64]]),
+        }
+    }
+
+    /// The number of points
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Point `index`, which must be less than `len()`
+    pub fn point(&self, index: u64) -> [f64; 2] {
+        assert!(index < self.count);
+        let y = (index as u128 * self.step as u128 % self.count as u128) as f64;
+        [index as f64 / self.count as f64, y / self.count as f64]
+    }
+
+    /// The index of the lattice point nearest to `point`, measuring distance
+    /// on the torus
+    pub fn nearest(&self, point: [f64; 2]) -> u64 {
+        let n = self.count as f64;
+        let target = point.map(|x| x.rem_euclid(1.0) * n);
+        let [b1, b2] = self.basis.map(|b| b.map(|x| x as f64));
+        // Round the coordinates of `target` in the reduced basis, then check
+        // the lattice vectors around the rounded one
+        let determinant = b1[0] * b2[1] - b1[1] * b2[0];
+        let a = ((target[0] * b2[1] - target[1] * b2[0]) / determinant).round() as i64;
+        let b = ((b1[0] * target[1] - b1[1] * target[0]) / determinant).round() as i64;
+        let mut best = (f64::INFINITY, 0);
+        for da in -1..=1 {
+            for db in -1..=1 {
+                let (a, b) = (a + da, b + db);
+                let x = a * self.basis[0][0] + b * self.basis[1][0];
+                let y = a * self.basis[0][1] + b * self.basis[1][1];
+                let distance = (x as f64 - target[0]).powi(2) + (y as f64 - target[1]).powi(2);
+                if distance < best.0 {
+                    best = (distance, x.rem_euclid(self.count as i64) as u64);
+                }
+            }
+        }
+        best.1
+    }
+}
+
+/// Lagrange-Gauss reduction of a basis of a 2D integer lattice, leaving
+/// two shortest, nearly orthogonal basis vectors
+fn reduce([mut u, mut v]: [[i64; 2]; 2]) -> [[i64; 2]; 2] {
+    let norm = |w: [i64; 2]| w[0] as i128 * w[0] as i128 + w[1] as i128 * w[1] as i128;
+    if norm(u) > norm(v) {
+        std::mem::swap(&mut u, &mut v);
+    }
+    loop {
+        let dot = u[0] as i128 * v[0] as i128 + u[1] as i128 * v[1] as i128;
+        let q = (dot as f64 / norm(u) as f64).round() as i64;
+        v = [v[0] - q * u[0], v[1] - q * u[1]];
+        if norm(v) >= norm(u) {
+            return [u, v];
+        }
+        std::mem::swap(&mut u, &mut v);
+    }
+}
+
+/// The spherical Fibonacci point set of `sphere_points` with seed zero, with
+/// the inverse mapping of Keinert et al., "Spherical Fibonacci Mapping"
+/// (2015), to find the point nearest to any direction in constant time.
+///
+/// ```
+/// use quasirandom::fibonacci::SphereLattice;
+///
+/// // Quantize normals to 16-bit indices
+/// let lattice = SphereLattice::new(65536);
+/// let normal = [0.48, -0.6, 0.64];
+/// let index = lattice.nearest(normal);
+/// let [x, y, z] = lattice.point(index);
+/// assert!((x - 0.48).abs() < 0.01 && (y + 0.6).abs() < 0.01 && (z - 0.64).abs() < 0.01);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SphereLattice {
+    count: u64,
+}
+
+impl SphereLattice {
+    pub fn new(count: u64) -> Self {
+        assert!(count > 0);
+        assert!(count < 1 << 40, "the inverse mapping loses precision beyond 2^40 points");
+        Self { count }
+    }
+
+    /// The number of points
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Point `index`, which must be less than `len()`
+    pub fn point(&self, index: u64) -> [f64; 3] {
+        assert!(index < self.count);
+        let z = 1.0 - (2 * index + 1) as f64 / self.count as f64;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * (index as f64 * CONSTANTS[0][0]).fract();
+        [r * phi.cos(), r * phi.sin(), z]
+    }
+
+    /// The index of the point nearest to `direction`, which need not be
+    /// normalized but must not be zero
+    pub fn nearest(&self, direction: [f64; 3]) -> u64 {
+        let length = direction.iter().map(|x| x * x).sum::<f64>().sqrt();
+        assert!(length > 0.0);
+        let p = direction.map(|x| x / length);
+        let n = self.count as f64;
+        let golden = 1.0 / CONSTANTS[0][0];
+
+        // Near height z, the points form a lattice in (phi, z) whose basis
+        // corresponds to two consecutive Fibonacci numbers of index steps
+        let phi = p[1].atan2(p[0]);
+        let z = p[2];
+        let k = ((n * PI * 5f64.sqrt() * (1.0 - z * z)).ln() / (golden * golden).ln()).floor().max(2.0);
+        let fk = golden.powf(k) / 5f64.sqrt();
+        let f = [fk.round(), (fk * golden).round()];
+        let ka = f.map(|f| -2.0 * f / n);
+        let kb = f.map(|f| 2.0 * PI * ((f + 1.0) * golden).fract() - 2.0 * PI * (golden - 1.0));
+        let determinant = ka[1] * kb[0] - ka[0] * kb[1];
+        let offset = z - (1.0 - 1.0 / n);
+        let c = [
+            ((ka[1] * phi - kb[1] * offset) / determinant).floor(),
+            ((-ka[0] * phi + kb[0] * offset) / determinant).floor(),
+        ];
+
+        let mut best = (f64::INFINITY, 0);
+        for du in -1..=2 {
+            for dv in -1..=2 {
+                let i = f[0] * (c[0] + du as f64) + f[1] * (c[1] + dv as f64);
+                if !(0.0..n).contains(&i) {
+                    continue;
+                }
+                let q = self.point(i as u64);
+                let distance: f64 = (0..3).map(|j| (q[j] - p[j]).powi(2)).sum();
+                if distance < best.0 {
+                    best = (distance, i as u64);
+                }
+            }
+        }
+        if best.0.is_infinite() {
+            // Only at the very poles, where the nearest point is the first or
+            // the last
+            best.1 = if z > 0.0 { 0 } else { self.count - 1 };
+        }
+        best.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sphere_points_are_even() {
+        let n = 1000;
+        let points = sphere_points(n, 0.25);
+        assert_eq!(points.len(), n);
+        let mut mean = [0.0; 3];
+        let mut octants = [0; 8];
+        for p in &points {
+            assert!((p.iter().map(|x| x * x).sum::<f64>() - 1.0).abs() < 1e-12);
+            (0..3).for_each(|k| mean[k] += p[k] / n as f64);
+            octants[(p[0] > 0.0) as usize + 2 * (p[1] > 0.0) as usize + 4 * (p[2] > 0.0) as usize] += 1;
+        }
+        assert!(mean.iter().all(|x| x.abs() < 1e-3), "{:?}", mean);
+        assert!(octants.iter().all(|&count| (count - 125_i32).abs() <= 3), "{:?}", octants);
+
+        // No two points are much closer than the average spacing
+        let spacing = (4.0 * PI / n as f64).sqrt();
+        for (i, p) in points.iter().enumerate() {
+            for q in &points[i + 1..] {
+                let distance = (0..3).map(|k| (p[k] - q[k]).powi(2)).sum::<f64>().sqrt();
+                assert!(distance > 0.5 * spacing);
+            }
+        }
+
+        // Adding a quarter to the seed turns the set a quarter turn about the
+        // z axis
+        let rotated = sphere_points(n, 0.5);
+        for (p, q) in points.iter().zip(&rotated) {
+            assert!((q[0] + p[1]).abs() < 1e-9 && (q[1] - p[0]).abs() < 1e-9 && q[2] == p[2]);
+        }
+    }
+
+    #[test]
+    fn torus_nearest_matches_brute_force() {
+        let lattice = TorusLattice::new(14);
+        assert_eq!(lattice.len(), 377);
+        let mut reference = crate::sequences::Lattice::fibonacci(14);
+        let mut expected = [0.0; 2];
+        for i in 0..377 {
+            crate::sequences::UniformSequence::next_into(&mut reference, &mut expected);
+            assert_eq!(lattice.point(i), expected);
+        }
+
+        let mut qrng = crate::Qrng::<[f64; 2]>::new(0.5);
+        for _ in 0..2000 {
+            let target = qrng.gen();
+            let torus_distance = |i: u64| {
+                let point = lattice.point(i);
+                (0..2).map(|k| {
+                    let d = (point[k] - target[k]).abs();
+                    d.min(1.0 - d).powi(2)
+                }).sum::<f64>()
+            };
+            let brute = (0..377).min_by(|&a, &b| torus_distance(a).total_cmp(&torus_distance(b))).unwrap();
+            assert_eq!(torus_distance(lattice.nearest(target)), torus_distance(brute));
+        }
+    }
+
+    #[test]
+    fn sphere_nearest_matches_brute_force() {
+        for n in [1, 2, 10, 100, 1000, 4321] {
+            let lattice = SphereLattice::new(n);
+            let points: Vec<[f64; 3]> = (0..n).map(|i| lattice.point(i)).collect();
+            assert_eq!(points, sphere_points(n as usize, 0.0));
+            for (i, &point) in points.iter().enumerate() {
+                assert_eq!(lattice.nearest(point), i as u64);
+            }
+
+            let mut qrng = crate::Qrng::<[f64; 2]>::new(0.5);
+            for _ in 0..1000 {
+                let target = crate::warp::to_unit_sphere(qrng.gen());
+                let distance = |i: u64| (0..3).map(|k| (points[i as usize][k] - target[k]).powi(2)).sum::<f64>();
+                let brute = (0..n).min_by(|&a, &b| distance(a).total_cmp(&distance(b))).unwrap();
+                assert_eq!(distance(lattice.nearest(target)), distance(brute), "n {} {:?}", n, target);
+            }
+            assert_eq!(lattice.nearest([0.0, 0.0, 1.0]), 0);
+            assert_eq!(lattice.nearest([0.0, 0.0, -2.0]), n - 1);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Filling buffers with points, using the best kernel the CPU supports.
+//!
+//! Instead of one dependent addition per point, the fill kernel advances a
+//! group of points at a time, each by the group size times the generating
+//! constants, and converts the fixed-point state to `f64` with integer and
+//! bit operations only. The whole loop then vectorizes.
+//!
+//! The kernel is compiled several times, once per instruction set, and the
+//! first fill picks the best one the running CPU supports (see `Kernel`), so
+//! one binary runs the fast path on every machine and still runs on machines
+//! without it. Points of two or three `f64`s, which most workloads draw, have
+//! their own entry points, `fill_f64x2` and `fill_f64x3`.
+//!
+//! Every kernel is bit-identical to calling `gen` once per point.
+
+use std::sync::OnceLock;
+
+use crate::{Qrng, Quasirandom, State};
+
+/// A version of the fill kernel, for one instruction set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kernel {
+    /// Portable code, as vectorized for the compilation target
+    Scalar,
+    /// x86-64 with AVX2, four points at a time
+    Avx2,
+    /// x86-64 with AVX-512F and DQ, eight points at a time
+    Avx512,
+    /// AArch64 with NEON, four points at a time
+    Neon,
+}
+
+impl Kernel {
+    /// Every kernel, from the least to the most preferred
+    pub const ALL: [Kernel; 4] = [Kernel::Scalar, Kernel::Neon, Kernel::Avx2, Kernel::Avx512];
+
+    /// The kernel used by every fill, detected once per process: the most
+    /// preferred one the CPU supports.
+    ///
+    /// ```
+    /// use quasirandom::fill::Kernel;
+    ///
+    /// let kernel = Kernel::selected();
+    /// assert!(kernel.is_supported());
+    /// println!("filling with {:?}", kernel);
+    /// ```
+    pub fn selected() -> Kernel {
+        static SELECTED: OnceLock<Kernel> = OnceLock::new();
+        *SELECTED.get_or_init(|| Kernel::ALL.into_iter().rev().find(|kernel| kernel.is_supported()).unwrap())
+    }
+
+    /// Whether the running CPU supports this kernel
+    pub fn is_supported(self) -> bool {
+        match self {
+            Kernel::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx512 => is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512dq"),
+            #[cfg(target_arch = "aarch64")]
+            Kernel::Neon => std::arch::is_aarch64_feature_detected!("neon"),
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+}
+
+impl<const N: usize, T: Quasirandom<State = State<N>>> Qrng<T> {
+    /// Fills `out` with the next `out.len()` points as `f64`s, the same as
+    /// calling `gen` for each of them and converting.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// let mut qrng = Qrng::<(f64, f32, f64, f64)>::new(0.5);
+    /// let mut points = vec![[0.0; 4]; 1000];
+    /// qrng.fill(&mut points);
+    /// assert_eq!(points[999], Qrng::<[f64; 4]>::new(0.5).sample_at(999));
+    /// ```
+    pub fn fill(&mut self, out: &mut [[f64; N]]) {
+        fill_with(Kernel::selected(), &mut self.state, out);
+    }
+
+    /// The next `n` points as `f64`s, in a new vector, generated by `fill`
+    pub fn collect_points(&mut self, n: usize) -> Vec<[f64; N]> {
+        let mut points = vec![[0.0; N]; n];
+        self.fill(&mut points);
+        points
+    }
+}
+
+impl<T: Quasirandom<State = State<2>>> Qrng<T> {
+    /// Fills `out` with the next `out.len()` points, the same as calling
+    /// `gen` for each of them.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// let mut qrng = Qrng::<[f64; 2]>::new(0.5);
+    /// let mut points = vec![[0.0; 2]; 1000];
+    /// qrng.fill_f64x2(&mut points);
+    /// assert_eq!(qrng.gen(), Qrng::<[f64; 2]>::new(0.5).sample_at(1000));
+    /// ```
+    pub fn fill_f64x2(&mut self, out: &mut [[f64; 2]]) {
+        self.fill(out);
+    }
+}
+
+impl<T: Quasirandom<State = State<3>>> Qrng<T> {
+    /// Fills `out` with the next `out.len()` points, the same as calling
+    /// `gen` for each of them.
+    pub fn fill_f64x3(&mut self, out: &mut [[f64; 3]]) {
+        self.fill(out);
+    }
+}
+
+/// Fills `out` with `kernel`, which the CPU must support
+fn fill_with<const N: usize>(kernel: Kernel, state: &mut State<N>, out: &mut [[f64; N]]) {
+    assert!(kernel.is_supported());
+    match kernel {
+        // SAFETY: the CPU supports the kernel's instructions.
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx2 => unsafe { fill_avx2(state, out) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx512 => unsafe { fill_avx512(state, out) },
+        #[cfg(target_arch = "aarch64")]
+        Kernel::Neon => unsafe { fill_neon(state, out) },
+        _ => fill_points::<N, 4>(state, out),
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn fill_avx2<const N: usize>(state: &mut State<N>, out: &mut [[f64; N]]) {
+    fill_points::<N, 4>(state, out);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f,avx512dq")]
+unsafe fn fill_avx512<const N: usize>(state: &mut State<N>, out: &mut [[f64; N]]) {
+    fill_points::<N, 8>(state, out);
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn fill_neon<const N: usize>(state: &mut State<N>, out: &mut [[f64; N]]) {
+    fill_points::<N, 4>(state, out);
+}
+
+/// Fills `out`, advancing `LANES` points at a time
+#[inline(always)]
+fn fill_points<const N: usize, const LANES: usize>(state: &mut State<N>, out: &mut [[f64; N]]) {
+    let alphas = State::<N>::ALPHAS;
+    // The next LANES points, and how far each coordinate moves from one
+    // group of points to the next
+    let mut next: [[u64; N]; LANES] = std::array::from_fn(|p| {
+        std::array::from_fn(|d| state.fixed[d].wrapping_add((p as u64 + 1).wrapping_mul(alphas[d])))
+    });
+    let step = alphas.map(|alpha| (LANES as u64).wrapping_mul(alpha));
+
+    let mut chunks = out.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        for (out, next) in chunk.iter_mut().zip(&mut next) {
+            for ((out, x), step) in out.iter_mut().zip(next).zip(step) {
+                *out = to_f64(*x);
+                *x = x.wrapping_add(step);
+            }
+        }
+    }
+    // Fewer than LANES points remain, and start at the front of `next`
+    for (out, next) in chunks.into_remainder().iter_mut().zip(&next) {
+        *out = next.map(to_f64);
+    }
+
+    let count = out.len() as u64;
+    state.fixed = std::array::from_fn(|d| state.fixed[d].wrapping_add(count.wrapping_mul(alphas[d])));
+    state.index = state.index.wrapping_add(count);
+}
+
+/// `from_fixed` without an integer to float conversion instruction, which
+/// x86-64 lacks for vectors of 64-bit integers before AVX-512: the low 52 of
+/// the 53 bits go into the mantissa of `2^52`, and the top bit adds `2^52` or
+/// zero, both exactly.
+#[inline(always)]
+fn to_f64(x: u64) -> f64 {
+    const TWO_52: u64 = 0x4330_0000_0000_0000;
+    let bits = x >> 11;
+    let low = f64::from_bits(TWO_52 | (bits & ((1 << 52) - 1))) - f64::from_bits(TWO_52);
+    let high = f64::from_bits((bits >> 52).wrapping_neg() & TWO_52);
+    (low + high) * (1.0 / 9_007_199_254_740_992.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_fixed;
+
+    #[test]
+    fn fills_match_gen() {
+        for x in [0, 1 << 11, 1 << 63, u64::MAX, 0x1234_5678_9abc_def0] {
+            assert_eq!(to_f64(x).to_bits(), from_fixed(x).to_bits());
+        }
+
+        for len in [0, 1, 3, 4, 5, 1001] {
+            let mut filled = Qrng::<(f64, f64)>::new(0.3);
+            let mut stepped = filled.clone();
+            let mut points = vec![[0.0; 2]; len];
+            filled.fill_f64x2(&mut points);
+            for point in points {
+                let (x, y) = stepped.gen();
+                assert_eq!(point, [x, y]);
+            }
+            assert_eq!(filled.gen(), stepped.gen());
+
+            let mut filled = Qrng::<[f64; 3]>::new(0.7);
+            let mut stepped = filled.clone();
+            let mut points = vec![[0.0; 3]; len];
+            filled.fill_f64x3(&mut points);
+            for point in points {
+                assert_eq!(point, stepped.gen());
+            }
+            assert_eq!(filled.gen(), stepped.gen());
+        }
+
+        let mut qrng = Qrng::<[f64; 5]>::new(0.2);
+        let points = qrng.collect_points(10);
+        assert_eq!(points, Qrng::<[f64; 5]>::new(0.2).gen_n(10));
+        assert_eq!(qrng.gen(), Qrng::<[f64; 5]>::new(0.2).sample_at(10));
+    }
+
+    #[test]
+    fn every_supported_kernel_agrees() {
+        assert!(Kernel::Scalar.is_supported());
+        assert!(Kernel::selected().is_supported());
+        let mut expected = vec![[0.0; 7]; 1003];
+        Qrng::<[f64; 7]>::new(0.1).fill(&mut expected);
+        for kernel in Kernel::ALL.into_iter().filter(|kernel| kernel.is_supported()) {
+            let mut qrng = Qrng::<[f64; 7]>::new(0.1);
+            let mut points = vec![[0.0; 7]; 1003];
+            fill_with(kernel, &mut qrng.state, &mut points);
+            assert_eq!(points, expected, "{:?}", kernel);
+            assert_eq!(qrng.gen(), Qrng::<[f64; 7]>::new(0.1).sample_at(1003));
+        }
+    }
+}
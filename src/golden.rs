@@ -0,0 +1,244 @@
+//! Reference vectors for checking ports of the sequence.
+//!
+//! Ports to shading languages, CUDA, or Python are easiest to trust when they
+//! reproduce the Rust output. `GoldenVectors` records the first points of a
+//! `Qrng` together with everything needed to regenerate them, in JSON or a
+//! simple binary layout that any language can read, and `verify` compares a
+//! port's output against them.
+//!
+//! The binary layout is little-endian throughout:
+//!
+//! | bytes | contents |
+//! |-------|----------|
+//! | 8 | the magic string `QRGOLDEN` |
+//! | 4 | format version, currently 2 (`u32`) |
+//! | 4 | dimension `d` (`u32`) |
+//! | 8 | number of points `n` (`u64`) |
+//! | 8 | seed (`f64`) |
+//! | 8 `d` | the generating constants, as fractions of 2^64 (`u64`) |
+//! | 8 `d` | the starting state, as fractions of 2^64 (`u64`) |
+//! | 8 `n d` | the points, row-major (`f64`) |
+//!
+//! The JSON holds the same fields, with numbers written in their shortest
+//! round-trip form.
+
+use std::fmt::{self, Write};
+
+use crate::sequences::{alphas, RSequence, UniformSequence};
+use crate::to_fixed;
+
+const MAGIC: &[u8; 8] = b"QRGOLDEN";
+const VERSION: u32 = 2;
+
+/// The first points of a `Qrng` of some dimension and seed, with the
+/// recurrence that produces them: in 64-bit fixed point, every point is the
+/// previous one (or the starting state) plus `alphas`, wrapping around, and
+/// each coordinate `x` becomes the `f64` `(x >> 11) * 2^-53`.
+///
+/// ```
+/// use quasirandom::golden::GoldenVectors;
+/// use quasirandom::Qrng;
+///
+/// let golden = GoldenVectors::generate(2, 0.5, 1000);
+/// let bytes = golden.to_bytes();
+/// assert_eq!(GoldenVectors::from_bytes(&bytes), Some(golden.clone()));
+///
+/// // A port in single precision is close, but not bit-exact
+/// let mut qrng = Qrng::<[f32; 2]>::new(0.5);
+/// let port: Vec<f64> = (0..1000).flat_map(|_| qrng.gen()).map(f64::from).collect();
+/// assert!(golden.verify(&port, 0.0).is_err());
+/// assert!(golden.verify(&port, 1e-6).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoldenVectors {
+    pub dimension: usize,
+    pub seed: f64,
+    pub alphas: Vec<u64>,
+    pub start: Vec<u64>,
+    /// The points, row-major
+    pub points: Vec<f64>,
+}
+
+/// The first difference found by `GoldenVectors::verify`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub index: usize,
+    pub coordinate: usize,
+    pub expected: f64,
+    /// The port's value, or `NaN` if it produced too few values
+    pub actual: f64,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "point {} coordinate {}: expected {:?}, got {:?}",
+            self.index, self.coordinate, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+impl GoldenVectors {
+    /// Records the first `count` points of `Qrng::<[f64; dimension]>::new(seed)`.
+    /// Dimensions beyond 32 record `RSequence` instead.
+    pub fn generate(dimension: usize, seed: f64, count: usize) -> Self {
+        let mut sequence = RSequence::new(dimension, seed);
+        let start = (0..dimension as u64).map(|i| to_fixed(seed).wrapping_mul(i)).collect();
+        let mut points = vec![0.0; dimension * count];
+        for point in points.chunks_exact_mut(dimension) {
+            sequence.next_into(point);
+        }
+        Self {
+            dimension,
+            seed,
+            alphas: alphas(dimension).into_iter().map(to_fixed).collect(),
+            start,
+            points,
+        }
+    }
+
+    /// The number of points
+    pub fn len(&self) -> usize {
+        self.points.len() / self.dimension
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Compares a port's output, given row-major, against the recorded
+    /// points. Each coordinate may differ by up to `tolerance`, measured
+    /// around the circle so that values just below one match values just
+    /// above zero. Values beyond the recorded points are ignored.
+    pub fn verify(&self, actual: &[f64], tolerance: f64) -> Result<(), Mismatch> {
+        for (i, &expected) in self.points.iter().enumerate() {
+            let value = actual.get(i).copied().unwrap_or(f64::NAN);
+            let difference = (value - expected).rem_euclid(1.0);
+            if value.is_nan() || difference.min(1.0 - difference) > tolerance {
+                return Err(Mismatch {
+                    index: i / self.dimension,
+                    coordinate: i % self.dimension,
+                    expected,
+                    actual: value,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        fn list<T: fmt::Debug>(values: &[T]) -> String {
+            values.iter().map(|x| format!("{:?}", x)).collect::<Vec<_>>().join(",")
+        }
+        let mut json = String::new();
+        json.push_str("{\n");
+        writeln!(json, "  \"format\": \"quasirandom-golden\",").unwrap();
+        writeln!(json, "  \"version\": {},", VERSION).unwrap();
+        writeln!(json, "  \"dimension\": {},", self.dimension).unwrap();
+        writeln!(json, "  \"seed\": {:?},", self.seed).unwrap();
+        writeln!(json, "  \"alphas\": [{}],", list(&self.alphas)).unwrap();
+        writeln!(json, "  \"start\": [{}],", list(&self.start)).unwrap();
+        json.push_str("  \"points\": [");
+        for (i, point) in self.points.chunks_exact(self.dimension).enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(json, "{}\n    [{}]", separator, list(point)).unwrap();
+        }
+        json.push_str("\n  ]\n}\n");
+        json
+    }
+
+    /// Encodes the vectors in the binary layout described in the module
+    /// documentation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 + 8 * (2 * self.dimension + self.points.len()));
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.dimension as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        for x in self.alphas.iter().chain(&self.start) {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+        for x in &self.points {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes the binary layout, or returns `None` if `bytes` is not in it.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let rest = bytes.strip_prefix(MAGIC)?;
+        let (version, rest) = rest.split_first_chunk::<4>()?;
+        let (dimension, rest) = rest.split_first_chunk::<4>()?;
+        let (count, rest) = rest.split_first_chunk::<8>()?;
+        let (seed, rest) = rest.split_first_chunk::<8>()?;
+        if u32::from_le_bytes(*version) != VERSION {
+            return None;
+        }
+        let dimension = u32::from_le_bytes(*dimension) as usize;
+        let count = usize::try_from(u64::from_le_bytes(*count)).ok()?;
+        let values = dimension.checked_mul(count.checked_add(2)?)?;
+        if dimension == 0 || rest.len() != values.checked_mul(8)? {
+            return None;
+        }
+        let mut words = rest.chunks_exact(8).map(|x| u64::from_le_bytes(x.try_into().unwrap()));
+        Some(Self {
+            dimension,
+            seed: f64::from_le_bytes(*seed),
+            alphas: words.by_ref().take(dimension).collect(),
+            start: words.by_ref().take(dimension).collect(),
+            points: words.map(f64::from_bits).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Qrng;
+
+    #[test]
+    fn vectors_match_and_round_trip() {
+        let golden = GoldenVectors::generate(3, 0.25, 500);
+        let mut qrng = Qrng::<[f64; 3]>::new(0.25);
+        let expected: Vec<f64> = (0..500).flat_map(|_| qrng.gen()).collect();
+        assert_eq!(golden.points, expected);
+        assert_eq!(golden.len(), 500);
+        assert_eq!(golden.verify(&expected, 0.0), Ok(()));
+
+        // Regenerating from the recorded recurrence reproduces the points
+        let mut state = golden.start.clone();
+        for point in golden.points.chunks_exact(3) {
+            for (x, alpha) in state.iter_mut().zip(&golden.alphas) {
+                *x = x.wrapping_add(*alpha);
+            }
+            assert!(point.iter().zip(&state).all(|(&value, &x)| value == (x >> 11) as f64 * 2f64.powi(-53)));
+        }
+
+        let mut wrong = expected.clone();
+        wrong[3 * 100 + 2] += 1e-9;
+        let mismatch = golden.verify(&wrong, 1e-12).unwrap_err();
+        assert_eq!((mismatch.index, mismatch.coordinate), (100, 2));
+        assert_eq!(golden.verify(&wrong, 1e-8), Ok(()));
+        assert_eq!(golden.verify(&expected[..30], 1.0).unwrap_err().index, 10);
+        // Just below one matches zero
+        let wrapped = GoldenVectors { points: vec![0.0], ..GoldenVectors::generate(1, 0.0, 1) };
+        assert!(wrapped.verify(&[1.0 - 1e-9], 1e-8).is_ok());
+
+        let bytes = golden.to_bytes();
+        assert_eq!(GoldenVectors::from_bytes(&bytes), Some(golden.clone()));
+        assert_eq!(GoldenVectors::from_bytes(&bytes[..bytes.len() - 1]), None);
+
+        let json = golden.to_json();
+        assert!(json.contains("\"dimension\": 3,"));
+        let first_point = json.lines().find(|line| line.trim_start().starts_with('[')).unwrap();
+        let parsed: Vec<f64> = first_point.trim().trim_matches(|c| c == '[' || c == ']' || c == ',')
+            .split(',')
+            .map(|x| x.parse().unwrap())
+            .collect();
+        assert_eq!(parsed, &expected[..3]);
+    }
+}
@@ -0,0 +1,77 @@
+//! Hashing helpers shared by the randomized constructions in this crate.
+
+pub(crate) const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The SplitMix64 finalizer, a bijective 64-bit mixing function
+pub(crate) fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Hashes two values into one
+pub(crate) fn combine(a: u64, b: u64) -> u64 {
+    mix(a ^ mix(b ^ GOLDEN_GAMMA))
+}
+
+/// Maps 64 random bits to a uniform value in `[0, 1)`
+pub(crate) fn to_unit(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// A pseudorandom permutation of `0..len`, selected by `pattern`, evaluated at
+/// `i` (Kensler, "Correlated Multi-Jittered Sampling"). Each step is a
+/// bijection on the bits covered by `mask`, and values outside `0..len` are
+/// cycled until they land inside.
+pub(crate) fn permute(mut i: u32, len: u32, pattern: u32) -> u32 {
+    assert!(i < len);
+    let mut mask = len - 1;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+    loop {
+        i ^= pattern;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= pattern >> 16;
+        i ^= (i & mask) >> 4;
+        i ^= pattern >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= pattern >> 23;
+        i ^= (i & mask) >> 1;
+        i = i.wrapping_mul(1 | pattern >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & mask) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= mask;
+        i ^= i >> 5;
+        if i < len {
+            break;
+        }
+    }
+    ((i as u64 + pattern as u64) % len as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permute_is_a_permutation() {
+        for &len in &[1, 2, 7, 64, 100, 1000] {
+            for pattern in 0..20 {
+                let mut seen = vec![false; len as usize];
+                for i in 0..len {
+                    let j = permute(i, len, mix(pattern) as u32);
+                    assert!(!seen[j as usize]);
+                    seen[j as usize] = true;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Generating the vector types of other math libraries directly.
+//!
+//! With the `glam` feature, `Qrng` generates `glam`'s float vectors, and with
+//! the `nalgebra` feature, `nalgebra`'s statically sized column vectors. Each
+//! vector takes one dimension per component, exactly like the array of the
+//! same length.
+//!
+//! With the `ndarray` feature, whole designs can be generated into an
+//! `ndarray::Array2` at once.
+
+#[cfg(any(feature = "glam", feature = "nalgebra"))]
+use crate::FromUniform;
+#[cfg(any(feature = "glam", feature = "nalgebra", feature = "ndarray"))]
+use crate::{Qrng, Quasirandom, State};
+
+#[cfg(feature = "glam")]
+macro_rules! glam_vectors {
+    ($($vector:ident $scalar:ident $n:literal)*) => {
+        $(
+        impl Quasirandom for glam::$vector {
+            #[doc(hidden)]
+            type State = State<$n>;
+
+            fn generate(state: &mut State<$n>) -> Self {
+                glam::$vector::from_array(state.gen().map($scalar::from_uniform))
+            }
+        }
+
+        impl Qrng<glam::$vector> {
+            pub fn new(seed: f64) -> Self {
+                let Qrng { state } = Qrng::<[$scalar; $n]>::new(seed);
+                Self { state }
+            }
+
+            pub fn gen(&mut self) -> glam::$vector {
+                glam::$vector::generate(&mut self.state)
+            }
+        }
+        )*
+    }
+}
+
+#[cfg(feature = "glam")]
+glam_vectors!(
+    Vec2 f32 2
+    Vec3 f32 3
+    Vec3A f32 3
+    Vec4 f32 4
+    DVec2 f64 2
+    DVec3 f64 3
+    DVec4 f64 4
+);
+
+#[cfg(feature = "nalgebra")]
+impl<T: FromUniform + nalgebra::Scalar, const N: usize> Quasirandom for nalgebra::SVector<T, N> {
+    #[doc(hidden)]
+    type State = State<N>;
+
+    fn generate(state: &mut State<N>) -> Self {
+        state.gen().map(T::from_uniform).into()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<T: FromUniform + nalgebra::Scalar, const N: usize> Qrng<nalgebra::SVector<T, N>> {
+    pub fn new(seed: f64) -> Self {
+        let Qrng { state } = Qrng::<[T; N]>::new(seed);
+        Self { state }
+    }
+
+    pub fn gen(&mut self) -> nalgebra::SVector<T, N> {
+        nalgebra::SVector::<T, N>::generate(&mut self.state)
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<const N: usize, T: Quasirandom<State = State<N>>> Qrng<T> {
+    /// Generates the next `n` points as the rows of an `n x N` array of the
+    /// raw uniform values, in standard (row-major, contiguous) layout.
+    pub fn gen_array2(&mut self, n: usize) -> ndarray::Array2<f64> {
+        let mut values = Vec::with_capacity(n * N);
+        for _ in 0..n {
+            values.extend_from_slice(&self.state.gen());
+        }
+        ndarray::Array2::from_shape_vec((n, N), values).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "glam", feature = "nalgebra", feature = "ndarray"))]
+    use crate::Qrng;
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn glam_matches_arrays() {
+        let mut vectors = Qrng::<glam::DVec3>::new(0.25);
+        let mut arrays = Qrng::<[f64; 3]>::new(0.25);
+        for _ in 0..10 {
+            assert_eq!(vectors.gen().to_array(), arrays.gen());
+        }
+        let v = Qrng::<glam::Vec2>::new(0.0).gen();
+        assert!(v.cmpge(glam::Vec2::ZERO).all() && v.cmple(glam::Vec2::ONE).all());
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn nalgebra_matches_arrays() {
+        let mut vectors = Qrng::<nalgebra::SVector<f64, 5>>::new(0.25);
+        let mut arrays = Qrng::<[f64; 5]>::new(0.25);
+        for _ in 0..10 {
+            assert_eq!(vectors.gen().as_slice(), arrays.gen());
+        }
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn ndarray_rows_are_points() {
+        use crate::sequences::{array2, RSequence};
+
+        let mut qrng = Qrng::<(f64, f64, f64)>::new(0.5);
+        let array = Qrng::<(f64, f64, f64)>::new(0.5).gen_array2(20);
+        assert_eq!(array.dim(), (20, 3));
+        assert!(array.is_standard_layout());
+        for row in array.rows() {
+            let (a, b, c) = qrng.gen();
+            assert_eq!(row.to_vec(), [a, b, c]);
+        }
+        assert_eq!(array2(&mut RSequence::new(3, 0.5), 20), array);
+    }
+}
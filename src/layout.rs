@@ -0,0 +1,238 @@
+//! Jittering regular layouts.
+//!
+//! Instanced foliage, scatter-plot markers, and icon grids look mechanical
+//! on a perfect grid, but pseudorandom jitter makes some neighbors collide
+//! while others drift apart. `JitteredGrid` offsets every cell by a value of
+//! a 2D `Qrng` instead, so the offsets of any run of cells are spread evenly
+//! over the allowed box, and bounds them so that items of a given radius
+//! never overlap.
+//!
+//! `Placer` packs rectangles such as the words of a word cloud or map labels
+//! into a region. Each item tries candidate positions from a 2D `Qrng` until
+//! one is free. The usual spiral search tries positions along a single curve
+//! out from the center, so a free spot off the curve is only found late; the
+//! candidates of the sequence cover the whole region evenly from the first
+//! few on, and keep covering it as more are drawn.
+
+use crate::Qrng;
+
+/// A grid of `columns` by `rows` cells whose items are jittered within their
+/// cells.
+///
+/// Each item stays at least `radius` away from the borders of its cell, so
+/// any two items are at least `2 * radius` apart: items up to that radius
+/// never overlap.
+///
+/// ```
+/// use quasirandom::layout::JitteredGrid;
+///
+/// // Trees on a 2 m grid, with trunks of radius 0.3 m
+/// let grid = JitteredGrid::new(100, 100, [2.0, 2.0], 0.3);
+/// let trees = grid.positions(0.5);
+/// assert_eq!(trees.len(), 10_000);
+/// let [x, y] = trees[0];
+/// assert!((0.3..=1.7).contains(&x) && (0.3..=1.7).contains(&y));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JitteredGrid {
+    columns: usize,
+    rows: usize,
+    spacing: [f64; 2],
+    radius: f64,
+    strength: f64,
+}
+
+impl JitteredGrid {
+    /// Cells are `spacing[0]` wide and `spacing[1]` tall, and `radius` must
+    /// be at most half of either.
+    pub fn new(columns: usize, rows: usize, spacing: [f64; 2], radius: f64) -> Self {
+        assert!(radius >= 0.0);
+        assert!(spacing.iter().all(|&s| 2.0 * radius <= s), "items of this radius do not fit in a cell");
+        Self {
+            columns,
+            rows,
+            spacing,
+            radius,
+            strength: 1.0,
+        }
+    }
+
+    /// Scales every offset by `strength` in `[0, 1]`, where zero leaves the
+    /// items at the cell centers and one, the default, uses all the room the
+    /// radius allows.
+    pub fn with_strength(mut self, strength: f64) -> Self {
+        assert!((0.0..=1.0).contains(&strength));
+        self.strength = strength;
+        self
+    }
+
+    /// The largest offset from the cell center along each axis
+    pub fn max_offset(&self) -> [f64; 2] {
+        self.spacing.map(|s| self.strength * (s / 2.0 - self.radius))
+    }
+
+    /// The position of every item, row by row, with the grid's corner at the
+    /// origin. The seed selects the offsets.
+    pub fn positions(&self, seed: f64) -> Vec<[f64; 2]> {
+        let mut qrng = Qrng::<[f64; 2]>::new(seed);
+        let max_offset = self.max_offset();
+        let mut positions = Vec::with_capacity(self.columns * self.rows);
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let cell = [column, row];
+                let u = qrng.gen();
+                positions.push(std::array::from_fn(|k| {
+                    let center = (cell[k] as f64 + 0.5) * self.spacing[k];
+                    center + (2.0 * u[k] - 1.0) * max_offset[k]
+                }));
+            }
+        }
+        positions
+    }
+}
+
+/// An axis-aligned rectangle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rectangle {
+    /// The corner with the smallest coordinates
+    pub min: [f64; 2],
+    pub size: [f64; 2],
+}
+
+impl Rectangle {
+    /// Whether the interiors of the two rectangles intersect, so that
+    /// rectangles sharing only an edge do not overlap
+    pub fn overlaps(&self, other: &Rectangle) -> bool {
+        (0..2).all(|k| self.min[k] < other.min[k] + other.size[k] && other.min[k] < self.min[k] + self.size[k])
+    }
+}
+
+/// Places rectangles one at a time in a region without overlaps.
+///
+/// ```
+/// use quasirandom::layout::Placer;
+///
+/// // A word cloud: larger words first, each sized by its text
+/// let mut cloud = Placer::new([400.0, 300.0], 0.5);
+/// for (word, size) in [("quasirandom", 48.0), ("sequence", 32.0), ("uniform", 20.0)] {
+///     let extent = [0.6 * size * word.len() as f64, size];
+///     if let Some([x, y]) = cloud.place(extent) {
+///         // ... draw `word` with its corner at (x, y)
+///     }
+/// }
+/// assert_eq!(cloud.placed().len(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Placer {
+    size: [f64; 2],
+    max_candidates: usize,
+    candidates: Qrng<[f64; 2]>,
+    placed: Vec<Rectangle>,
+}
+
+impl Placer {
+    /// An empty region from the origin to `size`, with the seed selecting
+    /// the candidates
+    pub fn new(size: [f64; 2], seed: f64) -> Self {
+        assert!(size.iter().all(|&s| s > 0.0));
+        Self {
+            size,
+            max_candidates: 1000,
+            candidates: Qrng::<[f64; 2]>::new(seed),
+            placed: Vec::new(),
+        }
+    }
+
+    /// The number of candidates tried for an item before giving up, 1000 by
+    /// default
+    pub fn with_max_candidates(mut self, max_candidates: usize) -> Self {
+        self.max_candidates = max_candidates;
+        self
+    }
+
+    /// The rectangles placed so far, in order
+    pub fn placed(&self) -> &[Rectangle] {
+        &self.placed
+    }
+
+    /// Places an item of the given size inside the region where it overlaps
+    /// no placed item, returning its smallest corner, or `None` if no
+    /// candidate was free.
+    pub fn place(&mut self, size: [f64; 2]) -> Option<[f64; 2]> {
+        self.place_with(size, |_| true)
+    }
+
+    /// Like `place`, but a candidate must also pass `fits`, for collisions
+    /// the rectangles do not capture, such as glyph outlines or obstacles
+    /// already on a map. Items rejected by `fits` do not count as placed.
+    pub fn place_with(&mut self, size: [f64; 2], mut fits: impl FnMut(&Rectangle) -> bool) -> Option<[f64; 2]> {
+        let room = [self.size[0] - size[0], self.size[1] - size[1]];
+        if room.iter().any(|&r| r < 0.0) {
+            return None;
+        }
+        // The sequence continues from item to item, so later items try
+        // positions their predecessors did not
+        for _ in 0..self.max_candidates {
+            let [u, v] = self.candidates.gen();
+            let candidate = Rectangle {
+                min: [u * room[0], v * room[1]],
+                size,
+            };
+            if !self.placed.iter().any(|item| item.overlaps(&candidate)) && fits(&candidate) {
+                self.placed.push(candidate);
+                return Some(candidate.min);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_never_overlap() {
+        let radius = 0.2;
+        let grid = JitteredGrid::new(30, 20, [1.0, 0.5], radius);
+        let positions = grid.positions(0.25);
+        for (i, p) in positions.iter().enumerate() {
+            for q in &positions[i + 1..] {
+                assert!((p[0] - q[0]).hypot(p[1] - q[1]) >= 2.0 * radius - 1e-12);
+            }
+        }
+
+        // The offsets fill their box evenly: each quadrant gets a quarter
+        let mut quadrants = [0; 4];
+        for (i, [x, y]) in positions.iter().enumerate() {
+            let center = [(i % 30) as f64 + 0.5, ((i / 30) as f64 + 0.5) * 0.5];
+            assert!((x - center[0]).abs() <= 0.3 && (y - center[1]).abs() <= 0.05 + 1e-12);
+            quadrants[(*x > center[0]) as usize + 2 * (*y > center[1]) as usize] += 1;
+        }
+        assert!(quadrants.iter().all(|&count| (count - 150_i32).abs() <= 3), "{:?}", quadrants);
+
+        let still = grid.with_strength(0.0).positions(0.25);
+        assert_eq!(still[31], [1.5, 0.75]);
+    }
+
+    #[test]
+    fn placed_items_do_not_overlap() {
+        let mut placer = Placer::new([10.0, 10.0], 0.0);
+        // Twenty-five 2 by 2 squares fill the region only when perfectly
+        // tiled, so some of them must fail
+        let placed: Vec<_> = (0..25).filter_map(|_| placer.place([2.0, 2.0])).collect();
+        assert!(placed.len() >= 10 && placed.len() < 25, "{}", placed.len());
+        for (i, a) in placer.placed().iter().enumerate() {
+            assert!(a.min[0] + 2.0 <= 10.0 && a.min[1] + 2.0 <= 10.0);
+            assert!(placer.placed()[i + 1..].iter().all(|b| !a.overlaps(b)));
+        }
+        assert_eq!(placer.place([11.0, 1.0]), None);
+
+        // The callback can reject candidates, here the left half
+        let mut placer = Placer::new([10.0, 10.0], 0.0);
+        let [x, _] = placer.place_with([1.0, 1.0], |item| item.min[0] >= 5.0).unwrap();
+        assert!(x >= 5.0);
+        assert_eq!(placer.place_with([1.0, 1.0], |_| false), None);
+        assert_eq!(placer.placed().len(), 1);
+    }
+}
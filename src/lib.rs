@@ -1,3 +1,54 @@
+#![allow(clippy::legacy_numeric_constants)]
+
+pub mod ais;
+pub mod bandit;
+pub mod benchmark;
+pub mod builder;
+pub mod cell;
+mod choose;
+pub mod cubature;
+pub mod decisions;
+pub mod design;
+pub mod diagnostics;
+pub mod diff;
+pub mod distributions;
+pub mod erosion;
+#[cfg(feature = "exact")]
+pub mod exact;
+pub mod export;
+pub mod fibonacci;
+pub mod fill;
+#[cfg(feature = "golden")]
+pub mod golden;
+mod hash;
+mod interop;
+pub mod layout;
+pub mod mis;
+pub mod noise;
+pub mod piecewise;
+pub mod pipeline;
+pub mod poisson;
+pub mod pool;
+pub mod prefetch;
+pub mod progressive;
+pub mod qrng32;
+pub mod randomize;
+pub mod rational;
+pub mod reduce;
+pub mod rng;
+pub mod sampler;
+pub mod schedule;
+pub mod sequences;
+pub mod stipple;
+pub mod streams;
+pub mod table;
+pub mod texture;
+pub mod trace;
+pub mod transform;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod warp;
+
 /// A type that implements `FromUniform` is able to instantiate itself
 /// from an `f64` uniformly distributed in the range `[0, 1)`.
 ///
@@ -8,6 +59,11 @@
 /// such as `Option`. In general, this is up to the judgment of the
 /// implementer. Reasonable implementations are provided for many common
 /// standard library types.
+///
+/// The value passed in is never `1.0`: it is at most `MAX_UNIFORM`.
+/// Implementations that rescale it before passing it on to another
+/// `FromUniform` must keep that guarantee, since rounding can turn a value
+/// just below one into exactly one.
 pub trait FromUniform {
     fn from_uniform(uniform_value: f64) -> Self;
 }
@@ -19,20 +75,29 @@ impl FromUniform for f64 {
     }
 }
 
-/// The identity mapping
+/// The nearest `f32`, or the largest `f32` below one where the nearest is one
 impl FromUniform for f32 {
     fn from_uniform(uniform_value: f64) -> Self {
-        uniform_value as f32
+        (uniform_value as f32).min(1.0 - f32::EPSILON / 2.0)
     }
 }
 
+/// The largest `f64` below one, and so the largest uniform value
+pub const MAX_UNIFORM: f64 = 1.0 - f64::EPSILON / 2.0;
+
+/// Clamps a rescaled uniform value that rounding pushed up to one
+fn below_one(uniform_value: f64) -> f64 {
+    uniform_value.min(MAX_UNIFORM)
+}
+
 macro_rules! unsigned {
     ($($ut:tt)*) => {
         $(
-        /// Uniform in `0 .. = MAX`
+        /// Uniform in `0 ..= MAX`. Scaling by a power of two is exact, so the
+        /// product is always below `MAX + 1`.
         impl FromUniform for $ut {
             fn from_uniform(uniform_value: f64) -> Self {
-                (::std::$ut::MAX as f64 * uniform_value) as $ut
+                ((::std::$ut::MAX as f64 + 1.0) * uniform_value) as $ut
             }
         }
         )*
@@ -56,6 +121,12 @@ macro_rules! signed {
 }
 signed!(i8 i16 i32 i64 i128 isize);
 
+/// Maps a uniform value in `[0, 1)` to an index in `0..len`, each with
+/// probability `1 / len`
+pub(crate) fn index_from_uniform(uniform_value: f64, len: usize) -> usize {
+    ((uniform_value * len as f64) as usize).min(len - 1)
+}
+
 /// 50% delegate to `Ok`, 50% to `Err`
 impl <T: FromUniform, E: FromUniform> FromUniform for Result<T, E> {
     fn from_uniform(uniform_value: f64) -> Self {
@@ -90,32 +161,311 @@ impl FromUniform for bool {
     }
 }
 
-/// A helper trait implemented for all tuples up to 32. The user
+/// A third each of `Less`, `Equal` and `Greater`
+impl FromUniform for std::cmp::Ordering {
+    fn from_uniform(uniform_value: f64) -> Self {
+        [Self::Less, Self::Equal, Self::Greater][index_from_uniform(uniform_value, 3)]
+    }
+}
+
+/// 50% delegate to `Continue`, 50% to `Break`
+impl<B: FromUniform, C: FromUniform> FromUniform for std::ops::ControlFlow<B, C> {
+    fn from_uniform(uniform_value: f64) -> Self {
+        if uniform_value < 0.5 {
+            Self::Continue(C::from_uniform(uniform_value * 2.0))
+        } else {
+            Self::Break(B::from_uniform(uniform_value * 2.0 - 1.0))
+        }
+    }
+}
+
+/// A third each of `Included` and `Excluded`, delegating, and `Unbounded`
+impl<T: FromUniform> FromUniform for std::ops::Bound<T> {
+    fn from_uniform(uniform_value: f64) -> Self {
+        let index = index_from_uniform(uniform_value, 3);
+        let rest = below_one(uniform_value * 3.0 - index as f64);
+        match index {
+            0 => Self::Included(T::from_uniform(rest)),
+            1 => Self::Excluded(T::from_uniform(rest)),
+            _ => Self::Unbounded,
+        }
+    }
+}
+
+/// Delegates to the wrapped type
+impl<T: FromUniform> FromUniform for std::cmp::Reverse<T> {
+    fn from_uniform(uniform_value: f64) -> Self {
+        Self(T::from_uniform(uniform_value))
+    }
+}
+
+/// A third each of `Left`, `Right` and `Center`
+impl FromUniform for std::fmt::Alignment {
+    fn from_uniform(uniform_value: f64) -> Self {
+        [Self::Left, Self::Right, Self::Center][index_from_uniform(uniform_value, 3)]
+    }
+}
+
+/// Uniform over all addresses, as a `u32`
+impl FromUniform for std::net::Ipv4Addr {
+    fn from_uniform(uniform_value: f64) -> Self {
+        Self::from(u32::from_uniform(uniform_value))
+    }
+}
+
+/// Uniform over all addresses, as a `u128`. A uniform value carries 53 bits,
+/// so the top 53 bits, which cover every routing prefix, vary and the rest
+/// are zero.
+impl FromUniform for std::net::Ipv6Addr {
+    fn from_uniform(uniform_value: f64) -> Self {
+        Self::from(u128::from_uniform(uniform_value))
+    }
+}
+
+/// Uniform over all address and port pairs: the 48 bits of both fit in one
+/// uniform value, the address in the high bits and the port in the low ones,
+/// so each is uniform on its own too.
+impl FromUniform for std::net::SocketAddrV4 {
+    fn from_uniform(uniform_value: f64) -> Self {
+        let bits = ((1u64 << 48) as f64 * uniform_value) as u64;
+        Self::new(std::net::Ipv4Addr::from((bits >> 16) as u32), bits as u16)
+    }
+}
+
+/// A probability known at compile time, used to parameterize `Weighted`.
+///
+/// Implement this on a marker type to use an arbitrary probability, or use
+/// `Percent` for whole percentages.
+pub trait Probability {
+    const VALUE: f64;
+}
+
+/// A probability of `N` percent
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Percent<const N: u32>;
+
+impl<const N: u32> Probability for Percent<N> {
+    const VALUE: f64 = N as f64 / 100.0;
+}
+
+/// Wraps an `Option` or `Result` so that `Some`/`Ok` is produced with
+/// probability `P` instead of the default 50%. Still consumes a single
+/// uniform value.
+///
+/// ```
+/// use quasirandom::{Qrng, Weighted, Percent};
+///
+/// let mut qrng = Qrng::<Weighted<Option<f64>, Percent<90>>>::new(0.0);
+/// let somes = (0..1000).filter(|_| qrng.gen().into_inner().is_some()).count();
+/// assert_eq!(somes, 900);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Weighted<T, P>(pub T, std::marker::PhantomData<P>);
+
+impl<T, P> Weighted<T, P> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// `Some` with probability `P`, `None` otherwise
+impl<T: FromUniform, P: Probability> FromUniform for Weighted<Option<T>, P> {
+    fn from_uniform(uniform_value: f64) -> Self {
+        const { assert!(P::VALUE >= 0.0 && P::VALUE <= 1.0, "probability must be in [0, 1]") };
+        let value = if uniform_value < P::VALUE {
+            Some(T::from_uniform(below_one(uniform_value / P::VALUE)))
+        } else {
+            None
+        };
+        Weighted(value, std::marker::PhantomData)
+    }
+}
+
+/// `Ok` with probability `P`, `Err` otherwise
+impl<T: FromUniform, E: FromUniform, P: Probability> FromUniform for Weighted<Result<T, E>, P> {
+    fn from_uniform(uniform_value: f64) -> Self {
+        const { assert!(P::VALUE >= 0.0 && P::VALUE <= 1.0, "probability must be in [0, 1]") };
+        let value = if uniform_value < P::VALUE {
+            Ok(T::from_uniform(below_one(uniform_value / P::VALUE)))
+        } else {
+            Err(E::from_uniform(below_one((uniform_value - P::VALUE) / (1.0 - P::VALUE))))
+        };
+        Weighted(value, std::marker::PhantomData)
+    }
+}
+
+/// Wraps a tuple of two to four `FromUniform` values so that the tuple
+/// itself is `FromUniform`, building every element from the one uniform
+/// value. Tuples can then appear inside other types, as in
+/// `Option<Split<(f64, bool)>>`. (A bare tuple cannot be `FromUniform`,
+/// since top-level tuples take one dimension per element.)
+///
+/// The 53 bits of the uniform value are dealt out to the elements in turn,
+/// most significant first, so element `j` of `k` gets bits `j`, `j + k`,
+/// `j + 2k`, ... Each element is then uniform on its own, with 53 / k bits
+/// of resolution, and consecutive strata of the uniform value cover the grid
+/// of the elements in Morton order, which keeps them evenly spread.
+///
+/// ```
+/// use quasirandom::{Qrng, Split};
+///
+/// let mut qrng = Qrng::<Option<Split<(f64, bool)>>>::new(0.5);
+/// if let Some(Split((x, flag))) = qrng.gen() {
+///     assert!((0.0..1.0).contains(&x));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Split<T>(pub T);
+
+impl<T> Split<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Element `part` of `parts` from the bits of `uniform_value`, as described
+/// on `Split`
+fn split_uniform(uniform_value: f64, part: u32, parts: u32) -> f64 {
+    let bits = (uniform_value * 9_007_199_254_740_992.0) as u64;
+    let (mut value, mut count) = (0u64, 0);
+    for position in (part..53).step_by(parts as usize) {
+        value = (value << 1) | ((bits >> (52 - position)) & 1);
+        count += 1;
+    }
+    value as f64 / (1u64 << count) as f64
+}
+
+macro_rules! split {
+    ($($n:literal: ($($t:ident $j:literal)*))*) => {
+        $(
+        impl<$($t: FromUniform,)*> FromUniform for Split<($($t,)*)> {
+            fn from_uniform(uniform_value: f64) -> Self {
+                Split(($($t::from_uniform(split_uniform(uniform_value, $j, $n)),)*))
+            }
+        }
+        )*
+    };
+}
+
+split! {
+    2: (A 0 B 1)
+    3: (A 0 B 1 C 2)
+    4: (A 0 B 1 C 2 D 3)
+}
+
+/// A helper trait implemented for all tuples and arrays up to 32. The user
 /// does not need to implement this. It exists because the `Qrng`
 /// needs to maintain different state for different cardinality
 /// tuples.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be generated by a `Qrng`",
+    note = "a `Qrng` generates a `FromUniform` type, or a tuple or array of up to 32 `FromUniform` types",
+    note = "for more than 32 dimensions, use `quasirandom::sequences::RSequence`, whose dimension is chosen at runtime"
+)]
 pub trait Quasirandom {
     type State;
+
+    /// Advances `state` to the next point and builds a value from it
+    #[doc(hidden)]
+    fn generate(state: &mut Self::State) -> Self;
 }
 
 impl<T: FromUniform> Quasirandom for T {
     #[doc(hidden)]
     type State = State<1>;
+
+    fn generate(state: &mut State<1>) -> Self {
+        let [x] = state.gen();
+        T::from_uniform(x)
+    }
+}
+
+/// The largest number of dimensions a `Qrng` supports
+pub const MAX_DIMENSION: usize = 32;
+
+impl<T: FromUniform, const N: usize> Quasirandom for [T; N] {
+    #[doc(hidden)]
+    type State = State<N>;
+
+    fn generate(state: &mut State<N>) -> Self {
+        state.gen().map(T::from_uniform)
+    }
 }
 
 #[doc(hidden)]
-pub struct State<const N: usize>([f64; N]);
+#[derive(Debug, Clone)]
+pub struct State<const N: usize> {
+    /// Each coordinate of the last point, as a fraction of 2^64
+    fixed: [u64; N],
+    /// The number of points generated since `fixed` was set
+    index: u64,
+}
 
 #[doc(hidden)]
 impl<const N: usize> State<N> {
-    fn gen(&mut self) -> &[f64; N] {
-        for i in 0..N {
-            self.0[i] = (self.0[i] + CONSTANTS[N-1][i]).fract();
+    const VALID: () = assert!(
+        N >= 1 && N <= MAX_DIMENSION,
+        "a Qrng supports between 1 and 32 dimensions; use quasirandom::sequences::RSequence for more"
+    );
+
+    /// The generating constants as fractions of 2^64, rounded down
+    const ALPHAS: [u64; N] = {
+        let mut alphas = [0; N];
+        let mut d = 0;
+        while N <= MAX_DIMENSION && d < N {
+            alphas[d] = to_fixed(CONSTANTS[N - 1][d]);
+            d += 1;
+        }
+        alphas
+    };
+
+    /// Starts at `start`, the fractions of 2^64 before the first point
+    fn new(start: [u64; N]) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        Self { fixed: start, index: 0 }
+    }
+
+    /// Starts every dimension `i` at `i * seed`, modulo one
+    fn seeded(seed: f64) -> Self {
+        assert!(seed >= 0.0);
+        assert!(seed < 1.0);
+        let seed = to_fixed(seed);
+        Self::new(std::array::from_fn(|i| seed.wrapping_mul(i as u64)))
+    }
+
+    /// The sum wraps modulo one exactly, so every coordinate stays in
+    /// `[0, 1)` and point `n` is the same however it is reached.
+    fn gen(&mut self) -> [f64; N] {
+        for (x, alpha) in self.fixed.iter_mut().zip(Self::ALPHAS) {
+            *x = x.wrapping_add(alpha);
+        }
+        self.index = self.index.wrapping_add(1);
+        self.fixed.map(from_fixed)
+    }
+
+    /// The state after `index` points from the same start
+    fn seek(&self, index: u64) -> Self {
+        let steps = index.wrapping_sub(self.index);
+        let mut fixed = self.fixed;
+        for (x, alpha) in fixed.iter_mut().zip(Self::ALPHAS) {
+            *x = x.wrapping_add(steps.wrapping_mul(alpha));
         }
-        &self.0
+        Self { fixed, index }
     }
 }
 
+/// A value in `[0, 1)` as a fraction of 2^64, rounded down
+pub(crate) const fn to_fixed(x: f64) -> u64 {
+    (x * 18_446_744_073_709_551_616.0) as u64
+}
+
+/// The top 53 bits of a fraction of 2^64 as an `f64`, which is exact and
+/// below one
+pub(crate) const fn from_fixed(x: u64) -> f64 {
+    (x >> 11) as f64 * (1.0 / 9_007_199_254_740_992.0)
+}
+
 /// Main driver of this library
 /// 
 /// # QRNG vs PRNG
@@ -131,12 +481,19 @@ impl<const N: usize> State<N> {
 /// 
 /// # Features
 /// 
-/// A `Qrng` can be built for any tuple up to size 32 for which all elements
-/// implement `FromUniform`.
+/// A `Qrng` can be built for any tuple or array up to size 32 for which all
+/// elements implement `FromUniform`. For more dimensions, or a dimension only
+/// known at runtime, see `sequences::RSequence`.
 /// 
 /// For instance, a `Qrng<(f64, u32, bool, Option<i16>)>` will generate values of
 /// the 5-tuple that, over enough samples, will uniformly cover that space.
 /// 
+/// # Arithmetic
+/// 
+/// The state is kept in 64-bit fixed point, and each value is converted to
+/// `f64` from its top 53 bits. Stepping is integer addition modulo 2^64, so
+/// `sample_at(n)` equals the value after `n` calls to `gen`, bit for bit.
+/// 
 /// # Note
 /// 
 /// Type inference will typically force you to specify the type at construction time, e.g.
@@ -178,10 +535,148 @@ impl<T: FromUniform> Qrng<T> {
     }
     
     pub fn gen(&mut self) -> T {
-        let [x] = self.state.gen();
-        T::from_uniform(*x)
+        T::generate(&mut self.state)
+    }
+
+}
+
+impl<T: Quasirandom> Qrng<T> {
+    /// What a `Qrng` supports, in the terms `sequences::UniformSequence`
+    /// uses for the other sequences
+    pub const CAPABILITIES: sequences::Capabilities = sequences::Capabilities {
+        max_dimension: Some(MAX_DIMENSION),
+        progressive: true,
+        scrambling: false,
+        random_access: true,
+    };
+
+    /// The next `n` values, as `gen` would return them one by one.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// let mut qrng = Qrng::<(f64, u8)>::new(0.5);
+    /// let sample = qrng.gen_n(100);
+    /// assert_eq!(sample.len(), 100);
+    /// assert_eq!(qrng.gen(), Qrng::<(f64, u8)>::new(0.5).sample_at(100));
+    /// ```
+    pub fn gen_n(&mut self, n: usize) -> Vec<T> {
+        (0..n).map(|_| T::generate(&mut self.state)).collect()
+    }
+}
+
+impl<const N: usize, T: Quasirandom<State = State<N>>> Qrng<T> {
+    /// Export the generator state as one little-endian `u64` per dimension,
+    /// the fixed-point fraction of the last point.
+    ///
+    /// The result is a plain fixed-size array so it can be embedded in any
+    /// binary format. Restore it with `from_state_bytes`.
+    pub fn state_bytes(&self) -> [[u8; 8]; N] {
+        self.state.fixed.map(u64::to_le_bytes)
+    }
+
+    /// Rebuild a generator from the output of `state_bytes`. The restored
+    /// generator continues exactly where the exported one left off, and
+    /// counts `sample_at` indices from there.
+    pub fn from_state_bytes(bytes: [[u8; 8]; N]) -> Self {
+        Self { state: State::new(bytes.map(u64::from_le_bytes)) }
     }
 
+    /// The value that `gen` returns after `index` earlier calls, counted from
+    /// construction, without changing the generator. It is bit-identical
+    /// to the value reached by stepping.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// let mut qrng = Qrng::<(f64, u16)>::new(0.3);
+    /// let sample = qrng.sample_at(1_000_000);
+    /// for _ in 0..1_000_000 {
+    ///     qrng.gen();
+    /// }
+    /// assert_eq!(qrng.gen(), sample);
+    /// ```
+    pub fn sample_at(&self, index: u64) -> T {
+        T::generate(&mut self.state.seek(index))
+    }
+
+    /// The number of values generated so far, counted from construction as
+    /// in `sample_at`, so that `sample_at(qrng.index())` is the next value.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// let mut qrng = Qrng::<f64>::new(0.5);
+    /// for _ in 0..1000 {
+    ///     let x = qrng.gen();
+    ///     if x < 0.001 {
+    ///         println!("failure at sample {}", qrng.index() - 1);
+    ///     }
+    /// }
+    ///
+    /// // Reproduce sample 42 later
+    /// let mut replay = Qrng::<f64>::new(0.5);
+    /// replay.set_index(42);
+    /// assert_eq!(replay.gen(), qrng.sample_at(42));
+    /// ```
+    pub fn index(&self) -> u64 {
+        self.state.index
+    }
+
+    /// Moves to `index`, so the next `gen` returns `sample_at(index)`. Takes
+    /// constant time in either direction.
+    pub fn set_index(&mut self, index: u64) {
+        self.state = self.state.seek(index);
+    }
+
+    /// Skips the next `n` values in constant time, like the `discard` of C++
+    /// `<random>` engines: the same as `n` calls to `gen`.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// let mut qrng = Qrng::<(f64, f64)>::new(0.5);
+    /// let mut skipped = qrng.clone();
+    /// for _ in 0..1000 {
+    ///     qrng.gen();
+    /// }
+    /// skipped.discard(1000);
+    /// assert_eq!(skipped.gen(), qrng.gen());
+    /// ```
+    pub fn discard(&mut self, n: u64) {
+        self.set_index(self.index().wrapping_add(n));
+    }
+
+    /// Steps the generator back by `n` values, so the next `n` calls to `gen`
+    /// repeat the last `n` values, bit for bit. Rewinding past the start
+    /// continues the sequence backwards.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// let mut qrng = Qrng::<(f64, f64)>::new(0.5);
+    /// let speculative = [qrng.gen(), qrng.gen(), qrng.gen()];
+    /// // ... roll back the last two steps of the simulation
+    /// qrng.rewind(2);
+    /// assert_eq!(qrng.gen(), speculative[1]);
+    /// ```
+    pub fn rewind(&mut self, n: u64) {
+        self.set_index(self.index().wrapping_sub(n));
+    }
+
+    /// The value the next `gen` will return, without advancing.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// let mut qrng = Qrng::<f64>::new(0.5);
+    /// let next = qrng.peek();
+    /// assert_eq!(qrng.peek(), next);
+    /// assert_eq!(qrng.gen(), next);
+    /// ```
+    pub fn peek(&self) -> T {
+        T::generate(&mut self.state.clone())
+    }
 }
 
 macro_rules! define_from_uniform {
@@ -189,21 +684,18 @@ macro_rules! define_from_uniform {
         impl<$($t: FromUniform,)*> Quasirandom for ($($t,)*) {
             #[doc(hidden)]
             type State = State<{$n}>;
+
+            fn generate(state: &mut Self::State) -> Self {
+                let [$($x,)*] = state.gen();
+                ($($t::from_uniform($x),)*)
+            }
         }
         impl<$($t: FromUniform,)*> Qrng<($($t,)*)> {
             pub fn new(seed: f64) -> Self {
-                assert!(seed >= 0.0);
-                assert!(seed < 1.0);
-                let mut seeds = [0.0; $n];
-                for i in 0..$n {
-                    seeds[i] = (seed * i as f64).fract();
-                }
-
-                Self { state: State(seeds) }
+                Self { state: State::seeded(seed) }
             }
             pub fn gen(&mut self) -> ($($t,)*) {
-                let [$($x,)*] = self.state.gen();
-                ($($t::from_uniform(*$x),)*)
+                <($($t,)*)>::generate(&mut self.state)
             }
         }
     };
@@ -222,6 +714,18 @@ macro_rules! define_from_uniform {
 
 define_from_uniform!(T31 T30 T29 T28 T27 T26 T25 T24 T23 T22 T21 T20 T19 T18 T17 T16 T15 T14 T13 T12 T11 T10 T9 T8 T7 T6 T5 T4 T3 T2 T1 T0);
 
+/// Arrays of up to 32 elements, one dimension per element. Larger arrays
+/// fail to compile with a message pointing at `RSequence`.
+impl<T: FromUniform, const N: usize> Qrng<[T; N]> {
+    pub fn new(seed: f64) -> Self {
+        Self { state: State::seeded(seed) }
+    }
+
+    pub fn gen(&mut self) -> [T; N] {
+        <[T; N]>::generate(&mut self.state)
+    }
+}
+
 /// The binary search finds the unique positive root of x^(d+1) = x + 1, and
 /// the magic numbers emitted in the loop are that the inverse of that root
 /// exponentiated by increasing integers. See the following blog post by
@@ -250,7 +754,6 @@ define_from_uniform!(T31 T30 T29 T28 T27 T26 T25 T24 T23 T22 T21 T20 T19 T18 T17
 ///     println!("    {:?},", parameters);
 /// }
 /// ```
-
 use std::f64::NAN;
 static CONSTANTS: [[f64; 32]; 32] = [
     [0.6180339887498955, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN, NAN],
@@ -360,4 +863,153 @@ mod tests {
         let rng_standard_deviation = standard_deviation(rng_distance_mean, rng_distances);
         assert!(qrng_standard_deviation < rng_standard_deviation / 3.0);
     }
+
+    #[test]
+    fn state_bytes_round_trip() {
+        let mut qrng = Qrng::<(f64, u32, bool)>::new(0.37);
+        for _ in 0..100 {
+            qrng.gen();
+        }
+        let mut restored = Qrng::<(f64, u32, bool)>::from_state_bytes(qrng.state_bytes());
+        for _ in 0..100 {
+            assert_eq!(qrng.gen(), restored.gen());
+        }
+
+        let single = Qrng::<f64>::new(0.5);
+        assert_eq!(single.state_bytes().len(), 1);
+    }
+
+    #[test]
+    fn sample_at_matches_stepping() {
+        let mut qrng = Qrng::<[f64; 7]>::new(0.61);
+        let copy = qrng.clone();
+        for index in 0..10_000 {
+            assert_eq!(copy.sample_at(index), qrng.gen());
+        }
+        // Indices are counted from construction, not from the current point
+        assert_eq!(qrng.sample_at(5), copy.sample_at(5));
+
+        // Rewinding undoes steps exactly, even past the start
+        assert_eq!(qrng.index(), 10_000);
+        qrng.rewind(10_000);
+        assert_eq!(qrng.state.fixed, copy.state.fixed);
+        qrng.rewind(3);
+        for _ in 0..3 {
+            qrng.gen();
+        }
+        assert_eq!(qrng.gen(), copy.sample_at(0));
+
+        // 2^64 steps bring every coordinate back around
+        let mut state = copy.state.seek(u64::MAX);
+        state.gen();
+        assert_eq!((state.fixed, state.index), (copy.state.fixed, 0));
+    }
+
+    #[test]
+    fn weighted_result() {
+        struct Quarter;
+        impl Probability for Quarter {
+            const VALUE: f64 = 0.25;
+        }
+
+        let mut qrng = Qrng::<Weighted<Result<f64, f64>, Quarter>>::new(0.0);
+        let n = 10_000;
+        let mut oks = 0;
+        for _ in 0..n {
+            let value = qrng.gen().into_inner();
+            if value.is_ok() {
+                oks += 1;
+            }
+            let (Ok(x) | Err(x)) = value;
+            assert!((0.0..1.0).contains(&x));
+        }
+        assert!((oks as f64 / n as f64 - 0.25).abs() < 1e-3);
+    }
+
+    #[test]
+    fn outputs_are_half_open() {
+        assert_eq!(MAX_UNIFORM.next_up(), 1.0);
+        assert!(f32::from_uniform(MAX_UNIFORM) < 1.0);
+        assert_eq!(u8::from_uniform(MAX_UNIFORM), u8::MAX);
+        assert_eq!(u64::from_uniform(MAX_UNIFORM), u64::MAX - 2047);
+        assert_eq!(i8::from_uniform(MAX_UNIFORM), i8::MAX);
+        assert_eq!(i8::from_uniform(0.0), i8::MIN);
+
+        // Every integer is equally likely
+        let mut qrng = Qrng::<u8>::new(0.0);
+        let mut histogram = [0; 256];
+        (0..256 * 100).for_each(|_| histogram[qrng.gen() as usize] += 1);
+        assert!(histogram.iter().all(|&count| (count - 100_i32).abs() <= 2), "{:?}", histogram);
+
+        // Rescaling the part above the probability rounds up to one here
+        assert_eq!((MAX_UNIFORM - 0.3) / (1.0 - 0.3), 1.0);
+        let Weighted(Err(x), _) = Weighted::<Result<(), f64>, Percent<30>>::from_uniform(MAX_UNIFORM) else { panic!() };
+        assert!(x < 1.0);
+
+        // The largest state converts to the largest value below one
+        assert_eq!(from_fixed(u64::MAX), MAX_UNIFORM);
+        let mut state = State::new([0_u64.wrapping_sub(State::<1>::ALPHAS[0]).wrapping_sub(1)]);
+        assert_eq!(state.gen(), [MAX_UNIFORM]);
+        assert_eq!(state.gen(), [from_fixed(State::<1>::ALPHAS[0] - 1)]);
+        let mut qrng = Qrng::<[f32; 1]>::from_state_bytes([u64::MAX.to_le_bytes()]);
+        assert!(qrng.gen()[0] < 1.0);
+    }
+
+    #[test]
+    fn split_tuples_cover_the_grid() {
+        // 64 consecutive strata of the uniform interval fill the 8 x 8 grid
+        let mut grid = [[0; 8]; 8];
+        for i in 0..64 {
+            let Split((x, y)) = Split::<(u8, u8)>::from_uniform((i as f64 + 0.5) / 64.0);
+            grid[(x >> 5) as usize][(y >> 5) as usize] += 1;
+        }
+        assert_eq!(grid, [[1; 8]; 8]);
+
+        let Split((a, b, c)) = Split::<(f64, f64, f64)>::from_uniform(MAX_UNIFORM);
+        assert!(a < 1.0 && b < 1.0 && c < 1.0);
+        assert_eq!(Split::<(f64, bool)>::from_uniform(0.0), Split((0.0, true)));
+
+        let mut qrng = Qrng::<(Option<Split<(f64, bool)>>, f64)>::new(0.5);
+        let somes = (0..1000).filter(|_| qrng.gen().0.is_some()).count();
+        assert!((somes as i32 - 500).abs() <= 2);
+    }
+
+    #[test]
+    fn std_enums_split_evenly() {
+        use std::cmp::Ordering;
+        use std::ops::{Bound, ControlFlow};
+
+        let mut qrng = Qrng::<(Ordering, ControlFlow<(), ()>, Bound<u8>)>::new(0.5);
+        let (mut less, mut breaks, mut unbounded) = (0, 0, 0);
+        for _ in 0..3000 {
+            let (ordering, flow, bound) = qrng.gen();
+            less += (ordering == Ordering::Less) as i32;
+            breaks += flow.is_break() as i32;
+            unbounded += (bound == Bound::Unbounded) as i32;
+        }
+        assert!([less - 1000, breaks - 1500, unbounded - 1000].iter().all(|d| d.abs() <= 10), "{} {} {}", less, breaks, unbounded);
+        assert_eq!(Bound::<u8>::from_uniform(0.5), Bound::Excluded(128));
+    }
+
+    #[test]
+    fn network_addresses_cover_their_space() {
+        use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4};
+
+        assert_eq!(Ipv4Addr::from_uniform(0.5), Ipv4Addr::new(128, 0, 0, 0));
+        assert_eq!(Ipv4Addr::from_uniform(MAX_UNIFORM), Ipv4Addr::BROADCAST);
+        assert_eq!(Ipv6Addr::from_uniform(0.25).segments()[0], 0x4000);
+        assert_eq!(SocketAddrV4::from_uniform(0.0), SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
+        assert_eq!(SocketAddrV4::from_uniform(MAX_UNIFORM), SocketAddrV4::new(Ipv4Addr::BROADCAST, u16::MAX));
+
+        // Every /8 network and every 256-port block is hit equally often
+        let mut qrng = Qrng::<SocketAddrV4>::new(0.5);
+        let (mut networks, mut ports) = ([0i32; 256], [0i32; 256]);
+        for _ in 0..25600 {
+            let address = qrng.gen();
+            networks[address.ip().octets()[0] as usize] += 1;
+            ports[(address.port() >> 8) as usize] += 1;
+        }
+        assert!(networks.iter().all(|&count| (count - 100).abs() <= 2), "{:?}", networks);
+        assert!(ports.iter().all(|&count| (count - 100).abs() <= 30), "{:?}", ports);
+    }
 }
@@ -0,0 +1,174 @@
+//! Multiple importance sampling: combining the samples of several warps.
+//!
+//! An integrand often matches different sampling strategies in different
+//! places, like a glossy reflection that follows the light's shape for small
+//! lights and the BRDF's for large ones. Multiple importance sampling (Veach,
+//! 1995) draws from every strategy and weights each sample by how well its
+//! own strategy explains it compared to the others, which keeps the estimate
+//! unbiased while each region is dominated by the strategy that suits it.
+//!
+//! `Combination` does this for two `Warp`s, taking every sample of both
+//! strategies from one quasirandom point.
+
+use crate::warp::Warp;
+
+/// The balance heuristic weight of a sample from a strategy with `n` samples
+/// and density `pdf`, against one with `other_n` samples and density
+/// `other_pdf` at the same point
+pub fn balance_heuristic(n: usize, pdf: f64, other_n: usize, other_pdf: f64) -> f64 {
+    let (f, g) = (n as f64 * pdf, other_n as f64 * other_pdf);
+    if f == 0.0 { 0.0 } else { f / (f + g) }
+}
+
+/// The power heuristic weight, with exponent two, of a sample from a strategy
+/// with `n` samples and density `pdf`, against one with `other_n` samples
+/// and density `other_pdf` at the same point. It reduces variance further
+/// than the balance heuristic where one strategy is much better than the
+/// other.
+pub fn power_heuristic(n: usize, pdf: f64, other_n: usize, other_pdf: f64) -> f64 {
+    let (f, g) = (n as f64 * pdf, other_n as f64 * other_pdf);
+    if f == 0.0 { 0.0 } else { f * f / (f * f + g * g) }
+}
+
+/// A heuristic for weighting the samples of each strategy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Heuristic {
+    Balance,
+    #[default]
+    Power,
+}
+
+impl Heuristic {
+    /// The weight of a sample from the first strategy, as in
+    /// `balance_heuristic` and `power_heuristic`
+    pub fn weight(self, n: usize, pdf: f64, other_n: usize, other_pdf: f64) -> f64 {
+        match self {
+            Heuristic::Balance => balance_heuristic(n, pdf, other_n, other_pdf),
+            Heuristic::Power => power_heuristic(n, pdf, other_n, other_pdf),
+        }
+    }
+}
+
+/// Two sampling strategies over the same domain, with a number of samples
+/// each, combined with multiple importance sampling.
+///
+/// ```
+/// use quasirandom::mis::Combination;
+/// use quasirandom::sequences::{RSequence, UniformSequence};
+/// use quasirandom::warp::{CosineHemisphere, Ggx};
+///
+/// // The integral of a glossy lobe, cos(theta)^20, over the hemisphere:
+/// // 2 pi / 21
+/// let combination = Combination::new(CosineHemisphere, Ggx { alpha: 0.2 });
+/// let mut sequence = RSequence::new(combination.dimension(), 0.5);
+/// let mut point = vec![0.0; combination.dimension()];
+/// let n = 10_000;
+/// let mut sum = 0.0;
+/// for _ in 0..n {
+///     sequence.next_into(&mut point);
+///     sum += combination.estimate(&point, |direction| direction[2].powi(20));
+/// }
+/// let exact = 2.0 * std::f64::consts::PI / 21.0;
+/// assert!((sum / n as f64 / exact - 1.0).abs() < 0.01);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Combination<A, B> {
+    first: A,
+    second: B,
+    samples: (usize, usize),
+    heuristic: Heuristic,
+}
+
+impl<A: Warp, B: Warp<Output = A::Output>> Combination<A, B> {
+    /// One sample from each strategy, weighted with the power heuristic
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            samples: (1, 1),
+            heuristic: Heuristic::Power,
+        }
+    }
+
+    /// The number of samples each estimate takes from each strategy
+    pub fn with_samples(mut self, first: usize, second: usize) -> Self {
+        assert!(first + second > 0);
+        self.samples = (first, second);
+        self
+    }
+
+    pub fn with_heuristic(mut self, heuristic: Heuristic) -> Self {
+        self.heuristic = heuristic;
+        self
+    }
+
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+
+    /// The number of uniforms each estimate consumes: those of every sample
+    /// of the first strategy, then those of the second
+    pub fn dimension(&self) -> usize {
+        self.samples.0 * self.first.dimension() + self.samples.1 * self.second.dimension()
+    }
+
+    /// One unbiased estimate of the integral of `f` over the domain, from
+    /// the first `dimension()` values of `u`
+    pub fn estimate(&self, u: &[f64], mut f: impl FnMut(&A::Output) -> f64) -> f64 {
+        let (n_first, n_second) = self.samples;
+        let (u_first, u_second) = u.split_at(n_first * self.first.dimension());
+        let mut sum = 0.0;
+        for u in u_first.chunks(self.first.dimension().max(1)).take(n_first) {
+            let (x, pdf) = self.first.warp(u);
+            if pdf > 0.0 {
+                let weight = self.heuristic.weight(n_first, pdf, n_second, self.second.pdf(&x));
+                sum += weight * f(&x) / (n_first as f64 * pdf);
+            }
+        }
+        for u in u_second.chunks(self.second.dimension().max(1)).take(n_second) {
+            let (x, pdf) = self.second.warp(u);
+            if pdf > 0.0 {
+                let weight = self.heuristic.weight(n_second, pdf, n_first, self.first.pdf(&x));
+                sum += weight * f(&x) / (n_second as f64 * pdf);
+            }
+        }
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequences::{RSequence, UniformSequence};
+    use crate::warp::{Annulus, UnitDisk};
+    use std::f64::consts::PI;
+
+    #[test]
+    fn strategies_covering_part_of_the_domain_combine() {
+        assert_eq!(balance_heuristic(1, 3.0, 2, 1.0), 0.6);
+        assert_eq!(power_heuristic(1, 3.0, 2, 1.0), 9.0 / 13.0);
+        assert_eq!(power_heuristic(1, 0.0, 1, 0.0), 0.0);
+
+        // Neither strategy covers the disk of radius two alone, but together
+        // they estimate its area
+        for heuristic in [Heuristic::Balance, Heuristic::Power] {
+            let combination = Combination::new(UnitDisk, Annulus::new(1.0, 2.0))
+                .with_samples(1, 3)
+                .with_heuristic(heuristic);
+            assert_eq!(combination.dimension(), 8);
+            let mut sequence = RSequence::new(combination.dimension(), 0.25);
+            let mut point = vec![0.0; 8];
+            let n = 10_000;
+            let mut sum = 0.0;
+            for _ in 0..n {
+                sequence.next_into(&mut point);
+                sum += combination.estimate(&point, |_| 1.0);
+            }
+            assert!((sum / n as f64 / (4.0 * PI) - 1.0).abs() < 1e-3, "{}", sum / n as f64);
+        }
+    }
+}
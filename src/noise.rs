@@ -0,0 +1,245 @@
+//! Baking tileable noise textures.
+//!
+//! Dithering, stochastic transparency, and per-pixel sample offsets all read
+//! a precomputed noise texture, and the texture's spectrum decides how the
+//! noise looks. A `NoiseTexture` ranks the cells of a 2D or 3D grid by the
+//! order in which the R-sequence first visits them. Because the sequence
+//! lives on the torus, the texture tiles seamlessly, every value appears
+//! exactly once, and cells with nearby values are spread out, which already
+//! suppresses low frequencies. `relax` pushes the spectrum further towards
+//! blue noise.
+//!
+//! Textures can be saved as PNG with the `image` feature.
+
+use std::io::{self, Write};
+
+use crate::hash::{combine, mix};
+use crate::Qrng;
+
+/// A tileable noise texture with values in `[0, 1)`.
+///
+/// ```
+/// use quasirandom::noise::NoiseTexture;
+///
+/// let mut noise = NoiseTexture::ranked_2d(64, 64, 0.5);
+/// noise.relax(20_000, 1);
+/// let bytes = noise.to_u8();
+/// assert_eq!(bytes.len(), 64 * 64);
+///
+/// // Every gray level is used equally often
+/// let mut histogram = [0; 256];
+/// bytes.iter().for_each(|&b| histogram[b as usize] += 1);
+/// assert!(histogram.iter().all(|&count| count == 16));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseTexture {
+    size: [usize; 3],
+    /// The rank of each cell, in row-major order with `x` fastest
+    ranks: Vec<u32>,
+}
+
+impl NoiseTexture {
+    /// A `width` by `height` texture ranked by a 2D `Qrng` with the given seed
+    pub fn ranked_2d(width: usize, height: usize, seed: f64) -> Self {
+        let mut qrng = Qrng::<[f64; 2]>::new(seed);
+        Self::ranked([width, height, 1], || {
+            let [x, y] = qrng.gen();
+            [x, y, 0.0]
+        })
+    }
+
+    /// A `width` by `height` by `depth` texture ranked by a 3D `Qrng` with the
+    /// given seed
+    pub fn ranked_3d(width: usize, height: usize, depth: usize, seed: f64) -> Self {
+        let mut qrng = Qrng::<[f64; 3]>::new(seed);
+        Self::ranked([width, height, depth], || qrng.gen())
+    }
+
+    fn ranked(size: [usize; 3], mut next: impl FnMut() -> [f64; 3]) -> Self {
+        assert!(size.iter().all(|&n| n > 0));
+        let cells = size.iter().product::<usize>();
+        assert!(cells <= u32::MAX as usize);
+        let mut ranks = vec![u32::MAX; cells];
+        let mut ranked = 0;
+        // Once the sequence has had plenty of chances, rank the few cells it
+        // has not reached yet in order
+        for _ in 0..64 * cells {
+            if ranked == cells {
+                break;
+            }
+            let point = next();
+            let [x, y, z] = std::array::from_fn(|k| ((point[k] * size[k] as f64) as usize).min(size[k] - 1));
+            let cell = &mut ranks[(z * size[1] + y) * size[0] + x];
+            if *cell == u32::MAX {
+                *cell = ranked as u32;
+                ranked += 1;
+            }
+        }
+        for cell in ranks.iter_mut().filter(|cell| **cell == u32::MAX) {
+            *cell = ranked as u32;
+            ranked += 1;
+        }
+        Self { size, ranks }
+    }
+
+    pub fn width(&self) -> usize {
+        self.size[0]
+    }
+
+    pub fn height(&self) -> usize {
+        self.size[1]
+    }
+
+    /// One for 2D textures
+    pub fn depth(&self) -> usize {
+        self.size[2]
+    }
+
+    /// The value at `(x, y, z)`, wrapping around at the edges
+    pub fn get(&self, x: usize, y: usize, z: usize) -> f32 {
+        let [w, h, d] = self.size;
+        self.value(self.ranks[((z % d) * h + y % h) * w + x % w])
+    }
+
+    /// All values, in row-major order with `x` fastest, then `y`, then `z`
+    pub fn values(&self) -> Vec<f32> {
+        self.ranks.iter().map(|&rank| self.value(rank)).collect()
+    }
+
+    /// All values as 8-bit levels. When the number of cells is a multiple of
+    /// 256, every level is used equally often.
+    pub fn to_u8(&self) -> Vec<u8> {
+        let cells = self.ranks.len() as u64;
+        self.ranks.iter().map(|&rank| (rank as u64 * 256 / cells) as u8).collect()
+    }
+
+    /// All values as 16-bit levels, like `to_u8`
+    pub fn to_u16(&self) -> Vec<u16> {
+        let cells = self.ranks.len() as u64;
+        self.ranks.iter().map(|&rank| (rank as u64 * 65536 / cells) as u16).collect()
+    }
+
+    /// Writes the 8-bit levels as raw bytes, in the order of `values`.
+    pub fn write_raw_u8(&self, mut out: impl Write) -> io::Result<()> {
+        out.write_all(&self.to_u8())
+    }
+
+    /// Writes the 16-bit levels as raw little-endian bytes, in the order of
+    /// `values`.
+    pub fn write_raw_u16(&self, mut out: impl Write) -> io::Result<()> {
+        let bytes: Vec<u8> = self.to_u16().iter().flat_map(|level| level.to_le_bytes()).collect();
+        out.write_all(&bytes)
+    }
+
+    /// Saves the 8-bit levels as a grayscale PNG. The slices of a 3D texture
+    /// are stacked vertically.
+    #[cfg(feature = "image")]
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> Result<(), image::ImageError> {
+        let [w, h, d] = self.size;
+        let image = image::GrayImage::from_raw(w as u32, (h * d) as u32, self.to_u8()).unwrap();
+        image.save_with_format(path, image::ImageFormat::Png)
+    }
+
+    /// Makes the texture closer to blue noise by trying `iterations` swaps of
+    /// nearby cells, chosen pseudorandomly from `seed`, and keeping those
+    /// that lower the energy of Georgiev and Fajardo, "Blue-Noise Dithered
+    /// Sampling" (2016). Swaps keep every value in the texture.
+    pub fn relax(&mut self, iterations: usize, seed: u64) {
+        let cells = self.ranks.len() as u64;
+        for i in 0..iterations {
+            let hash = combine(seed, i as u64);
+            let p = self.position((hash % cells) as usize);
+            let offsets = mix(hash);
+            let q = std::array::from_fn(|k| {
+                let offset = (offsets >> (8 * k)) % (2 * RADIUS + 1) as u64;
+                (p[k] + offset as usize + self.size[k] * RADIUS - RADIUS) % self.size[k]
+            });
+            let (a, b) = (self.index(p), self.index(q));
+            if a == b {
+                continue;
+            }
+            let before = self.energy(p) + self.energy(q);
+            self.ranks.swap(a, b);
+            if self.energy(p) + self.energy(q) >= before {
+                self.ranks.swap(a, b);
+            }
+        }
+    }
+
+    fn value(&self, rank: u32) -> f32 {
+        let value = (rank as f64 / self.ranks.len() as f64) as f32;
+        value.min(1.0 - f32::EPSILON / 2.0)
+    }
+
+    fn position(&self, index: usize) -> [usize; 3] {
+        let [w, h, _] = self.size;
+        [index % w, index / w % h, index / (w * h)]
+    }
+
+    fn index(&self, [x, y, z]: [usize; 3]) -> usize {
+        (z * self.size[1] + y) * self.size[0] + x
+    }
+
+    /// The energy between cell `p` and the cells around it on the torus
+    fn energy(&self, p: [usize; 3]) -> f64 {
+        let cells = self.ranks.len() as f64;
+        let value = self.ranks[self.index(p)] as f64 / cells;
+        let reach = self.size.map(|n| RADIUS.min((n - 1) / 2) as isize);
+        let mut energy = 0.0;
+        for dz in -reach[2]..=reach[2] {
+            for dy in -reach[1]..=reach[1] {
+                for dx in -reach[0]..=reach[0] {
+                    if (dx, dy, dz) == (0, 0, 0) {
+                        continue;
+                    }
+                    let q: [usize; 3] =
+                        std::array::from_fn(|k| (p[k] as isize + [dx, dy, dz][k]).rem_euclid(self.size[k] as isize) as usize);
+                    let other = self.ranks[self.index(q)] as f64 / cells;
+                    let distance = (dx * dx + dy * dy + dz * dz) as f64;
+                    energy += (-distance / (SIGMA_I * SIGMA_I) - (value - other).abs().sqrt() / (SIGMA_S * SIGMA_S)).exp();
+                }
+            }
+        }
+        energy
+    }
+}
+
+/// How far, in cells, `relax` looks around a cell
+const RADIUS: usize = 3;
+const SIGMA_I: f64 = 2.1;
+const SIGMA_S: f64 = 1.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn textures_are_permutations_and_relax() {
+        for mut noise in [NoiseTexture::ranked_2d(32, 16, 0.5), NoiseTexture::ranked_3d(8, 8, 8, 0.5)] {
+            let cells = noise.ranks.len();
+            let mut sorted = noise.ranks.clone();
+            sorted.sort_unstable();
+            assert!(sorted.iter().enumerate().all(|(i, &rank)| rank as usize == i));
+
+            let total = |noise: &NoiseTexture| (0..cells).map(|i| noise.energy(noise.position(i))).sum::<f64>();
+            let before = total(&noise);
+            noise.relax(5000, 3);
+            assert!(total(&noise) < before);
+            let mut relaxed = noise.ranks.clone();
+            relaxed.sort_unstable();
+            assert_eq!(relaxed, sorted);
+        }
+
+        // Neighbors differ far more than in white noise, where the mean
+        // difference is 1/3
+        let noise = NoiseTexture::ranked_2d(64, 64, 0.0);
+        let mut difference = 0.0;
+        for y in 0..64 {
+            for x in 0..64 {
+                difference += (noise.get(x, y, 0) - noise.get(x + 1, y, 0)).abs() as f64 / 4096.0;
+            }
+        }
+        assert!(difference > 0.37, "{}", difference);
+        assert_eq!(noise.get(64, 65, 3), noise.get(0, 1, 0));
+    }
+}
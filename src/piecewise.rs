@@ -0,0 +1,442 @@
+//! Piecewise-constant distributions over `[0, 1)`, `[0, 1)^2`, and
+//! `[0, 1)^3`.
+//!
+//! These turn a histogram, an image, or a density volume into a warp that
+//! maps uniform samples to samples distributed proportionally to the
+//! histogram. The warps are monotone in each coordinate, so evenly spread
+//! inputs stay evenly spread relative to the target density.
+//!
+//! `Piecewise1D` and `Piecewise2D` are PBRT's `Distribution1D` and
+//! `Distribution2D`, also available under those names, and
+//! `EnvironmentDistribution` uses them to importance sample environment maps.
+
+use std::f64::consts::PI;
+
+use crate::warp::{is_unit, Warp};
+use crate::MAX_UNIFORM;
+
+/// PBRT's name for `Piecewise1D`
+pub type Distribution1D = Piecewise1D;
+
+/// PBRT's name for `Piecewise2D`
+pub type Distribution2D = Piecewise2D;
+
+/// A piecewise-constant distribution over `[0, 1)` with one constant segment
+/// per histogram bin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Piecewise1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Piecewise1D {
+    /// Builds the distribution from nonnegative bin weights, which do not need
+    /// to be normalized. If every weight is zero the distribution is uniform.
+    pub fn new(weights: &[f64]) -> Self {
+        assert!(!weights.is_empty());
+        assert!(weights.iter().all(|&w| w >= 0.0 && w.is_finite()));
+
+        let n = weights.len() as f64;
+        let mut cdf = Vec::with_capacity(weights.len() + 1);
+        cdf.push(0.0);
+        let mut sum = 0.0;
+        for &w in weights {
+            sum += w / n;
+            cdf.push(sum);
+        }
+        let integral = sum;
+        for (i, c) in cdf.iter_mut().enumerate() {
+            *c = if integral > 0.0 { *c / integral } else { i as f64 / n };
+        }
+
+        Self {
+            func: weights.to_vec(),
+            cdf,
+            integral,
+        }
+    }
+
+    /// The number of bins
+    pub fn len(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.func.is_empty()
+    }
+
+    /// The integral of the unnormalized weights over `[0, 1)`
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Maps a uniform `u` to a sample, returning the sample, its density, and
+    /// the bin it fell in.
+    pub fn sample_continuous(&self, u: f64) -> (f64, f64, usize) {
+        let n = self.func.len();
+        let offset = (self.cdf.partition_point(|&c| c <= u).max(1) - 1).min(n - 1);
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let mut du = u - self.cdf[offset];
+        if width > 0.0 {
+            du /= width;
+        }
+        let x = ((offset as f64 + du) / n as f64).clamp(0.0, MAX_UNIFORM);
+        (x, self.pdf_of_bin(offset), offset)
+    }
+
+    /// Maps a uniform `u` to a bin, returning the bin, its probability, and
+    /// `u` rescaled to `[0, 1)` within the bin, which can be reused as a
+    /// fresh uniform.
+    pub fn sample_discrete(&self, u: f64) -> (usize, f64, f64) {
+        let (x, _, bin) = self.sample_continuous(u);
+        let remapped = (x * self.func.len() as f64 - bin as f64).clamp(0.0, MAX_UNIFORM);
+        (bin, self.cdf[bin + 1] - self.cdf[bin], remapped)
+    }
+
+    /// The density at `x` in `[0, 1)`
+    pub fn pdf(&self, x: f64) -> f64 {
+        let n = self.func.len();
+        let bin = ((x * n as f64) as usize).min(n - 1);
+        self.pdf_of_bin(bin)
+    }
+
+    fn pdf_of_bin(&self, bin: usize) -> f64 {
+        if self.integral > 0.0 {
+            self.func[bin] / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A piecewise-constant distribution over `[0, 1)^2` built from a row-major
+/// grid of weights.
+///
+/// Sampling first chooses a row from the marginal distribution of row sums
+/// using the second uniform, then a column within that row using the first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Piecewise2D {
+    conditional: Vec<Piecewise1D>,
+    marginal: Piecewise1D,
+}
+
+impl Piecewise2D {
+    /// `weights` holds `height` rows of `width` nonnegative weights each.
+    pub fn new(width: usize, height: usize, weights: &[f64]) -> Self {
+        assert!(width > 0 && height > 0);
+        assert_eq!(weights.len(), width * height);
+        let conditional: Vec<Piecewise1D> = weights.chunks(width).map(Piecewise1D::new).collect();
+        let row_integrals: Vec<f64> = conditional.iter().map(Piecewise1D::integral).collect();
+        let marginal = Piecewise1D::new(&row_integrals);
+        Self { conditional, marginal }
+    }
+
+    pub fn width(&self) -> usize {
+        self.conditional[0].len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.conditional.len()
+    }
+
+    /// The integral of the unnormalized weights over `[0, 1)^2`
+    pub fn integral(&self) -> f64 {
+        self.marginal.integral()
+    }
+
+    /// Maps the uniform pair `(u, v)` to a point and its density.
+    pub fn sample_continuous(&self, u: f64, v: f64) -> ([f64; 2], f64) {
+        let (y, pdf_y, row) = self.marginal.sample_continuous(v);
+        let (x, pdf_x, _) = self.conditional[row].sample_continuous(u);
+        ([x, y], pdf_x * pdf_y)
+    }
+
+    /// The density at `point` in `[0, 1)^2`
+    pub fn pdf(&self, point: [f64; 2]) -> f64 {
+        let row = ((point[1] * self.height() as f64) as usize).min(self.height() - 1);
+        self.marginal.pdf(point[1]) * self.conditional[row].pdf(point[0])
+    }
+}
+
+/// A piecewise-constant distribution over `[0, 1)^3` built from a grid of
+/// weights, such as a density volume.
+///
+/// Sampling chooses a slice from the marginal distribution of slice sums
+/// using the third uniform, then samples that slice like a `Piecewise2D`.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::piecewise::Piecewise3D;
+///
+/// // A 4x4x4 volume with all its density in one voxel
+/// let mut density = vec![0.0; 64];
+/// density[2 * 16 + 1 * 4 + 3] = 5.0;
+/// let volume = Piecewise3D::new(4, 4, 4, &density);
+/// let mut qrng = Qrng::<[f64; 3]>::new(0.5);
+/// let ([x, y, z], pdf) = volume.sample_continuous(qrng.gen());
+/// assert!((x * 4.0) as usize == 3 && (y * 4.0) as usize == 1 && (z * 4.0) as usize == 2);
+/// assert_eq!(pdf, 64.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Piecewise3D {
+    slices: Vec<Piecewise2D>,
+    marginal: Piecewise1D,
+}
+
+impl Piecewise3D {
+    /// `weights` holds `depth` slices of `height` rows of `width` nonnegative
+    /// weights each, so the weight of voxel `(x, y, z)` is at index
+    /// `(z * height + y) * width + x`.
+    pub fn new(width: usize, height: usize, depth: usize, weights: &[f64]) -> Self {
+        assert!(width > 0 && height > 0 && depth > 0);
+        assert_eq!(weights.len(), width * height * depth);
+        let slices: Vec<Piecewise2D> =
+            weights.chunks(width * height).map(|slice| Piecewise2D::new(width, height, slice)).collect();
+        let slice_integrals: Vec<f64> = slices.iter().map(Piecewise2D::integral).collect();
+        let marginal = Piecewise1D::new(&slice_integrals);
+        Self { slices, marginal }
+    }
+
+    pub fn width(&self) -> usize {
+        self.slices[0].width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.slices[0].height()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.slices.len()
+    }
+
+    /// The integral of the unnormalized weights over `[0, 1)^3`
+    pub fn integral(&self) -> f64 {
+        self.marginal.integral()
+    }
+
+    /// Maps a uniform point to a point and its density.
+    pub fn sample_continuous(&self, [u, v, w]: [f64; 3]) -> ([f64; 3], f64) {
+        let (z, pdf_z, slice) = self.marginal.sample_continuous(w);
+        let ([x, y], pdf_xy) = self.slices[slice].sample_continuous(u, v);
+        ([x, y, z], pdf_xy * pdf_z)
+    }
+
+    /// The density at `point` in `[0, 1)^3`
+    pub fn pdf(&self, point: [f64; 3]) -> f64 {
+        let slice = ((point[2] * self.depth() as f64) as usize).min(self.depth() - 1);
+        self.marginal.pdf(point[2]) * self.slices[slice].pdf([point[0], point[1]])
+    }
+}
+
+impl Warp for Piecewise1D {
+    type Output = f64;
+
+    fn dimension(&self) -> usize {
+        1
+    }
+
+    fn warp(&self, u: &[f64]) -> (f64, f64) {
+        let (x, pdf, _) = self.sample_continuous(u[0]);
+        (x, pdf)
+    }
+
+    fn pdf(&self, x: &f64) -> f64 {
+        if (0.0..1.0).contains(x) { Piecewise1D::pdf(self, *x) } else { 0.0 }
+    }
+}
+
+impl Warp for Piecewise2D {
+    type Output = [f64; 2];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; 2], f64) {
+        self.sample_continuous(u[0], u[1])
+    }
+
+    fn pdf(&self, point: &[f64; 2]) -> f64 {
+        if point.iter().all(|x| (0.0..1.0).contains(x)) { Piecewise2D::pdf(self, *point) } else { 0.0 }
+    }
+}
+
+impl Warp for Piecewise3D {
+    type Output = [f64; 3];
+
+    fn dimension(&self) -> usize {
+        3
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; 3], f64) {
+        self.sample_continuous([u[0], u[1], u[2]])
+    }
+
+    fn pdf(&self, point: &[f64; 3]) -> f64 {
+        if point.iter().all(|x| (0.0..1.0).contains(x)) { Piecewise3D::pdf(self, *point) } else { 0.0 }
+    }
+}
+
+/// Importance sampling of an environment map in the equirectangular
+/// projection, in proportion to its luminance.
+///
+/// Column `x` of the map is the azimuth `2 pi x / width` and row `y` the
+/// polar angle `pi y / height` from `+z`. Rows near the poles cover less solid
+/// angle, so each row is weighted by the sine of its polar angle, and the
+/// densities are per unit solid angle.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::piecewise::EnvironmentDistribution;
+///
+/// // A dim sky with a bright sun at the horizon
+/// let (width, height) = (64, 32);
+/// let mut luminance = vec![0.1; width * height];
+/// luminance[16 * width + 8] = 10000.0;
+/// let environment = EnvironmentDistribution::new(width, height, &luminance);
+///
+/// let mut qrng = Qrng::<[f64; 2]>::new(0.5);
+/// let near_sun = (0..1000)
+///     .filter(|_| environment.sample_direction(qrng.gen()).0[2].abs() < 0.1)
+///     .count();
+/// assert!(near_sun > 900);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvironmentDistribution {
+    distribution: Piecewise2D,
+}
+
+impl EnvironmentDistribution {
+    /// `luminance` holds `height` rows of `width` nonnegative values each.
+    pub fn new(width: usize, height: usize, luminance: &[f64]) -> Self {
+        assert_eq!(luminance.len(), width * height);
+        let weights: Vec<f64> = luminance
+            .iter()
+            .enumerate()
+            .map(|(i, l)| l * (PI * ((i / width) as f64 + 0.5) / height as f64).sin())
+            .collect();
+        Self {
+            distribution: Piecewise2D::new(width, height, &weights),
+        }
+    }
+
+    /// The distribution over the map, with densities per unit area of
+    /// `[0, 1)^2`
+    pub fn distribution(&self) -> &Piecewise2D {
+        &self.distribution
+    }
+
+    /// Maps a uniform point to a unit direction and its density per unit
+    /// solid angle.
+    pub fn sample_direction(&self, [u, v]: [f64; 2]) -> ([f64; 3], f64) {
+        let ([x, y], pdf) = self.distribution.sample_continuous(u, v);
+        let (theta, phi) = (PI * y, 2.0 * PI * x);
+        let direction = [theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()];
+        (direction, solid_angle_pdf(pdf, theta))
+    }
+
+    /// The density per unit solid angle of sampling the unit `direction`
+    pub fn pdf(&self, [x, y, z]: [f64; 3]) -> f64 {
+        let theta = z.clamp(-1.0, 1.0).acos();
+        let phi = y.atan2(x).rem_euclid(2.0 * PI);
+        let point = [(phi / (2.0 * PI)).min(MAX_UNIFORM), (theta / PI).min(MAX_UNIFORM)];
+        solid_angle_pdf(self.distribution.pdf(point), theta)
+    }
+}
+
+impl Warp for EnvironmentDistribution {
+    type Output = [f64; 3];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; 3], f64) {
+        self.sample_direction([u[0], u[1]])
+    }
+
+    fn pdf(&self, direction: &[f64; 3]) -> f64 {
+        if is_unit(direction) { EnvironmentDistribution::pdf(self, *direction) } else { 0.0 }
+    }
+}
+
+/// Converts a density over the equirectangular map to one per unit solid
+/// angle: `dA = dphi dtheta / (2 pi^2)` and `domega = sin(theta) dtheta dphi`
+fn solid_angle_pdf(pdf: f64, theta: f64) -> f64 {
+    let sin_theta = theta.sin();
+    if sin_theta == 0.0 {
+        0.0
+    } else {
+        pdf / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Qrng;
+
+    #[test]
+    fn piecewise_2d_matches_weights() {
+        let weights = [1.0, 0.0, 3.0, 2.0, 2.0, 0.0];
+        let distribution = Piecewise2D::new(3, 2, &weights);
+        let mut counts = [0usize; 6];
+        let mut qrng = Qrng::<(f64, f64)>::new(0.0);
+        let n = 80_000;
+        for _ in 0..n {
+            let (u, v) = qrng.gen();
+            let ([x, y], pdf) = distribution.sample_continuous(u, v);
+            assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+            assert_eq!(pdf, distribution.pdf([x, y]));
+            counts[(y * 2.0) as usize * 3 + (x * 3.0) as usize] += 1;
+        }
+        for (count, weight) in counts.iter().zip(&weights) {
+            assert!((*count as f64 / n as f64 - weight / 8.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn piecewise_3d_matches_weights() {
+        let weights: Vec<f64> = (0..24).map(|i| (i % 5) as f64).collect();
+        let total: f64 = weights.iter().sum();
+        let distribution = Piecewise3D::new(4, 3, 2, &weights);
+        assert!((distribution.integral() - total / 24.0).abs() < 1e-12);
+        let mut counts = [0usize; 24];
+        let mut qrng = Qrng::<[f64; 3]>::new(0.0);
+        let n = 100_000;
+        for _ in 0..n {
+            let ([x, y, z], pdf) = distribution.sample_continuous(qrng.gen());
+            assert_eq!(pdf, distribution.pdf([x, y, z]));
+            let voxel = ((z * 2.0) as usize * 3 + (y * 3.0) as usize) * 4 + (x * 4.0) as usize;
+            assert!((pdf - 24.0 * weights[voxel] / total).abs() < 1e-9);
+            counts[voxel] += 1;
+        }
+        for (count, weight) in counts.iter().zip(&weights) {
+            assert!((*count as f64 / n as f64 - weight / total).abs() < 2e-3);
+        }
+    }
+
+    #[test]
+    fn environment_densities_are_per_solid_angle() {
+        let (width, height) = (16, 8);
+        let luminance: Vec<f64> = (0..width * height).map(|i| 1.0 + (i % 7) as f64).collect();
+        let environment = EnvironmentDistribution::new(width, height, &luminance);
+        let mut qrng = Qrng::<[f64; 2]>::new(0.5);
+        // Integrating the density over the sphere by sampling it gives the
+        // number of samples that land anywhere, one each
+        let n = 20_000;
+        let mut area = 0.0;
+        for _ in 0..n {
+            let (direction, pdf) = environment.sample_direction(qrng.gen());
+            assert!((direction.iter().map(|x| x * x).sum::<f64>() - 1.0).abs() < 1e-12);
+            assert!((environment.pdf(direction) / pdf - 1.0).abs() < 1e-9);
+            area += 1.0 / pdf;
+        }
+        assert!((area / n as f64 - 4.0 * PI).abs() < 0.05, "{}", area / n as f64);
+
+        let distribution: &Distribution1D = &Piecewise1D::new(&[1.0, 3.0]);
+        assert_eq!(distribution.sample_discrete(0.625), (1, 0.75, 0.5));
+        assert_eq!(distribution.sample_discrete(0.125).0, 0);
+    }
+}
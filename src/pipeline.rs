@@ -0,0 +1,109 @@
+//! Composing warps while keeping track of the sample density.
+//!
+//! Each stage of a `Pipeline` maps a value to a new value together with the
+//! density the stage introduces: for a stage that takes uniformly distributed
+//! input to output with density `p`, the factor is `p`, or in general one over
+//! the absolute Jacobian determinant of the stage. By the change of variables
+//! formula, the density of the final value is the product of the factors, so
+//! the pipeline multiplies them as it goes.
+
+use std::marker::PhantomData;
+
+use crate::{Qrng, Quasirandom};
+
+/// A `Qrng` followed by a chain of stages. Created by `Qrng::map`.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use std::f64::consts::PI;
+///
+/// // Uniform on the unit disk, then lifted onto the hemisphere: together,
+/// // cosine-weighted directions
+/// let mut directions = Qrng::<[f64; 2]>::new(0.5)
+///     .map(|u| (quasirandom::warp::to_unit_disk(u), 1.0 / PI))
+///     .map(|[x, y]| {
+///         let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+///         ([x, y, z], z)
+///     });
+/// let ([_, _, z], pdf) = directions.gen();
+/// assert!((pdf - z / PI).abs() < 1e-12);
+/// ```
+pub struct Pipeline<T: Quasirandom, U, F> {
+    qrng: Qrng<T>,
+    stages: F,
+    output: PhantomData<fn() -> U>,
+}
+
+impl<T: Quasirandom> Qrng<T> {
+    /// Starts a pipeline whose first stage is `stage`, which returns each
+    /// mapped value along with the density it introduces (see the module
+    /// documentation).
+    pub fn map<U>(self, stage: impl FnMut(T) -> (U, f64)) -> Pipeline<T, U, impl FnMut(T) -> (U, f64)> {
+        Pipeline {
+            qrng: self,
+            stages: stage,
+            output: PhantomData,
+        }
+    }
+}
+
+impl<T: Quasirandom, U, F: FnMut(T) -> (U, f64)> Pipeline<T, U, F> {
+    /// Appends a stage, multiplying its density into the running density.
+    pub fn map<V>(self, mut stage: impl FnMut(U) -> (V, f64)) -> Pipeline<T, V, impl FnMut(T) -> (V, f64)> {
+        let mut stages = self.stages;
+        Pipeline {
+            qrng: self.qrng,
+            stages: move |x| {
+                let (y, density) = stages(x);
+                let (z, factor) = stage(y);
+                (z, density * factor)
+            },
+            output: PhantomData,
+        }
+    }
+
+    /// Generates the next value and its density.
+    pub fn gen(&mut self) -> (U, f64) {
+        (self.stages)(T::generate(&mut self.qrng.state))
+    }
+
+    pub fn into_qrng(self) -> Qrng<T> {
+        self.qrng
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::warp::{cosine_hemisphere, to_unit_disk};
+    use crate::Qrng;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn composed_densities() {
+        // Disk, then lift: matches the cosine-weighted hemisphere warp
+        let mut pipeline = Qrng::<[f64; 2]>::new(0.0)
+            .map(|u| ((u, to_unit_disk(u)), 1.0 / PI))
+            .map(|(u, [x, y])| {
+                let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+                ((u, [x, y, z]), z)
+            });
+        for _ in 0..100 {
+            let ((u, direction), pdf) = pipeline.gen();
+            let (expected, expected_pdf) = cosine_hemisphere(u);
+            assert_eq!(direction, expected);
+            assert!((pdf - expected_pdf).abs() < 1e-12);
+        }
+
+        // Scaling [0, 1) to [0, 4) and squaring: x = 16 u^2 on [0, 16) has
+        // density 1 / (8 sqrt(x)), and the mean of 1 / pdf is the length 16
+        let mut pipeline = Qrng::<f64>::new(0.0).map(|u| (4.0 * u, 0.25)).map(|x| (x * x, 1.0 / (2.0 * x)));
+        let n = 10_000;
+        let mut length = 0.0;
+        for _ in 0..n {
+            let (x, pdf) = pipeline.gen();
+            assert!((pdf - 1.0 / (8.0 * x.sqrt())).abs() < 1e-9 * pdf);
+            length += 1.0 / pdf;
+        }
+        assert!((length / n as f64 - 16.0).abs() < 1e-2);
+    }
+}
@@ -0,0 +1,275 @@
+//! Poisson-disk point sets.
+//!
+//! A Poisson-disk set is a set of points in the unit square (or cube) where no
+//! two points are closer than a given radius. Candidates are drawn from a
+//! `Qrng` and accepted whenever they keep their distance from every accepted
+//! point, so the result inherits the even coverage of the sequence while
+//! gaining a hard minimum distance.
+//!
+//! The same process can fill the gaps of an existing layout: existing points
+//! are kept as they are, and new points keep their distance from them too.
+
+use crate::Qrng;
+
+/// Generates Poisson-disk point sets in `[0, 1)^2` and `[0, 1)^3`.
+///
+/// ```
+/// use quasirandom::poisson::PoissonDisk;
+///
+/// let points = PoissonDisk::new(0.05, 0.5).points_2d();
+/// for (i, a) in points.iter().enumerate() {
+///     for b in &points[i + 1..] {
+///         assert!((a[0] - b[0]).hypot(a[1] - b[1]) >= 0.05);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoissonDisk {
+    radius: f64,
+    seed: f64,
+    max_rejections: Option<usize>,
+}
+
+impl PoissonDisk {
+    /// `radius` is the minimum distance between points and `seed` seeds the
+    /// `Qrng` that proposes candidates.
+    pub fn new(radius: f64, seed: f64) -> Self {
+        assert!(radius > 0.0);
+        assert!(seed >= 0.0);
+        assert!(seed < 1.0);
+        Self {
+            radius,
+            seed,
+            max_rejections: None,
+        }
+    }
+
+    /// Stop after this many candidates in a row have been rejected.
+    ///
+    /// Defaults to four times the number of cells in the acceleration grid,
+    /// which in practice leaves almost no room for further points.
+    pub fn max_rejections(self, max_rejections: usize) -> Self {
+        Self {
+            max_rejections: Some(max_rejections),
+            ..self
+        }
+    }
+
+    pub fn points_2d(&self) -> Vec<[f64; 2]> {
+        self.fill_2d(&[])
+    }
+
+    pub fn points_3d(&self) -> Vec<[f64; 3]> {
+        self.fill_3d(&[])
+    }
+
+    /// Adds points to the gaps of an existing layout, returning only the new
+    /// points. Each new point is at least the radius away from every other
+    /// point, new or existing. The existing points are not moved, and may be
+    /// closer together than the radius.
+    ///
+    /// ```
+    /// use quasirandom::poisson::PoissonDisk;
+    ///
+    /// // A hand-placed cluster in one corner
+    /// let existing = [[0.1, 0.1], [0.12, 0.1], [0.1, 0.13]];
+    /// let detail = PoissonDisk::new(0.05, 0.5).fill_2d(&existing);
+    /// for point in &detail {
+    ///     for other in &existing {
+    ///         assert!((point[0] - other[0]).hypot(point[1] - other[1]) >= 0.05);
+    ///     }
+    /// }
+    /// ```
+    pub fn fill_2d(&self, existing: &[[f64; 2]]) -> Vec<[f64; 2]> {
+        let mut qrng = Qrng::<(f64, f64)>::new(self.seed);
+        generate(self.radius, self.max_rejections, existing, || {
+            let (x, y) = qrng.gen();
+            [x, y]
+        })
+    }
+
+    /// Like `fill_2d`, in the unit cube.
+    pub fn fill_3d(&self, existing: &[[f64; 3]]) -> Vec<[f64; 3]> {
+        let mut qrng = Qrng::<(f64, f64, f64)>::new(self.seed);
+        generate(self.radius, self.max_rejections, existing, || {
+            let (x, y, z) = qrng.gen();
+            [x, y, z]
+        })
+    }
+}
+
+/// A background grid whose cells are small enough to hold at most one
+/// generated point. Existing points, which may be closer together, are kept
+/// separately, sorted by cell.
+struct Grid<const D: usize> {
+    cell_size: f64,
+    resolution: usize,
+    cells: Vec<Option<u32>>,
+    /// The existing points in cell `c` are `existing[starts[c]..starts[c + 1]]`
+    existing: Vec<u32>,
+    starts: Vec<u32>,
+}
+
+impl<const D: usize> Grid<D> {
+    fn new(radius: f64, existing: &[[f64; D]]) -> Self {
+        let cell_size = radius / (D as f64).sqrt();
+        let resolution = (1.0 / cell_size).ceil() as usize;
+        let cells = resolution.checked_pow(D as u32).expect("radius is too small");
+        let mut grid = Self {
+            cell_size,
+            resolution,
+            cells: vec![None; cells],
+            existing: vec![],
+            starts: vec![],
+        };
+        if !existing.is_empty() {
+            // Counting sort by cell
+            let indices: Vec<usize> = existing.iter().map(|point| grid.index(&grid.cell(point))).collect();
+            grid.starts = vec![0; cells + 1];
+            for &index in &indices {
+                grid.starts[index + 1] += 1;
+            }
+            for c in 0..cells {
+                grid.starts[c + 1] += grid.starts[c];
+            }
+            let mut next = grid.starts.clone();
+            grid.existing = vec![0; existing.len()];
+            for (i, &index) in indices.iter().enumerate() {
+                grid.existing[next[index] as usize] = i as u32;
+                next[index] += 1;
+            }
+        }
+        grid
+    }
+
+    /// Points outside the unit cube go to the nearest cell
+    fn cell(&self, point: &[f64; D]) -> [usize; D] {
+        point.map(|x| ((x.max(0.0) / self.cell_size) as usize).min(self.resolution - 1))
+    }
+
+    fn index(&self, cell: &[usize; D]) -> usize {
+        cell.iter().fold(0, |index, &c| index * self.resolution + c)
+    }
+
+    /// Calls `f` with every generated point whose cell is within `reach`
+    /// cells of `center` along every axis, stopping early if `f` returns
+    /// false. Likewise calls `existing` with every nearby existing point.
+    fn neighbors(
+        &self,
+        center: &[usize; D],
+        reach: usize,
+        mut f: impl FnMut(u32) -> bool,
+        mut existing: impl FnMut(u32) -> bool,
+    ) -> bool {
+        let lo = center.map(|c| c.saturating_sub(reach));
+        let hi = center.map(|c| (c + reach).min(self.resolution - 1));
+        let mut cell = lo;
+        loop {
+            let index = self.index(&cell);
+            if let Some(point) = self.cells[index] {
+                if !f(point) {
+                    return false;
+                }
+            }
+            if !self.starts.is_empty() {
+                let range = self.starts[index] as usize..self.starts[index + 1] as usize;
+                if !self.existing[range].iter().all(|&point| existing(point)) {
+                    return false;
+                }
+            }
+            let mut axis = 0;
+            loop {
+                if axis == D {
+                    return true;
+                }
+                if cell[axis] < hi[axis] {
+                    cell[axis] += 1;
+                    break;
+                }
+                cell[axis] = lo[axis];
+                axis += 1;
+            }
+        }
+    }
+}
+
+fn distance_squared<const D: usize>(a: &[f64; D], b: &[f64; D]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+fn generate<const D: usize>(
+    radius: f64,
+    max_rejections: Option<usize>,
+    existing: &[[f64; D]],
+    mut candidate: impl FnMut() -> [f64; D],
+) -> Vec<[f64; D]> {
+    let mut grid = Grid::<D>::new(radius, existing);
+    let max_rejections = max_rejections.unwrap_or(4 * grid.cells.len());
+    let reach = (D as f64).sqrt().ceil() as usize;
+    let radius_squared = radius * radius;
+
+    let mut points: Vec<[f64; D]> = vec![];
+    let mut rejections = 0;
+    while rejections < max_rejections {
+        let point = candidate();
+        let cell = grid.cell(&point);
+        let accepted = grid.cells[grid.index(&cell)].is_none()
+            && grid.neighbors(
+                &cell,
+                reach,
+                |other| distance_squared(&point, &points[other as usize]) >= radius_squared,
+                |other| distance_squared(&point, &existing[other as usize]) >= radius_squared,
+            );
+        if accepted {
+            let index = grid.index(&cell);
+            grid.cells[index] = Some(points.len() as u32);
+            points.push(point);
+            rejections = 0;
+        } else {
+            rejections += 1;
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn min_distance<const D: usize>(points: &[[f64; D]]) -> f64 {
+        let mut min = f64::INFINITY;
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                min = min.min(distance_squared(a, b).sqrt());
+            }
+        }
+        min
+    }
+
+    #[test]
+    fn minimum_distance() {
+        let points = PoissonDisk::new(0.03, 0.1).points_2d();
+        assert!(min_distance(&points) >= 0.03);
+        // Maximal disk packings of this radius hold roughly 0.7 / r^2 points
+        assert!(points.len() > 500, "{}", points.len());
+
+        let points = PoissonDisk::new(0.1, 0.1).points_3d();
+        assert!(min_distance(&points) >= 0.1);
+        assert!(points.len() > 300, "{}", points.len());
+    }
+
+    #[test]
+    fn filling_around_existing_points() {
+        // A dense clump, a line, and a point outside the square
+        let mut existing: Vec<[f64; 2]> = (0..50).map(|i| [0.3 + 0.001 * i as f64, 0.3]).collect();
+        existing.extend((0..20).map(|i| [0.05 * i as f64, 0.8]));
+        existing.push([1.02, 0.5]);
+        let filled = PoissonDisk::new(0.04, 0.2).fill_2d(&existing);
+        assert!(min_distance(&filled) >= 0.04);
+        for point in &filled {
+            assert!(existing.iter().all(|other| distance_squared(point, other) >= 0.04 * 0.04));
+        }
+        assert!(filled.len() > 300, "{}", filled.len());
+        assert_eq!(PoissonDisk::new(0.04, 0.2).fill_2d(&[]), PoissonDisk::new(0.04, 0.2).points_2d());
+    }
+}
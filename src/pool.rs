@@ -0,0 +1,158 @@
+//! Reusable sample buffers for code that must not allocate.
+//!
+//! Real-time threads (audio callbacks, frame loops) cannot collect samples
+//! into a fresh `Vec` for every batch. A `BufferPool` allocates all of its
+//! buffers up front; afterwards, acquiring a buffer, filling it, and
+//! returning it to the pool never touch the allocator. Buffers start on a
+//! 64-byte boundary, so they are cache-line aligned for SIMD consumers.
+
+use std::cell::{RefCell, RefMut};
+use std::ops::{Deref, DerefMut};
+
+use crate::Qrng;
+
+/// The alignment of every buffer, in bytes
+pub const BUFFER_ALIGNMENT: usize = 64;
+
+/// A fixed set of buffers, each holding `len` points of dimension `D`.
+///
+/// The pool lives on one thread at a time. A buffer returns to the pool when
+/// it is dropped.
+///
+/// ```
+/// use quasirandom::pool::BufferPool;
+/// use quasirandom::Qrng;
+///
+/// // Allocate before the real-time loop starts
+/// let pool = BufferPool::<2>::new(4, 512);
+/// let mut qrng = Qrng::<[f64; 2]>::new(0.5);
+///
+/// // Inside the loop: no allocation
+/// for _ in 0..100 {
+///     let mut buffer = pool.acquire().unwrap();
+///     buffer.fill(&mut qrng);
+///     assert_eq!(buffer.len(), 512);
+///     assert_eq!(buffer.as_ptr() as usize % 64, 0);
+/// }
+/// ```
+pub struct BufferPool<const D: usize> {
+    /// Each buffer's storage, with room to skip ahead to an aligned start
+    storage: Vec<RefCell<Vec<f64>>>,
+    /// The indices of the buffers not handed out, with capacity for all
+    free: RefCell<Vec<usize>>,
+    len: usize,
+}
+
+impl<const D: usize> BufferPool<D> {
+    /// Allocates `buffers` buffers of `len` points each.
+    pub fn new(buffers: usize, len: usize) -> Self {
+        assert!(D > 0);
+        let padding = BUFFER_ALIGNMENT / std::mem::size_of::<f64>();
+        let storage = (0..buffers).map(|_| RefCell::new(vec![0.0; len * D + padding])).collect();
+        Self {
+            storage,
+            free: RefCell::new((0..buffers).rev().collect()),
+            len,
+        }
+    }
+
+    /// The number of points in each buffer
+    pub fn buffer_len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of buffers currently in the pool
+    pub fn available(&self) -> usize {
+        self.free.borrow().len()
+    }
+
+    /// Takes a buffer from the pool, or returns `None` if all of them are in
+    /// use. The buffer holds whatever was last written to it.
+    pub fn acquire(&self) -> Option<PooledBuffer<'_, D>> {
+        let index = self.free.borrow_mut().pop()?;
+        let storage = self.storage[index].borrow_mut();
+        let offset = storage.as_ptr().align_offset(BUFFER_ALIGNMENT);
+        let len = self.len;
+        let points = RefMut::map(storage, |storage| {
+            storage[offset..offset + len * D].as_chunks_mut::<D>().0
+        });
+        Some(PooledBuffer {
+            free: &self.free,
+            index,
+            points,
+        })
+    }
+}
+
+/// A buffer on loan from a `BufferPool`. Dereferences to its points.
+pub struct PooledBuffer<'a, const D: usize> {
+    free: &'a RefCell<Vec<usize>>,
+    index: usize,
+    points: RefMut<'a, [[f64; D]]>,
+}
+
+impl<const D: usize> PooledBuffer<'_, D> {
+    /// Overwrites every point with the next values of `qrng`.
+    pub fn fill(&mut self, qrng: &mut Qrng<[f64; D]>) {
+        for point in self.points.iter_mut() {
+            *point = qrng.gen();
+        }
+    }
+
+    /// The points as one flat slice of coordinates
+    pub fn as_flattened(&self) -> &[f64] {
+        self.points.as_flattened()
+    }
+}
+
+impl<const D: usize> Deref for PooledBuffer<'_, D> {
+    type Target = [[f64; D]];
+
+    fn deref(&self) -> &[[f64; D]] {
+        &self.points
+    }
+}
+
+impl<const D: usize> DerefMut for PooledBuffer<'_, D> {
+    fn deref_mut(&mut self) -> &mut [[f64; D]] {
+        &mut self.points
+    }
+}
+
+impl<const D: usize> Drop for PooledBuffer<'_, D> {
+    fn drop(&mut self) {
+        // Never reallocates: the list has room for every buffer
+        self.free.borrow_mut().push(self.index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_are_recycled() {
+        let pool = BufferPool::<3>::new(2, 100);
+        let mut qrng = Qrng::<[f64; 3]>::new(0.25);
+        let mut expected = Qrng::<[f64; 3]>::new(0.25);
+        {
+            let mut a = pool.acquire().unwrap();
+            let mut b = pool.acquire().unwrap();
+            assert!(pool.acquire().is_none());
+            assert_eq!(pool.available(), 0);
+            a.fill(&mut qrng);
+            b.fill(&mut qrng);
+            for point in a.iter().chain(b.iter()) {
+                assert_eq!(*point, expected.gen());
+            }
+            assert_eq!(a.as_flattened().len(), 300);
+            assert_eq!(b.as_flattened().as_ptr() as usize % BUFFER_ALIGNMENT, 0);
+        }
+        assert_eq!(pool.available(), 2);
+
+        // The same storage comes back, without reallocating
+        let first = pool.acquire().unwrap().as_ptr();
+        assert_eq!(pool.acquire().unwrap().as_ptr(), first);
+        assert_eq!(pool.free.borrow().capacity(), 2);
+    }
+}
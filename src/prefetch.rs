@@ -0,0 +1,98 @@
+//! Generating ahead on a background thread.
+//!
+//! A single `gen` is cheap, but latency-sensitive loops that consume values
+//! in bursts can still see generation show up in their timings. A
+//! `Prefetcher` moves the work to a background thread with two buffers: the
+//! thread fills one batch while the caller consumes the other, and the two
+//! swap whenever the caller runs out.
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use crate::{Qrng, Quasirandom};
+
+/// A `Qrng` whose values are generated in batches on a background thread.
+/// Created by `Qrng::prefetch`.
+///
+/// Values come out in exactly the order the `Qrng` would produce them. The
+/// thread stops when the `Prefetcher` is dropped.
+///
+/// ```
+/// use quasirandom::Qrng;
+///
+/// let mut prefetcher = Qrng::<[f64; 2]>::new(0.5).prefetch(4096);
+/// let mut qrng = Qrng::<[f64; 2]>::new(0.5);
+/// for _ in 0..10_000 {
+///     assert_eq!(prefetcher.gen(), qrng.gen());
+/// }
+/// ```
+pub struct Prefetcher<T: Quasirandom> {
+    /// The batch being consumed, in reverse order
+    current: Vec<T>,
+    filled: Receiver<Vec<T>>,
+    empty: Sender<Vec<T>>,
+}
+
+impl<T> Qrng<T>
+where
+    T: Quasirandom + Send + 'static,
+    T::State: Send,
+{
+    /// Moves the generator to a background thread that generates
+    /// `batch_size` values at a time ahead of the caller.
+    pub fn prefetch(mut self, batch_size: usize) -> Prefetcher<T> {
+        assert!(batch_size > 0);
+        let (filled_sender, filled) = channel();
+        let (empty, empty_receiver) = channel::<Vec<T>>();
+        thread::spawn(move || {
+            // Ends once the Prefetcher, and with it the other end of either
+            // channel, is dropped
+            while let Ok(mut batch) = empty_receiver.recv() {
+                batch.extend((0..batch_size).map(|_| T::generate(&mut self.state)));
+                batch.reverse();
+                if filled_sender.send(batch).is_err() {
+                    break;
+                }
+            }
+        });
+        for _ in 0..2 {
+            empty.send(Vec::with_capacity(batch_size)).unwrap();
+        }
+        Prefetcher {
+            current: vec![],
+            filled,
+            empty,
+        }
+    }
+}
+
+impl<T: Quasirandom> Prefetcher<T> {
+    /// Generates the next value, waiting for the background thread only if
+    /// it has fallen a whole batch behind.
+    pub fn gen(&mut self) -> T {
+        if let Some(value) = self.current.pop() {
+            return value;
+        }
+        let next = self.filled.recv().expect("the prefetch thread panicked");
+        let used = std::mem::replace(&mut self.current, next);
+        // The thread only stops after the Prefetcher is gone
+        let _ = self.empty.send(used);
+        self.current.pop().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Qrng;
+
+    #[test]
+    fn prefetched_values_match() {
+        for batch_size in [1, 3, 1000] {
+            let mut prefetcher = Qrng::<(f64, bool, u8)>::new(0.25).prefetch(batch_size);
+            let mut qrng = Qrng::<(f64, bool, u8)>::new(0.25);
+            for _ in 0..5000 {
+                assert_eq!(prefetcher.gen(), qrng.gen());
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Reordering point sets for progressive sampling.
+//!
+//! A low-discrepancy sequence is only evenly spread asymptotically; short
+//! prefixes can still have close pairs. Progressive renderers look at every
+//! prefix, so this module reorders a finite point set such that each prefix is
+//! as spread out as possible (blue noise), while the full set is unchanged.
+//!
+//! `Densifier` covers the opposite case, where a point cloud grows over time
+//! and the points already emitted must never move.
+
+use crate::Qrng;
+
+/// Returns the order in which to visit `points` so that every prefix is well
+/// spread.
+///
+/// The order is built greedily by farthest-point insertion: each step picks
+/// the remaining point whose distance to the nearest already chosen point is
+/// largest. Distances are measured on the unit torus, matching the
+/// wrap-around structure of the sequences in this crate. Ties go to the
+/// lowest index and the first point is always kept first, so the result is
+/// deterministic.
+///
+/// Takes `O(n^2)` time and `O(n)` memory.
+pub fn blue_noise_order<const D: usize>(points: &[[f64; D]]) -> Vec<usize> {
+    let n = points.len();
+    let mut order = Vec::with_capacity(n);
+    if n == 0 {
+        return order;
+    }
+
+    let mut chosen = vec![false; n];
+    let mut nearest = vec![f64::INFINITY; n];
+    let mut next = 0;
+    for _ in 0..n {
+        chosen[next] = true;
+        order.push(next);
+
+        let mut farthest = None;
+        let mut farthest_distance = -1.0;
+        for i in 0..n {
+            if chosen[i] {
+                continue;
+            }
+            nearest[i] = nearest[i].min(toroidal_distance_squared(&points[i], &points[next]));
+            if nearest[i] > farthest_distance {
+                farthest_distance = nearest[i];
+                farthest = Some(i);
+            }
+        }
+        match farthest {
+            Some(i) => next = i,
+            None => break,
+        }
+    }
+    order
+}
+
+/// Reorders `points` in place according to `blue_noise_order`.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::progressive::reorder;
+///
+/// let mut qrng = Qrng::<(f64, f64)>::new(0.0);
+/// let mut points: Vec<[f64; 2]> = (0..256).map(|_| {
+///     let (x, y) = qrng.gen();
+///     [x, y]
+/// }).collect();
+/// reorder(&mut points);
+/// ```
+pub fn reorder<const D: usize>(points: &mut [[f64; D]]) {
+    let order = blue_noise_order(points);
+    let reordered: Vec<[f64; D]> = order.iter().map(|&i| points[i]).collect();
+    points.copy_from_slice(&reordered);
+}
+
+/// Progressively densifies a point cloud in `[0, 1)^D`.
+///
+/// Every batch continues the same sequence, so the union of all points
+/// emitted so far is always a prefix of a low-discrepancy sequence: adding
+/// detail never disturbs or duplicates existing points, and the cloud stays
+/// evenly spread at every size. A densifier can be resumed from just the seed
+/// and the number of points already emitted.
+///
+/// ```
+/// use quasirandom::progressive::Densifier;
+///
+/// let mut densifier = Densifier::<3>::new(0.5);
+/// let mut cloud = densifier.densify(1000);
+///
+/// // Later, possibly in another process: add the next level of detail
+/// let mut resumed = Densifier::<3>::resume(0.5, cloud.len());
+/// cloud.extend(resumed.densify(3000));
+/// assert_eq!(cloud, Densifier::<3>::new(0.5).densify(4000));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Densifier<const D: usize> {
+    qrng: Qrng<[f64; D]>,
+    seed: f64,
+    emitted: usize,
+}
+
+impl<const D: usize> Densifier<D> {
+    pub fn new(seed: f64) -> Self {
+        Self {
+            qrng: Qrng::<[f64; D]>::new(seed),
+            seed,
+            emitted: 0,
+        }
+    }
+
+    /// The densifier that has already emitted `emitted` points from `seed`.
+    /// Takes `O(emitted)` time, and the points it goes on to emit are
+    /// bit-for-bit the ones the original would have.
+    pub fn resume(seed: f64, emitted: usize) -> Self {
+        let mut densifier = Self::new(seed);
+        for _ in 0..emitted {
+            densifier.qrng.gen();
+        }
+        densifier.emitted = emitted;
+        densifier
+    }
+
+    pub fn seed(&self) -> f64 {
+        self.seed
+    }
+
+    /// The number of points emitted so far
+    pub fn emitted(&self) -> usize {
+        self.emitted
+    }
+
+    /// Returns the next `count` points, which together with all the points
+    /// emitted before stay evenly spread.
+    pub fn densify(&mut self, count: usize) -> Vec<[f64; D]> {
+        self.emitted += count;
+        (0..count).map(|_| self.qrng.gen()).collect()
+    }
+}
+
+fn toroidal_distance_squared<const D: usize>(a: &[f64; D], b: &[f64; D]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| {
+            let d = (x - y).abs();
+            let d = d.min(1.0 - d);
+            d * d
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn min_distance(points: &[[f64; 2]]) -> f64 {
+        let mut min = f64::INFINITY;
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                min = min.min(toroidal_distance_squared(a, b).sqrt());
+            }
+        }
+        min
+    }
+
+    #[test]
+    fn prefixes_are_better_spread() {
+        let mut qrng = Qrng::<(f64, f64)>::new(0.3);
+        let points: Vec<[f64; 2]> = (0..1024)
+            .map(|_| {
+                let (x, y) = qrng.gen();
+                [x, y]
+            })
+            .collect();
+        let mut reordered = points.clone();
+        reorder(&mut reordered);
+
+        let mut sorted = reordered.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut original = points.clone();
+        original.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(sorted, original);
+
+        for &k in &[8, 32, 100, 300] {
+            assert!(min_distance(&reordered[..k]) > min_distance(&points[..k]), "prefix {}", k);
+        }
+    }
+
+    #[test]
+    fn densified_clouds_stay_even() {
+        let mut densifier = Densifier::<2>::new(0.1);
+        let mut cloud = vec![];
+        for level in 0..4 {
+            cloud.extend(densifier.densify(1000 * 4usize.pow(level)));
+            // Each cell of an 8x8 grid holds its share of the points, off by
+            // far less than the square root of the share typical of random
+            // points
+            let mut cells = [0usize; 64];
+            for [x, y] in &cloud {
+                cells[(y * 8.0) as usize * 8 + (x * 8.0) as usize] += 1;
+            }
+            let share = cloud.len() as f64 / 64.0;
+            assert!(cells.iter().all(|&count| (count as f64 - share).abs() < 1.0 + share.sqrt() / 2.0), "{:?}", cells);
+        }
+        assert_eq!(densifier.emitted(), cloud.len());
+
+        let mut resumed = Densifier::<2>::resume(0.1, 1000);
+        assert_eq!(resumed.densify(10), cloud[1000..1010]);
+    }
+}
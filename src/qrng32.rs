@@ -0,0 +1,135 @@
+//! A 32-bit generator for matching GPU implementations bit for bit.
+//!
+//! `Qrng` accumulates in `f64`, which shaders usually lack, and an `f32`
+//! accumulator would round differently from one implementation to the next
+//! as soon as any operation is fused or reordered. `Qrng32` keeps its state
+//! in 32-bit fixed point instead: each step is one wrapping integer addition
+//! per dimension, and each output converts the top 24 bits to an `f32`
+//! exactly. Any shader doing the same integer operations produces the same
+//! bits, e.g. in WGSL:
+//!
+//! ```text
+//! // alpha from Qrng32::<N>::constants(), start from Qrng32::<N>::new(seed).start()
+//! fn sample(start: u32, alpha: u32, index: u32) -> f32 {
+//!     let bits = start + (index + 1u) * alpha;
+//!     return f32(bits >> 8u) * (1.0 / 16777216.0);
+//! }
+//! ```
+
+use crate::{CONSTANTS, MAX_DIMENSION};
+
+/// The `N`-dimensional sequence of `Qrng`, in 32-bit fixed point.
+///
+/// The values follow those of `Qrng::<[f32; N]>` with the seed
+/// `seed / 2^32` closely, but not exactly, since the generating constants
+/// are rounded to 32 bits.
+///
+/// ```
+/// use quasirandom::qrng32::Qrng32;
+///
+/// let mut qrng = Qrng32::<2>::new(0);
+/// let [x, y] = qrng.gen();
+/// assert_eq!(qrng.index(), 1);
+///
+/// // Point i is start + (i + 1) * alpha, as in a shader
+/// let mut jumped = Qrng32::<2>::new(0);
+/// jumped.set_index(1001);
+/// for _ in 0..1000 {
+///     qrng.gen();
+/// }
+/// assert_eq!(qrng.gen(), jumped.gen());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Qrng32<const N: usize> {
+    start: [u32; N],
+    state: [u32; N],
+    index: u32,
+}
+
+impl<const N: usize> Qrng32<N> {
+    /// Starts every dimension `d` at `d * seed` (as a fraction of 2^32), like
+    /// `Qrng::new`.
+    pub fn new(seed: u32) -> Self {
+        assert!(N >= 1 && N <= MAX_DIMENSION);
+        let start = std::array::from_fn(|d| seed.wrapping_mul(d as u32));
+        Self {
+            start,
+            state: start,
+            index: 0,
+        }
+    }
+
+    /// The generating constants, as fractions of 2^32 rounded to nearest
+    pub fn constants() -> [u32; N] {
+        assert!(N >= 1 && N <= MAX_DIMENSION);
+        std::array::from_fn(|d| (CONSTANTS[N - 1][d] * 2f64.powi(32)).round() as u32)
+    }
+
+    /// The starting state, before the first point
+    pub fn start(&self) -> [u32; N] {
+        self.start
+    }
+
+    /// The current state, as fractions of 2^32: the last point generated
+    pub fn state(&self) -> [u32; N] {
+        self.state
+    }
+
+    /// The number of points generated so far, modulo 2^32
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Moves to just after point `index - 1`, so the next point is number
+    /// `index`. Wraps around after 2^32 points, like the state.
+    pub fn set_index(&mut self, index: u32) {
+        for ((x, start), alpha) in self.state.iter_mut().zip(self.start).zip(Self::constants()) {
+            *x = start.wrapping_add(index.wrapping_mul(alpha));
+        }
+        self.index = index;
+    }
+
+    /// Generates the next point as 32-bit fractions.
+    pub fn gen_bits(&mut self) -> [u32; N] {
+        for (x, alpha) in self.state.iter_mut().zip(Self::constants()) {
+            *x = x.wrapping_add(alpha);
+        }
+        self.index = self.index.wrapping_add(1);
+        self.state
+    }
+
+    /// Generates the next point. Each coordinate is a multiple of `2^-24`
+    /// below one, so it is exact in `f32`.
+    pub fn gen(&mut self) -> [f32; N] {
+        self.gen_bits().map(|bits| (bits >> 8) as f32 * 2f32.powi(-24))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Qrng;
+
+    #[test]
+    fn follows_the_f64_sequence() {
+        let mut qrng32 = Qrng32::<3>::new(1 << 31);
+        let mut qrng = Qrng::<[f64; 3]>::new(0.5);
+        assert_eq!(qrng32.start(), [0, 1 << 31, 0]);
+        for _ in 0..10_000 {
+            let fixed = qrng32.gen();
+            let wide = qrng.gen();
+            for d in 0..3 {
+                assert!(fixed[d] < 1.0);
+                assert_eq!(fixed[d] * 2f32.powi(24), (fixed[d] * 2f32.powi(24)).floor());
+                let difference = (fixed[d] as f64 - wide[d]).abs();
+                assert!(difference.min(1.0 - difference) < 1e-5);
+            }
+        }
+
+        // Wrapping around the full period returns to the start
+        let mut wrapped = Qrng32::<1>::new(7);
+        wrapped.set_index(u32::MAX);
+        wrapped.gen();
+        assert_eq!((wrapped.index(), wrapped.state()), (0, wrapped.start()));
+    }
+}
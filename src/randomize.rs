@@ -0,0 +1,317 @@
+//! Randomized copies of the sequence, for randomized quasi-Monte Carlo.
+//!
+//! Shifting every point of the sequence by the same vector, modulo one,
+//! keeps its low discrepancy, and a uniformly random shift makes every point
+//! uniformly distributed. Estimates from several independent shifts are then
+//! independent and unbiased, so their spread gives an error estimate.
+//!
+//! `Shifts` evaluates one index of the sequence under many shifts at once.
+//! The arithmetic is done in 64-bit fixed point, with no branches, so the
+//! loop over shifts vectorizes.
+//!
+//! `Randomized` stacks several randomizations (scrambling, antithetic
+//! reflection, and shifting) in a chosen order, and keeps track of what each
+//! one does to the point indices and which random values it draws.
+
+use crate::hash::{combine, mix};
+use crate::{from_fixed, to_fixed, CONSTANTS};
+
+/// A set of shifts of the `N`-dimensional sequence.
+///
+/// Point `index` under shift `s` is `frac(index * alpha + s)`, with `alpha`
+/// the generating constants of `Qrng`, computed with 64 bits of fraction.
+///
+/// ```
+/// use quasirandom::randomize::Shifts;
+///
+/// // Estimate the integral of x * y with 8 independent replicates
+/// let shifts = Shifts::<2>::random(8, 42);
+/// let mut sums = [0.0; 8];
+/// let mut points = [[0.0; 2]; 8];
+/// for index in 0..4096 {
+///     shifts.points_at(index, &mut points);
+///     for (sum, [x, y]) in sums.iter_mut().zip(points) {
+///         *sum += x * y / 4096.0;
+///     }
+/// }
+/// let mean = sums.iter().sum::<f64>() / 8.0;
+/// assert!((mean - 0.25).abs() < 1e-3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shifts<const N: usize> {
+    /// Each shift in fixed point, as a fraction of 2^64
+    shifts: Vec<[u64; N]>,
+    alphas: [u64; N],
+}
+
+impl<const N: usize> Shifts<N> {
+    /// The given shifts, each coordinate in `[0, 1)`
+    pub fn new(shifts: &[[f64; N]]) -> Self {
+        assert!(shifts.iter().flatten().all(|x| (0.0..1.0).contains(x)));
+        Self::from_fixed(shifts.iter().map(|shift| shift.map(to_fixed)).collect())
+    }
+
+    /// `k` pseudorandom shifts derived from `seed`
+    pub fn random(k: usize, seed: u64) -> Self {
+        let shifts = (0..k)
+            .map(|i| {
+                let hash = combine(seed, i as u64);
+                std::array::from_fn(|d| mix(hash ^ mix(d as u64 + 1)))
+            })
+            .collect();
+        Self::from_fixed(shifts)
+    }
+
+    fn from_fixed(shifts: Vec<[u64; N]>) -> Self {
+        assert!(N >= 1 && N <= crate::MAX_DIMENSION);
+        Self {
+            shifts,
+            alphas: std::array::from_fn(|d| to_fixed(CONSTANTS[N - 1][d])),
+        }
+    }
+
+    /// The number of shifts
+    pub fn len(&self) -> usize {
+        self.shifts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shifts.is_empty()
+    }
+
+    /// Writes point `index` under every shift into `out`, which must have
+    /// one entry per shift.
+    pub fn points_at(&self, index: u64, out: &mut [[f64; N]]) {
+        assert_eq!(out.len(), self.shifts.len());
+        let base: [u64; N] = std::array::from_fn(|d| index.wrapping_mul(self.alphas[d]));
+        for (out, shift) in out.iter_mut().zip(&self.shifts) {
+            for d in 0..N {
+                out[d] = from_fixed(base[d].wrapping_add(shift[d]));
+            }
+        }
+    }
+
+    /// Point `index` under every shift, as a new vector
+    pub fn points(&self, index: u64) -> Vec<[f64; N]> {
+        let mut out = vec![[0.0; N]; self.shifts.len()];
+        self.points_at(index, &mut out);
+        out
+    }
+}
+
+/// One randomization in a `Randomized` stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    /// Nested uniform (Owen) scrambling of the bits of each coordinate, with
+    /// the hash of Laine and Karras widened to 64 bits
+    Scramble,
+    /// Reflection of every point through the center, `x -> 1 - x`. Each
+    /// antithetic layer doubles the number of points: the lowest index bit
+    /// not used by an inner layer selects the reflected copy.
+    Antithetic,
+    /// A uniformly random shift modulo one
+    Shift,
+}
+
+/// A stack of randomizations of the `N`-dimensional sequence, applied in the
+/// order they were added, so that `.scramble().antithetic().shift()` is
+/// shift ∘ antithetic ∘ scramble.
+///
+/// Every layer of every replicate draws its own random value for each
+/// dimension, so the replicates are independent and stacking a layer twice
+/// is not the same as applying it once.
+///
+/// ```
+/// use quasirandom::randomize::Randomized;
+///
+/// let stack = Randomized::<2>::new(7).scramble().antithetic().shift();
+/// assert_eq!(stack.points_per_index(), 2);
+///
+/// // Estimate the integral of x * y with 8 independent replicates of 4096
+/// // points, 2048 antithetic pairs each
+/// let estimates: Vec<f64> = (0..8)
+///     .map(|r| {
+///         let replicate = stack.replicate(r);
+///         (0..4096).map(|i| replicate.point(i)).map(|[x, y]| x * y).sum::<f64>() / 4096.0
+///     })
+///     .collect();
+/// let mean = estimates.iter().sum::<f64>() / 8.0;
+/// assert!((mean - 0.25).abs() < 1e-3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Randomized<const N: usize> {
+    layers: Vec<Layer>,
+    seed: u64,
+}
+
+impl<const N: usize> Randomized<N> {
+    /// The plain sequence, with `seed` selecting the random values of the
+    /// layers added later
+    pub fn new(seed: u64) -> Self {
+        assert!(N >= 1 && N <= crate::MAX_DIMENSION);
+        Self { layers: Vec::new(), seed }
+    }
+
+    /// Adds a layer on top of the stack.
+    pub fn then(mut self, layer: Layer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    pub fn scramble(self) -> Self {
+        self.then(Layer::Scramble)
+    }
+
+    pub fn antithetic(self) -> Self {
+        self.then(Layer::Antithetic)
+    }
+
+    pub fn shift(self) -> Self {
+        self.then(Layer::Shift)
+    }
+
+    /// The layers, innermost first
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// The number of points generated from each point of the sequence
+    pub fn points_per_index(&self) -> u64 {
+        1 << self.layers.iter().filter(|&&layer| layer == Layer::Antithetic).count()
+    }
+
+    /// Replicate `replicate` of the stack, with its own random values
+    pub fn replicate(&self, replicate: u64) -> Replicate<N> {
+        let hash = combine(self.seed, replicate);
+        let keys = (0..self.layers.len())
+            .map(|l| {
+                let hash = combine(hash, l as u64);
+                std::array::from_fn(|d| mix(hash ^ mix(d as u64 + 1)))
+            })
+            .collect();
+        Replicate {
+            layers: self.layers.clone(),
+            keys,
+            alphas: std::array::from_fn(|d| to_fixed(CONSTANTS[N - 1][d])),
+        }
+    }
+}
+
+/// One replicate of a `Randomized` stack, with its random values drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replicate<const N: usize> {
+    layers: Vec<Layer>,
+    /// The random values of each layer, per dimension
+    keys: Vec<[u64; N]>,
+    alphas: [u64; N],
+}
+
+impl<const N: usize> Replicate<N> {
+    /// Point `index` of the randomized sequence. Points `k * m` to
+    /// `(k + 1) * m - 1`, with `m` the points per index, all come from point
+    /// `k` of the sequence.
+    pub fn point(&self, index: u64) -> [f64; N] {
+        let antithetic = self.layers.iter().filter(|&&layer| layer == Layer::Antithetic).count();
+        let base = if antithetic < 64 { index >> antithetic } else { 0 };
+        let mut point: [u64; N] = std::array::from_fn(|d| base.wrapping_mul(self.alphas[d]));
+        let mut reflections = index;
+        for (layer, keys) in self.layers.iter().zip(&self.keys) {
+            match layer {
+                Layer::Scramble => {
+                    for d in 0..N {
+                        point[d] = owen_scramble(point[d], keys[d]);
+                    }
+                }
+                Layer::Antithetic => {
+                    if reflections & 1 == 1 {
+                        point = point.map(u64::wrapping_neg);
+                    }
+                    reflections >>= 1;
+                }
+                Layer::Shift => {
+                    for d in 0..N {
+                        point[d] = point[d].wrapping_add(keys[d]);
+                    }
+                }
+            }
+        }
+        point.map(from_fixed)
+    }
+}
+
+/// Nested uniform scrambling of a fraction of 2^64. Each step changes a bit
+/// only according to the bits above it, so points sharing their first `k`
+/// bits still share them afterwards.
+fn owen_scramble(bits: u64, seed: u64) -> u64 {
+    let mut v = bits.reverse_bits();
+    v ^= v.wrapping_mul(0x6a6f_3b2e_3d20_adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 32) | 1);
+    v ^= v.wrapping_mul(0x1b87_3593_0552_6c56);
+    v ^= v.wrapping_mul(0xcc9e_2d51_53a2_2864);
+    v.reverse_bits()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Qrng;
+
+    #[test]
+    fn shifted_points_follow_the_recurrence() {
+        let shifts = Shifts::<3>::new(&[[0.0; 3], [0.5, 0.25, 0.75]]);
+        let mut qrng = Qrng::<[f64; 3]>::new(0.0);
+        for index in 1..1000 {
+            let [plain, shifted] = shifts.points(index)[..] else { unreachable!() };
+            let expected = qrng.gen();
+            for d in 0..3 {
+                assert!((plain[d] - expected[d]).abs() < 1e-10);
+                let difference = (shifted[d] - plain[d]).rem_euclid(1.0);
+                assert!((difference - [0.5, 0.25, 0.75][d]).abs() < 1e-12);
+            }
+        }
+
+        let random = Shifts::<2>::random(16, 1);
+        assert_eq!(random.len(), 16);
+        let points = random.points(0);
+        assert!(points.iter().flatten().all(|x| (0.0..1.0).contains(x)));
+        assert_ne!(points[0], points[1]);
+        assert_eq!(Shifts::<2>::random(16, 1), random);
+    }
+
+    #[test]
+    fn layers_compose_in_order() {
+        // Antithetic pairs, then a shared shift: each pair sums to twice the
+        // shift, modulo one
+        let stack = Randomized::<2>::new(3).antithetic().shift();
+        assert_eq!(stack.points_per_index(), 2);
+        let replicate = stack.replicate(0);
+        let shift = replicate.keys[1];
+        for k in 0..100 {
+            let [a, b] = [replicate.point(2 * k), replicate.point(2 * k + 1)];
+            for d in 0..2 {
+                let sum = to_fixed(a[d]).wrapping_add(to_fixed(b[d])) >> 20;
+                assert_eq!(sum, shift[d].wrapping_mul(2) >> 20);
+            }
+        }
+
+        // Two antithetic layers: the four points of each index are the
+        // point, its reflection, and the same two after the inner shift
+        let stack = Randomized::<1>::new(3).antithetic().shift().antithetic();
+        assert_eq!(stack.points_per_index(), 4);
+        let replicate = stack.replicate(5);
+        let [a, b, c, e] = [0, 1, 2, 3].map(|i| replicate.point(40 + i)[0]);
+        assert!(((a + c) - 1.0).abs() < 1e-12 && ((b + e) - 1.0).abs() < 1e-12);
+        assert_ne!(stack.replicate(5), stack.replicate(6));
+
+        // Scrambling keeps points that share leading bits together
+        for seed in 0..100 {
+            let x = mix(seed);
+            for bits in [1, 8, 30, 63] {
+                let y = x ^ (mix(seed + 1000) >> bits);
+                let key = mix(seed + 2000);
+                assert_eq!(owen_scramble(x, key) >> (64 - bits), owen_scramble(y, key) >> (64 - bits));
+            }
+        }
+    }
+}
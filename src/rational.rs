@@ -0,0 +1,152 @@
+//! Exact rational output for exact-arithmetic code.
+//!
+//! Every `f64` in `[0, 1)` is a dyadic rational `m / 2^k`, so generated values
+//! can be handed to exact geometric predicates without any binary-float
+//! noise: a `Rational` generated by a `Qrng` is the generated value itself,
+//! written as a fraction in lowest terms.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::FromUniform;
+
+/// A nonnegative fraction `numerator / denominator` in lowest terms.
+///
+/// As a `FromUniform` type it can be generated like any other value, alone or
+/// in tuples and arrays. Values of at least `2^-11`, which is all but a tiny
+/// fraction of them, are converted exactly; the denominator is then a power
+/// of two no larger than `2^63`. Smaller values are rounded to the nearest
+/// multiple of `2^-63`.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::rational::Rational;
+///
+/// let mut qrng = Qrng::<[Rational; 2]>::new(0.5);
+/// let [x, y] = qrng.gen();
+/// assert!(x.denominator().is_power_of_two() && x.numerator() < x.denominator());
+///
+/// // The same values as the floating-point generator
+/// let [u, v] = Qrng::<[f64; 2]>::new(0.5).gen();
+/// assert_eq!((f64::from(x), f64::from(y)), (u, v));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: u64,
+    denominator: u64,
+}
+
+impl Rational {
+    /// The denominator must be positive. The fraction is reduced to lowest
+    /// terms.
+    pub fn new(numerator: u64, denominator: u64) -> Self {
+        assert!(denominator > 0);
+        let divisor = gcd(numerator, denominator);
+        Self {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        }
+    }
+
+    pub fn numerator(&self) -> u64 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> u64 {
+        self.denominator
+    }
+}
+
+/// Converts the value exactly, or rounds it to a multiple of `2^-63` if it is
+/// below `2^-11`
+impl FromUniform for Rational {
+    fn from_uniform(uniform_value: f64) -> Self {
+        assert!((0.0..1.0).contains(&uniform_value));
+        if uniform_value == 0.0 {
+            return Self::new(0, 1);
+        }
+        // uniform_value = mantissa * 2^exponent, with exponent negative
+        let bits = uniform_value.to_bits();
+        let biased = (bits >> 52) as i32;
+        let fraction = bits & ((1 << 52) - 1);
+        let (mantissa, exponent) = if biased == 0 {
+            (fraction, -1074)
+        } else {
+            (fraction | 1 << 52, biased - 1075)
+        };
+        let shift = -exponent - 63;
+        if shift <= 0 {
+            Self::new(mantissa, 1 << -exponent)
+        } else if shift < 64 {
+            // Round to nearest
+            let numerator = (mantissa >> shift) + (mantissa >> (shift - 1) & 1);
+            Self::new(numerator, 1 << 63)
+        } else {
+            Self::new(0, 1)
+        }
+    }
+}
+
+impl From<Rational> for f64 {
+    fn from(rational: Rational) -> f64 {
+        rational.numerator as f64 / rational.denominator as f64
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let left = self.numerator as u128 * other.denominator as u128;
+        let right = other.numerator as u128 * self.denominator as u128;
+        left.cmp(&right)
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Qrng;
+
+    #[test]
+    fn conversion_is_exact() {
+        let mut rationals = Qrng::<(Rational, Rational, Rational)>::new(0.25);
+        let mut floats = Qrng::<(f64, f64, f64)>::new(0.25);
+        for _ in 0..10_000 {
+            let (a, b, c) = rationals.gen();
+            let (x, y, z) = floats.gen();
+            for (rational, float) in [(a, x), (b, y), (c, z)] {
+                assert!(rational.denominator().is_power_of_two());
+                assert!(float < 2f64.powi(-11) || f64::from(rational) == float);
+                // Exact up to 2^-64 even when rounded
+                assert!((f64::from(rational) - float).abs() <= 2f64.powi(-64));
+            }
+        }
+
+        assert_eq!(Rational::from_uniform(0.75), Rational::new(3, 4));
+        assert_eq!(Rational::from_uniform(0.0), Rational::new(0, 1));
+        assert_eq!(Rational::from_uniform(2f64.powi(-63)), Rational::new(1, 1 << 63));
+        assert_eq!(Rational::from_uniform(2f64.powi(-70)), Rational::new(0, 1));
+        assert_eq!(Rational::from_uniform(1.0 - f64::EPSILON / 2.0).to_string(), "9007199254740991/9007199254740992");
+        assert!(Rational::new(1, 3) < Rational::new(2, 5));
+        assert_eq!(Rational::new(6, 8), Rational::new(3, 4));
+    }
+}
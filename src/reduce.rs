@@ -0,0 +1,344 @@
+//! Summing many terms on several threads, reproducibly.
+//!
+//! Floating-point addition is not associative, so a sum split across threads
+//! usually depends on how the work was split and in which order the partial
+//! sums arrived. `tree_sum` fixes the order instead: the terms are summed in
+//! blocks of `BLOCK_LEN`, left to right, and the block sums are combined by a
+//! pairwise tree whose shape depends only on the number of terms. The threads
+//! only decide who computes which block, so the result is bit-identical for
+//! any number of threads, and the pairwise combination also keeps the
+//! rounding error growing only logarithmically with the number of blocks.
+//!
+//! `Partition` splits the same blocks across the ranks of a cluster, such as
+//! MPI processes. Each rank positions its generator at its first index in
+//! constant time and sums its own blocks into a `PartialSum`, and merging the
+//! partial sums of all ranks gives exactly the `tree_sum` result, whatever the
+//! number of ranks.
+//!
+//! `CompensatedSum` accumulates terms one at a time with Neumaier's variant
+//! of Kahan summation, which carries the rounding error of the running sum
+//! in a second `f64`. The estimators in this crate sum with it, so their
+//! error does not grow with the number of samples.
+
+use std::iter::Sum;
+use std::ops::{AddAssign, Range};
+use std::thread;
+
+use crate::{Qrng, Quasirandom, State};
+
+/// The number of terms summed sequentially at the leaves of the tree
+pub const BLOCK_LEN: u64 = 1024;
+
+/// The sum of `term(i)` for `i` in `0..count`, computed on `threads` threads.
+///
+/// The result depends only on `count` and the terms, never on `threads`.
+///
+/// ```
+/// use quasirandom::reduce::tree_sum;
+/// use quasirandom::sequences::Halton;
+///
+/// // Integrate x * y * z over the unit cube with 100,000 Halton points
+/// let halton = Halton::new(3);
+/// let term = |i| {
+///     let mut point = [0.0; 3];
+///     halton.point(i, &mut point);
+///     point.iter().product::<f64>()
+/// };
+/// let sum = tree_sum(100_000, 4, term);
+/// assert!((sum / 100_000.0 - 0.125).abs() < 1e-4);
+/// assert_eq!(sum.to_bits(), tree_sum(100_000, 3, term).to_bits());
+/// ```
+pub fn tree_sum(count: u64, threads: usize, term: impl Fn(u64) -> f64 + Sync) -> f64 {
+    assert!(threads > 0);
+    let blocks = count.div_ceil(BLOCK_LEN) as usize;
+    let mut block_sums = vec![0.0; blocks];
+    if blocks > 0 {
+        let per_thread = blocks.div_ceil(threads);
+        let term = &term;
+        thread::scope(|scope| {
+            for (chunk, sums) in block_sums.chunks_mut(per_thread).enumerate() {
+                scope.spawn(move || {
+                    for (k, sum) in sums.iter_mut().enumerate() {
+                        let start = (chunk * per_thread + k) as u64 * BLOCK_LEN;
+                        let end = (start + BLOCK_LEN).min(count);
+                        *sum = (start..end).map(term).sum::<CompensatedSum>().sum();
+                    }
+                });
+            }
+        });
+    }
+    pairwise_sum(&block_sums)
+}
+
+/// The share of `count` indices computed by one rank of `ranks`.
+///
+/// The indices are split into the blocks of `tree_sum`, and each rank gets a
+/// contiguous run of whole blocks, as even in number as possible.
+///
+/// ```
+/// use quasirandom::reduce::{Partition, PartialSum};
+/// use quasirandom::Qrng;
+///
+/// let count = 100_000;
+/// let term = |x: [f64; 2]| x[0] * x[1];
+///
+/// // On each of 3 ranks: generate and sum this rank's share
+/// let parts: Vec<PartialSum> = (0..3)
+///     .map(|rank| {
+///         let partition = Partition::new(count, rank, 3);
+///         let mut qrng: Qrng<[f64; 2]> = partition.qrng(0.5);
+///         partition.sum(|_| term(qrng.gen()))
+///     })
+///     .collect();
+///
+/// // On the root, after gathering the parts
+/// let sum = PartialSum::merge(parts);
+/// assert!((sum / count as f64 - 0.25).abs() < 1e-4);
+///
+/// // The same bits as a single process summing everything
+/// let all = Partition::new(count, 0, 1);
+/// let mut qrng: Qrng<[f64; 2]> = all.qrng(0.5);
+/// assert_eq!(PartialSum::merge([all.sum(|_| term(qrng.gen()))]), sum);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Partition {
+    count: u64,
+    rank: usize,
+    ranks: usize,
+}
+
+impl Partition {
+    pub fn new(count: u64, rank: usize, ranks: usize) -> Self {
+        assert!(rank < ranks);
+        Self { count, rank, ranks }
+    }
+
+    /// The blocks of this rank, numbered from zero over all ranks
+    pub fn blocks(&self) -> Range<u64> {
+        let blocks = self.count.div_ceil(BLOCK_LEN) as u128;
+        let boundary = |rank: usize| (blocks * rank as u128 / self.ranks as u128) as u64;
+        boundary(self.rank)..boundary(self.rank + 1)
+    }
+
+    /// The indices of this rank
+    pub fn indices(&self) -> Range<u64> {
+        let blocks = self.blocks();
+        (blocks.start * BLOCK_LEN).min(self.count)..(blocks.end * BLOCK_LEN).min(self.count)
+    }
+
+    /// A generator whose next value is the first index of this rank, as
+    /// generated by `Qrng::new(seed)`. Jumping there takes constant time.
+    pub fn qrng<T: Quasirandom<State = State<N>>, const N: usize>(&self, seed: f64) -> Qrng<T> {
+        Qrng::builder().seed(seed).burn_in(self.indices().start).build()
+    }
+
+    /// Sums `term(i)` over the indices of this rank, in order.
+    pub fn sum(&self, mut term: impl FnMut(u64) -> f64) -> PartialSum {
+        let indices = self.indices();
+        let block_sums = self
+            .blocks()
+            .map(|block| {
+                let start = block * BLOCK_LEN;
+                (start..(start + BLOCK_LEN).min(indices.end)).map(&mut term).sum::<CompensatedSum>().sum()
+            })
+            .collect();
+        PartialSum {
+            first_block: self.blocks().start,
+            total_blocks: self.count.div_ceil(BLOCK_LEN),
+            block_sums,
+        }
+    }
+}
+
+/// The block sums of one rank, to be merged with those of the other ranks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialSum {
+    first_block: u64,
+    total_blocks: u64,
+    block_sums: Vec<f64>,
+}
+
+impl PartialSum {
+    /// The sum over all ranks, from the partial sums of every rank in any
+    /// order. Panics unless they partition the same number of indices and
+    /// cover the blocks exactly once.
+    pub fn merge(parts: impl IntoIterator<Item = PartialSum>) -> f64 {
+        let mut parts: Vec<PartialSum> = parts.into_iter().collect();
+        parts.sort_by_key(|part| part.first_block);
+        let total_blocks = parts.first().map_or(0, |part| part.total_blocks);
+        let mut block_sums = Vec::new();
+        // Ranks with no blocks may start anywhere
+        for part in parts.into_iter().filter(|part| !part.block_sums.is_empty()) {
+            assert_eq!(part.first_block, block_sums.len() as u64, "the partial sums must cover every block once");
+            assert_eq!(part.total_blocks, total_blocks, "the partial sums must be over the same number of indices");
+            block_sums.extend(part.block_sums);
+        }
+        assert_eq!(block_sums.len() as u64, total_blocks, "the partial sums must cover every block once");
+        pairwise_sum(&block_sums)
+    }
+
+    /// The partial sum as bytes, for sending to another rank: the first
+    /// block, the number of blocks over all ranks and the number of blocks of
+    /// this rank as little-endian `u64`s, then the block sums as
+    /// little-endian `f64`s.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(24 + 8 * self.block_sums.len());
+        bytes.extend_from_slice(&self.first_block.to_le_bytes());
+        bytes.extend_from_slice(&self.total_blocks.to_le_bytes());
+        bytes.extend_from_slice(&(self.block_sums.len() as u64).to_le_bytes());
+        for sum in &self.block_sums {
+            bytes.extend_from_slice(&sum.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Reads the bytes written by `to_bytes`, or `None` if they are not a
+    /// partial sum.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let word = |i: usize| Some(u64::from_le_bytes(bytes.get(8 * i..8 * i + 8)?.try_into().ok()?));
+        let (first_block, total_blocks, len) = (word(0)?, word(1)?, word(2)?);
+        if bytes.len() as u64 != len.checked_mul(8)?.checked_add(24)? {
+            return None;
+        }
+        let block_sums = (0..len as usize).map(|i| Some(f64::from_bits(word(i + 3)?))).collect::<Option<_>>()?;
+        Some(Self {
+            first_block,
+            total_blocks,
+            block_sums,
+        })
+    }
+}
+
+/// A running sum with compensation for rounding error.
+///
+/// ```
+/// use quasirandom::reduce::CompensatedSum;
+///
+/// let mut sum = CompensatedSum::new();
+/// for _ in 0..1_000_000 {
+///     sum += 0.1;
+/// }
+/// assert_eq!(sum.sum(), 100_000.0);
+/// assert!(sum.compensation() != 0.0);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The compensated sum of every term so far
+    pub fn sum(&self) -> f64 {
+        self.sum + self.compensation
+    }
+
+    /// The running sum as plain addition would have it
+    pub fn uncompensated(&self) -> f64 {
+        self.sum
+    }
+
+    /// The rounding error lost by the running sum, which `sum` adds back
+    pub fn compensation(&self) -> f64 {
+        self.compensation
+    }
+}
+
+impl AddAssign<f64> for CompensatedSum {
+    fn add_assign(&mut self, term: f64) {
+        let sum = self.sum + term;
+        // Recover the low-order bits of whichever operand was smaller
+        self.compensation += if self.sum.abs() >= term.abs() {
+            (self.sum - sum) + term
+        } else {
+            (term - sum) + self.sum
+        };
+        self.sum = sum;
+    }
+}
+
+impl Sum<f64> for CompensatedSum {
+    fn sum<I: Iterator<Item = f64>>(terms: I) -> Self {
+        let mut sum = Self::new();
+        terms.for_each(|term| sum += term);
+        sum
+    }
+}
+
+/// Sums `values` by adding the sums of the two halves
+fn pairwise_sum(values: &[f64]) -> f64 {
+    match values {
+        [] => 0.0,
+        [value] => *value,
+        _ => {
+            let (left, right) = values.split_at(values.len() / 2);
+            pairwise_sum(left) + pairwise_sum(right)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::{mix, to_unit};
+
+    #[test]
+    fn sums_do_not_depend_on_the_thread_count() {
+        // Terms of wildly different magnitudes, where the order matters
+        let term = |i: u64| (to_unit(mix(i)) - 0.5) * 10f64.powi((i % 17) as i32 - 8);
+        for count in [0, 1, 1000, BLOCK_LEN * 7 + 3, 100_000] {
+            let single = tree_sum(count, 1, term);
+            for threads in [2, 3, 8, 64] {
+                assert_eq!(tree_sum(count, threads, term).to_bits(), single.to_bits());
+            }
+            let sequential: f64 = (0..count).map(term).sum();
+            assert!((single - sequential).abs() <= 1e-6 * sequential.abs().max(1.0));
+        }
+        assert_eq!(tree_sum(0, 4, term), 0.0);
+    }
+
+    #[test]
+    fn compensation_recovers_lost_digits() {
+        let sum: CompensatedSum = [1.0, 1e100, 1.0, -1e100].into_iter().sum();
+        assert_eq!(sum.sum(), 2.0);
+        assert_eq!(sum.uncompensated(), 0.0);
+
+        // A billion near-equal terms, sped up: 2^20 copies of a value just
+        // above one, added to a large running sum
+        let term = 1.0 + 2f64.powi(-30);
+        let mut sum = CompensatedSum::new();
+        sum += 2f64.powi(40);
+        (0..1 << 20).for_each(|_| sum += term);
+        assert_eq!(sum.sum(), 2f64.powi(40) + 2f64.powi(20) + 2f64.powi(-10));
+        assert!((sum.uncompensated() - sum.sum()).abs() > 1e-4);
+    }
+
+    #[test]
+    fn partitions_do_not_depend_on_the_rank_count() {
+        let term = |i: u64| to_unit(mix(i)) * 10f64.powi((i % 13) as i32 - 6);
+        for count in [0, 5, BLOCK_LEN * 10 + 1, 50_000] {
+            let expected = tree_sum(count, 2, term);
+            for ranks in [1, 2, 3, 7, 100] {
+                let partitions: Vec<_> = (0..ranks).map(|rank| Partition::new(count, rank, ranks)).collect();
+                let covered: u64 = partitions.iter().map(|p| p.indices().end - p.indices().start).sum();
+                assert_eq!(covered, count);
+                let parts = partitions.iter().rev().map(|p| {
+                    let bytes = p.sum(term).to_bytes();
+                    PartialSum::from_bytes(&bytes).unwrap()
+                });
+                assert_eq!(PartialSum::merge(parts).to_bits(), expected.to_bits());
+            }
+        }
+
+        // Each rank starts its generator at its first index
+        let partition = Partition::new(10_000, 2, 3);
+        let start = partition.indices().start;
+        let mut qrng: Qrng<[f64; 2]> = partition.qrng(0.3);
+        assert_eq!(qrng.gen(), Qrng::<[f64; 2]>::new(0.3).sample_at(start));
+        assert_eq!(PartialSum::from_bytes(&[0; 23]), None);
+    }
+}
@@ -0,0 +1,252 @@
+//! Driving code written against random number generator traits.
+//!
+//! Libraries like quickcheck draw their values through a random number
+//! generator, one integer at a time. `SequenceRng` hands out the coordinates
+//! of an `RSequence` point by point instead: the draws of one test case come
+//! from one point, as long as the case takes no more draws than the sequence
+//! has dimensions, so the cases are spread evenly over everything they can
+//! generate, and the same seed always produces the same cases.
+//!
+//! With the `quickcheck` feature, it implements quickcheck's `RngCore`, so
+//! existing `Arbitrary` implementations run on it unchanged. With the `rand`
+//! feature, it implements `rand::RngCore`, and `sample_dist` samples the
+//! distributions of `rand` and `rand_distr` one point per sample.
+
+use crate::hash::mix;
+use crate::sequences::{RSequence, UniformSequence};
+use crate::FromUniform;
+
+/// A source of uniform draws taken coordinate by coordinate from the points
+/// of an `RSequence`.
+///
+/// ```
+/// use quasirandom::rng::SequenceRng;
+///
+/// let mut rng = SequenceRng::new(2, 0.5);
+/// let first = (rng.next_f64(), rng.next_f64());
+/// // A third draw moves on to the next point
+/// let third = rng.next_f64();
+/// assert_ne!(first.0, third);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceRng {
+    sequence: RSequence,
+    point: Vec<f64>,
+    next: usize,
+}
+
+impl SequenceRng {
+    /// Draws from a sequence of `dimension` dimensions. It should be at
+    /// least the number of draws a case takes.
+    pub fn new(dimension: usize, seed: f64) -> Self {
+        let sequence = RSequence::new(dimension, seed);
+        Self {
+            point: vec![0.0; sequence.dimension()],
+            next: sequence.dimension(),
+            sequence,
+        }
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.point.len()
+    }
+
+    /// The next coordinate of the current point, moving to the next point
+    /// once every coordinate has been drawn
+    pub fn next_f64(&mut self) -> f64 {
+        if self.next == self.point.len() {
+            self.sequence.next_into(&mut self.point);
+            self.next = 0;
+        }
+        self.next += 1;
+        self.point[self.next - 1]
+    }
+
+    /// Skips the rest of the current point, so the next draw starts a new
+    /// one. Call it between cases that take fewer draws than the dimension
+    /// when the driving code allows it.
+    pub fn next_case(&mut self) {
+        self.next = self.point.len();
+    }
+
+    /// Draws 32 bits, the top ones of the next coordinate
+    pub fn next_u32(&mut self) -> u32 {
+        u32::from_uniform(self.next_f64())
+    }
+
+    /// Draws 64 bits. A coordinate carries 53 bits, and the low 11 are a
+    /// hash of them, so that code which takes bits from both ends of a word
+    /// never sees constant bits.
+    pub fn next_u64(&mut self) -> u64 {
+        let high = u64::from_uniform(self.next_f64());
+        high | mix(high) >> 53
+    }
+
+    /// Fills `dest` with the bytes of successive `next_u64` draws, most
+    /// significant first
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_be_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+/// Runs quickcheck's `Arbitrary` implementations on the sequence, with the
+/// `quickcheck` feature.
+///
+/// ```
+/// use quasirandom::rng::SequenceRng;
+/// use quickcheck::QuickCheck;
+///
+/// fn reverse_twice(xs: Vec<u32>) -> bool {
+///     xs.iter().rev().rev().eq(&xs)
+/// }
+///
+/// QuickCheck::new()
+///     .gen(SequenceRng::new(8, 0.5).quickcheck_gen(100))
+///     .quickcheck(reverse_twice as fn(Vec<u32>) -> bool);
+/// ```
+#[cfg(feature = "quickcheck")]
+impl quickcheck::RngCore for SequenceRng {
+    fn next_u32(&mut self) -> u32 {
+        SequenceRng::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        SequenceRng::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        SequenceRng::fill_bytes(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core_05::Error> {
+        SequenceRng::fill_bytes(self, dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl SequenceRng {
+    /// A quickcheck generator of values up to `size`, drawing from this
+    pub fn quickcheck_gen(self, size: usize) -> quickcheck::StdGen<Self> {
+        quickcheck::StdGen::new(self, size)
+    }
+}
+
+#[cfg(feature = "rand")]
+impl rand::RngCore for SequenceRng {
+    fn next_u32(&mut self) -> u32 {
+        SequenceRng::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        SequenceRng::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        SequenceRng::fill_bytes(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        SequenceRng::fill_bytes(self, dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rand")]
+impl SequenceRng {
+    /// Samples `distribution` from a new point, with the `rand` feature.
+    ///
+    /// A distribution that turns a fixed number of draws into a sample by
+    /// inversion, like `Uniform` or `Bernoulli`, keeps the samples as evenly
+    /// spread as the points, as long as it takes no more draws than the
+    /// dimension. Rejection samplers, including the ziggurats behind
+    /// `rand_distr`'s `Normal`, `LogNormal` and `Exp`, take a varying
+    /// number of draws and split words into several values, so their samples
+    /// follow the distribution but are no better spread than random ones.
+    /// Prefer the inverse CDF samplers of `distributions` for those.
+    ///
+    /// ```
+    /// use quasirandom::rng::SequenceRng;
+    /// use rand::distributions::Uniform;
+    ///
+    /// let mut rng = SequenceRng::new(1, 0.5);
+    /// let uniform = Uniform::new(10.0, 20.0);
+    /// let samples: Vec<f64> = (0..1000).map(|_| rng.sample_dist(&uniform)).collect();
+    /// let below_15 = samples.iter().filter(|&&x| x < 15.0).count();
+    /// assert!((below_15 as i32 - 500).abs() <= 1);
+    /// ```
+    pub fn sample_dist<T, D: rand::distributions::Distribution<T>>(&mut self, distribution: &D) -> T {
+        self.next_case();
+        distribution.sample(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cases_are_points_of_the_sequence() {
+        let mut rng = SequenceRng::new(3, 0.25);
+        let mut sequence = RSequence::new(3, 0.25);
+        let mut point = [0.0; 3];
+        for _ in 0..100 {
+            sequence.next_into(&mut point);
+            assert_eq!([rng.next_f64(), rng.next_f64(), rng.next_f64()], point);
+        }
+        sequence.next_into(&mut point);
+        assert_eq!(rng.next_u32(), u32::from_uniform(point[0]));
+        rng.next_case();
+        sequence.next_into(&mut point);
+        let mut bytes = [0; 12];
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(bytes[..6], u64::from_uniform(point[0]).to_be_bytes()[..6]);
+        assert_eq!(bytes[8..], u64::from_uniform(point[1]).to_be_bytes()[..4]);
+    }
+
+    #[cfg(feature = "quickcheck")]
+    #[test]
+    fn quickcheck_cases_are_spread_evenly() {
+        use quickcheck::{Arbitrary, Gen};
+
+        // One draw per case: every value of a range is hit equally often
+        let mut gen = SequenceRng::new(1, 0.5).quickcheck_gen(10);
+        assert_eq!(gen.size(), 10);
+        let mut counts = [0; 10];
+        for _ in 0..1000 {
+            counts[u8::arbitrary(&mut gen) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&count| (95..=105).contains(&count)), "{:?}", counts);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn rand_distributions_sample_one_point_each() {
+        use rand::Rng;
+        use rand::distributions::Bernoulli;
+        use rand_distr::Normal;
+
+        // `gen::<f64>` keeps the 53 bits of the coordinate exactly
+        let mut rng = SequenceRng::new(2, 0.25);
+        let mut sequence = RSequence::new(2, 0.25);
+        let mut point = [0.0; 2];
+        sequence.next_into(&mut point);
+        assert_eq!(rng.gen::<f64>(), point[0]);
+
+        // Inversion is as even as the points
+        let bernoulli = Bernoulli::new(0.3).unwrap();
+        let successes = (0..1000).filter(|_| rng.sample_dist(&bernoulli)).count();
+        assert!((successes as i32 - 300).abs() <= 2, "{}", successes);
+
+        // The ziggurat still produces the right distribution
+        let normal = Normal::new(1.0, 2.0).unwrap();
+        let samples: Vec<f64> = (0..20000).map(|_| rng.sample_dist(&normal)).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / samples.len() as f64;
+        assert!((mean - 1.0).abs() < 0.05, "{}", mean);
+        assert!((variance - 4.0).abs() < 0.2, "{}", variance);
+    }
+}
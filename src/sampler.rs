@@ -0,0 +1,680 @@
+//! Per-pixel samplers for renderers, in the style of PBRT.
+//!
+//! Using one global `Qrng` for a whole image makes neighboring pixels see
+//! neighboring (and therefore correlated) points, which shows up as
+//! structured artifacts. A `PixelSampler` instead gives every pixel its own
+//! well-stratified set of samples, decorrelated from other pixels by hashed
+//! toroidal shifts. A `CmjSampler` uses Kensler's correlated multi-jittered
+//! patterns instead, for 2D samples at a fixed sample count, and a
+//! `ZeroTwoSampler` uses a scrambled (0, 2)-sequence for 2D samples at
+//! power-of-two counts.
+//!
+//! `PixelSampler::get_camera_sample` draws everything a camera ray needs in
+//! one call, with the ray differentials used for texture filtering: the
+//! differential rays reuse the lens and time values of the main ray and only
+//! move on the film, so their difference measures the pixel footprint alone.
+//!
+//! `roulette` makes Russian roulette decisions from one uniform, and
+//! `PixelSampler::roulette` always consumes one dimension for them, so the
+//! dimensions of later bounces line up across paths and each bounce's
+//! decisions stay stratified over the pixel's samples.
+
+use crate::hash::{combine, mix, permute, to_unit, GOLDEN_GAMMA};
+use crate::sequences::zero_two_bits;
+use crate::CONSTANTS;
+
+/// Produces the samples for one pixel at a time.
+///
+/// Each call to `get_1d` or `get_2d` consumes the next dimension(s) of the
+/// current sample. Within a pixel, every dimension (or pair of dimensions for
+/// `get_2d`) takes its values from the first `samples_per_pixel` points of the
+/// 1D or 2D sequence, so each is evenly stratified over the pixel's samples.
+/// The order of those points is permuted by a hash of the pixel and
+/// dimension, which decorrelates different dimensions from one another, and a
+/// hashed toroidal shift decorrelates different pixels.
+///
+/// ```
+/// use quasirandom::sampler::PixelSampler;
+///
+/// let mut sampler = PixelSampler::new(16, 0);
+/// for y in 0..4 {
+///     for x in 0..4 {
+///         sampler.start_pixel(x, y);
+///         loop {
+///             let [film_x, film_y] = sampler.get_2d();
+///             let [lens_u, lens_v] = sampler.get_2d();
+///             let time = sampler.get_1d();
+///             // ... trace a ray
+///             if !sampler.start_next_sample() {
+///                 break;
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PixelSampler {
+    samples_per_pixel: u32,
+    seed: u64,
+    pixel: u64,
+    position: [u32; 2],
+    sample_index: u32,
+    dimension: u32,
+}
+
+/// The samples for one camera ray and its two differential rays, from
+/// `PixelSampler::get_camera_sample`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraSample {
+    /// The film position of the main ray, in pixels
+    pub film: [f64; 2],
+    /// The film position of the ray differential in x: `film` moved by the
+    /// differential scale along x
+    pub film_dx: [f64; 2],
+    /// The film position of the ray differential in y
+    pub film_dy: [f64; 2],
+    /// The point on the lens, shared by all three rays
+    pub lens: [f64; 2],
+    /// The time, shared by all three rays
+    pub time: f64,
+}
+
+impl PixelSampler {
+    /// The dimensions used by `get_camera_sample`: film, lens and time
+    pub const CAMERA_DIMENSIONS: u32 = 5;
+
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        assert!(samples_per_pixel > 0);
+        let mut sampler = Self {
+            samples_per_pixel,
+            seed,
+            pixel: 0,
+            position: [0, 0],
+            sample_index: 0,
+            dimension: 0,
+        };
+        sampler.start_pixel(0, 0);
+        sampler
+    }
+
+    pub fn samples_per_pixel(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    pub fn sample_index(&self) -> u32 {
+        self.sample_index
+    }
+
+    /// The number of dimensions consumed so far by the current sample
+    pub fn dimension(&self) -> u32 {
+        self.dimension
+    }
+
+    /// Moves to the first sample of pixel `(x, y)`.
+    pub fn start_pixel(&mut self, x: u32, y: u32) {
+        self.pixel = combine(combine(self.seed, x as u64), y as u64);
+        self.position = [x, y];
+        self.sample_index = 0;
+        self.dimension = 0;
+    }
+
+    /// Moves to the next sample of the current pixel. Returns false, without
+    /// moving, once all of the pixel's samples have been started.
+    pub fn start_next_sample(&mut self) -> bool {
+        if self.sample_index + 1 >= self.samples_per_pixel {
+            return false;
+        }
+        self.set_sample_index(self.sample_index + 1);
+        true
+    }
+
+    /// Moves to sample `index` of the current pixel, starting from its first
+    /// dimension.
+    pub fn set_sample_index(&mut self, index: u32) {
+        assert!(index < self.samples_per_pixel);
+        self.sample_index = index;
+        self.dimension = 0;
+    }
+
+    pub fn get_1d(&mut self) -> f64 {
+        let (index, hash) = self.next_dimension(1);
+        let alpha = CONSTANTS[0][0];
+        (to_unit(mix(hash)) + index * alpha).fract()
+    }
+
+    pub fn get_2d(&mut self) -> [f64; 2] {
+        let (index, hash) = self.next_dimension(2);
+        let alpha = &CONSTANTS[1];
+        [
+            (to_unit(mix(hash)) + index * alpha[0]).fract(),
+            (to_unit(mix(hash ^ GOLDEN_GAMMA)) + index * alpha[1]).fract(),
+        ]
+    }
+
+    /// A Russian roulette decision from the next dimension, as in `roulette`.
+    /// The dimension is consumed even when `continuation_probability` is one,
+    /// so the layout of the remaining dimensions does not depend on it.
+    ///
+    /// ```
+    /// use quasirandom::sampler::PixelSampler;
+    ///
+    /// let mut sampler = PixelSampler::new(64, 0);
+    /// sampler.start_pixel(0, 0);
+    /// let mut throughput = 1.0;
+    /// for bounce in 0.. {
+    ///     let _bsdf = sampler.get_2d();
+    ///     throughput *= 0.5;
+    ///     match sampler.roulette(if bounce < 3 { 1.0 } else { throughput }) {
+    ///         Some(weight) => throughput *= weight,
+    ///         None => break,
+    ///     }
+    /// }
+    /// ```
+    pub fn roulette(&mut self, continuation_probability: f64) -> Option<f64> {
+        roulette(self.get_1d(), continuation_probability)
+    }
+
+    /// The distance, in pixels, between the main ray and its differentials.
+    /// With many samples per pixel, each sample only needs to filter its share
+    /// of the pixel, so the one-pixel footprint is scaled by
+    /// `1 / sqrt(samples_per_pixel)`, but never below an eighth of a pixel.
+    pub fn differential_scale(&self) -> f64 {
+        (1.0 / (self.samples_per_pixel as f64).sqrt()).max(0.125)
+    }
+
+    /// The film, lens and time samples of a camera ray, with its ray
+    /// differentials. Must be the first call of a sample, so that these
+    /// always take the first `CAMERA_DIMENSIONS` dimensions; later calls to
+    /// `get_1d` and `get_2d` continue from there.
+    ///
+    /// ```
+    /// use quasirandom::sampler::PixelSampler;
+    ///
+    /// let mut sampler = PixelSampler::new(16, 0);
+    /// sampler.start_pixel(10, 20);
+    /// let sample = sampler.get_camera_sample();
+    /// assert!((10.0..11.0).contains(&sample.film[0]));
+    /// assert_eq!(sample.film_dx, [sample.film[0] + 0.25, sample.film[1]]);
+    /// assert_eq!(sample.film_dy, [sample.film[0], sample.film[1] + 0.25]);
+    /// let bsdf = sampler.get_2d();
+    /// ```
+    pub fn get_camera_sample(&mut self) -> CameraSample {
+        assert_eq!(self.dimension, 0, "the camera sample must come first in each sample");
+        let [x, y] = self.get_2d();
+        let film = [self.position[0] as f64 + x, self.position[1] as f64 + y];
+        let scale = self.differential_scale();
+        CameraSample {
+            film,
+            film_dx: [film[0] + scale, film[1]],
+            film_dy: [film[0], film[1] + scale],
+            lens: self.get_2d(),
+            time: self.get_1d(),
+        }
+    }
+
+    /// Consumes `count` dimensions, returning the permuted index of the point
+    /// to use for them and the hash that randomizes them
+    fn next_dimension(&mut self, count: u32) -> (f64, u64) {
+        let hash = combine(self.pixel, self.dimension as u64);
+        self.dimension += count;
+        let index = permute(self.sample_index, self.samples_per_pixel, hash as u32);
+        (index as f64 + 1.0, hash)
+    }
+}
+
+/// A Russian roulette decision: continues a path when the uniform `u` is
+/// below `continuation_probability`, returning the weight `1 / p` that keeps
+/// the estimate unbiased, and terminates it otherwise. A probability of
+/// zero always terminates, and one always continues with weight one, as do
+/// probabilities above one. Negative and NaN probabilities are bugs in the
+/// caller: they panic in debug builds and terminate the path otherwise.
+///
+/// Comparing `u` against the probability is monotone, so a set of evenly
+/// spread uniforms continues almost exactly the expected share of paths.
+///
+/// ```
+/// use quasirandom::sampler::roulette;
+///
+/// assert_eq!(roulette(0.3, 0.5), Some(2.0));
+/// assert_eq!(roulette(0.7, 0.5), None);
+/// assert_eq!(roulette(0.99, 1.5), Some(1.0));
+/// assert_eq!(roulette(0.0, 0.0), None);
+/// ```
+pub fn roulette(u: f64, continuation_probability: f64) -> Option<f64> {
+    debug_assert!(continuation_probability >= 0.0, "continuation probability {}", continuation_probability);
+    // Unlike `min`, this keeps NaN, which then fails the comparison below
+    let p = if continuation_probability > 1.0 { 1.0 } else { continuation_probability };
+    if u < p {
+        Some(1.0 / p)
+    } else {
+        None
+    }
+}
+
+/// Produces 2D samples for one pixel at a time with correlated multi-jittered
+/// sampling (Kensler, "Correlated Multi-Jittered Sampling").
+///
+/// The samples of a pixel are laid out on a grid of `columns` by `rows`
+/// cells, as close to square as the sample count allows. When the count fills
+/// the grid, every cell holds exactly one sample and the samples also fall
+/// one to each of the count's narrow strata along both axes. Each call to
+/// `get_2d` consumes the next pair of dimensions of the current sample, with
+/// its own pattern for every pixel and pair of dimensions.
+///
+/// ```
+/// use quasirandom::sampler::CmjSampler;
+///
+/// let mut sampler = CmjSampler::new(16, 0);
+/// sampler.start_pixel(3, 4);
+/// let mut columns = [0; 16];
+/// loop {
+///     let [film_x, film_y] = sampler.get_2d();
+///     columns[(film_x * 16.0) as usize] += 1;
+///     if !sampler.start_next_sample() {
+///         break;
+///     }
+/// }
+/// assert_eq!(columns, [1; 16]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CmjSampler {
+    samples_per_pixel: u32,
+    columns: u32,
+    rows: u32,
+    seed: u64,
+    pixel: u64,
+    sample_index: u32,
+    dimension: u32,
+}
+
+impl CmjSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        assert!(samples_per_pixel > 0);
+        let columns = (samples_per_pixel as f64).sqrt() as u32;
+        let rows = samples_per_pixel.div_ceil(columns);
+        Self {
+            samples_per_pixel,
+            columns,
+            rows,
+            seed,
+            pixel: combine(combine(seed, 0), 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    pub fn samples_per_pixel(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    /// The number of columns and rows of the grid the samples are jittered in
+    pub fn grid(&self) -> (u32, u32) {
+        (self.columns, self.rows)
+    }
+
+    pub fn sample_index(&self) -> u32 {
+        self.sample_index
+    }
+
+    /// The number of dimensions consumed so far by the current sample
+    pub fn dimension(&self) -> u32 {
+        self.dimension
+    }
+
+    /// Moves to the first sample of pixel `(x, y)`.
+    pub fn start_pixel(&mut self, x: u32, y: u32) {
+        self.pixel = combine(combine(self.seed, x as u64), y as u64);
+        self.sample_index = 0;
+        self.dimension = 0;
+    }
+
+    /// Moves to the next sample of the current pixel. Returns false, without
+    /// moving, once all of the pixel's samples have been started.
+    pub fn start_next_sample(&mut self) -> bool {
+        if self.sample_index + 1 >= self.samples_per_pixel {
+            return false;
+        }
+        self.set_sample_index(self.sample_index + 1);
+        true
+    }
+
+    /// Moves to sample `index` of the current pixel, starting from its first
+    /// dimension.
+    pub fn set_sample_index(&mut self, index: u32) {
+        assert!(index < self.samples_per_pixel);
+        self.sample_index = index;
+        self.dimension = 0;
+    }
+
+    pub fn get_2d(&mut self) -> [f64; 2] {
+        let hash = combine(self.pixel, self.dimension as u64);
+        self.dimension += 2;
+        let pattern = hash as u32;
+        let (m, n) = (self.columns, self.rows);
+
+        let s = permute(self.sample_index, self.samples_per_pixel, pattern.wrapping_mul(0x5163_3e2d));
+        let (column, row) = (s % m, s / m);
+        // The sub-cell offsets are shared along each column and each row,
+        // which is what makes the pattern multi-jittered
+        let shuffled_column = permute(column, m, pattern.wrapping_mul(0xa511_e9b3));
+        let shuffled_row = permute(row, n, pattern.wrapping_mul(0x63d8_3595));
+        let jitter_x = to_unit(combine(hash, s as u64));
+        let jitter_y = to_unit(combine(hash ^ GOLDEN_GAMMA, s as u64));
+        [
+            (column as f64 + (shuffled_row as f64 + jitter_x) / n as f64) / m as f64,
+            (row as f64 + (shuffled_column as f64 + jitter_y) / m as f64) / n as f64,
+        ]
+    }
+}
+
+/// How a `ZeroTwoSampler` randomizes the points of each pixel.
+///
+/// Every variant maps the 32-bit fixed-point coordinates of a point to new
+/// ones, given a seed that depends on the pixel, the dimension, and the
+/// coordinate.
+#[derive(Debug, Clone, Copy)]
+pub enum Scramble {
+    /// The plain sequence: every pixel gets the same points
+    None,
+    /// Random digit scrambling: XORs the bits with the seed
+    Xor,
+    /// Owen scrambling, with the hash of Laine and Karras as refined in
+    /// PBRT: randomly permutes every elementary interval of every size,
+    /// which also improves convergence for smooth integrands
+    Owen,
+    /// A custom scramble. To keep the points a (0, 2)-net it must be a
+    /// bijection that only depends on higher bits when changing lower ones.
+    Custom(fn(u32, u64) -> u32),
+}
+
+impl Scramble {
+    /// Scrambles one fixed-point coordinate.
+    pub fn apply(self, bits: u32, seed: u64) -> u32 {
+        match self {
+            Scramble::None => bits,
+            Scramble::Xor => bits ^ seed as u32,
+            Scramble::Owen => {
+                let seed = seed as u32;
+                let mut v = bits.reverse_bits();
+                v ^= v.wrapping_mul(0x3d20_adea);
+                v = v.wrapping_add(seed);
+                v = v.wrapping_mul((seed >> 16) | 1);
+                v ^= v.wrapping_mul(0x0552_6c56);
+                v ^= v.wrapping_mul(0x53a2_2864);
+                v.reverse_bits()
+            }
+            Scramble::Custom(scramble) => scramble(bits, seed),
+        }
+    }
+}
+
+/// Produces 2D samples for one pixel at a time from a scrambled base-2
+/// (0, 2)-sequence (see `sequences::ZeroTwo`).
+///
+/// When the number of samples per pixel is a power of two, the samples of
+/// each pixel form a (0, 2)-net for every pair of dimensions: they are
+/// stratified over every grid of power-of-two cells with as many cells as
+/// samples, not just one. Each call to `get_2d` consumes the next pair of
+/// dimensions of the current sample. The order of the points is permuted by
+/// a hash of the pixel and dimension, and the points themselves are
+/// scrambled as chosen by `Scramble` (Owen scrambling by default), with a
+/// seed per pixel, dimension, and coordinate.
+///
+/// ```
+/// use quasirandom::sampler::{Scramble, ZeroTwoSampler};
+///
+/// let mut sampler = ZeroTwoSampler::new(16, 0).with_scramble(Scramble::Xor);
+/// sampler.start_pixel(3, 4);
+/// let mut cells = [0; 16];
+/// loop {
+///     // One sample in each cell of a 2x8 grid, and of 4x4 and 8x2 grids
+///     let [film_x, film_y] = sampler.get_2d();
+///     cells[(film_y * 8.0) as usize * 2 + (film_x * 2.0) as usize] += 1;
+///     if !sampler.start_next_sample() {
+///         break;
+///     }
+/// }
+/// assert_eq!(cells, [1; 16]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ZeroTwoSampler {
+    samples_per_pixel: u32,
+    scramble: Scramble,
+    seed: u64,
+    pixel: u64,
+    sample_index: u32,
+    dimension: u32,
+}
+
+impl ZeroTwoSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        assert!(samples_per_pixel > 0);
+        Self {
+            samples_per_pixel,
+            scramble: Scramble::Owen,
+            seed,
+            pixel: combine(combine(seed, 0), 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Replaces the default Owen scrambling.
+    pub fn with_scramble(mut self, scramble: Scramble) -> Self {
+        self.scramble = scramble;
+        self
+    }
+
+    pub fn samples_per_pixel(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    pub fn sample_index(&self) -> u32 {
+        self.sample_index
+    }
+
+    /// The number of dimensions consumed so far by the current sample
+    pub fn dimension(&self) -> u32 {
+        self.dimension
+    }
+
+    /// Moves to the first sample of pixel `(x, y)`.
+    pub fn start_pixel(&mut self, x: u32, y: u32) {
+        self.pixel = combine(combine(self.seed, x as u64), y as u64);
+        self.sample_index = 0;
+        self.dimension = 0;
+    }
+
+    /// Moves to the next sample of the current pixel. Returns false, without
+    /// moving, once all of the pixel's samples have been started.
+    pub fn start_next_sample(&mut self) -> bool {
+        if self.sample_index + 1 >= self.samples_per_pixel {
+            return false;
+        }
+        self.set_sample_index(self.sample_index + 1);
+        true
+    }
+
+    /// Moves to sample `index` of the current pixel, starting from its first
+    /// dimension.
+    pub fn set_sample_index(&mut self, index: u32) {
+        assert!(index < self.samples_per_pixel);
+        self.sample_index = index;
+        self.dimension = 0;
+    }
+
+    pub fn get_2d(&mut self) -> [f64; 2] {
+        let hash = combine(self.pixel, self.dimension as u64);
+        self.dimension += 2;
+        let index = permute(self.sample_index, self.samples_per_pixel, hash as u32);
+        let [x, y] = zero_two_bits(index as u64);
+        [
+            self.scramble.apply(x, mix(hash)),
+            self.scramble.apply(y, mix(hash ^ GOLDEN_GAMMA)),
+        ]
+        .map(|bits| bits as f64 / (1u64 << 32) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roulette_is_stratified_and_keeps_the_layout() {
+        // A quarter of the pixel's samples continue, give or take one
+        let mut sampler = PixelSampler::new(64, 3);
+        sampler.start_pixel(5, 7);
+        let mut continued = 0;
+        let mut weights = 0.0;
+        loop {
+            assert_eq!(sampler.roulette(1.0), Some(1.0));
+            if let Some(weight) = sampler.roulette(0.25) {
+                continued += 1;
+                weights += weight;
+            }
+            assert_eq!(sampler.dimension(), 2);
+            if !sampler.start_next_sample() {
+                break;
+            }
+        }
+        assert!((continued - 16_i32).abs() <= 1, "{}", continued);
+        assert_eq!(weights, 4.0 * continued as f64);
+        assert_eq!(roulette(0.0, 0.0), None);
+        assert_eq!(roulette(crate::MAX_UNIFORM, 0.0), None);
+        assert_eq!(roulette(0.0, 1.0), Some(1.0));
+        assert_eq!(roulette(crate::MAX_UNIFORM, 1.0), Some(1.0));
+        assert_eq!(roulette(crate::MAX_UNIFORM, f64::INFINITY), Some(1.0));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn roulette_rejects_invalid_probabilities() {
+        for p in [-0.5, f64::NAN] {
+            assert!(std::panic::catch_unwind(|| roulette(0.0, p)).is_err());
+        }
+    }
+
+    #[test]
+    fn pixel_samples_are_stratified_and_decorrelated() {
+        let spp = 64;
+        let mut sampler = PixelSampler::new(spp, 7);
+        sampler.start_pixel(3, 5);
+        let mut samples = vec![];
+        loop {
+            samples.push((sampler.get_1d(), sampler.get_1d(), sampler.get_2d()));
+            if !sampler.start_next_sample() {
+                break;
+            }
+        }
+        assert_eq!(samples.len(), spp as usize);
+
+        let mut strata = [0; 8];
+        let mut covariance = 0.0;
+        for &(a, b, [x, y]) in &samples {
+            strata[(a * 8.0) as usize] += 1;
+            covariance += (a - 0.5) * (b - 0.5);
+            assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+        }
+        assert!(strata.iter().all(|&count| (7..=9).contains(&count)), "{:?}", strata);
+        assert!((12.0 * covariance / spp as f64).abs() < 0.3);
+
+        // Replaying a sample is deterministic, and other pixels differ
+        sampler.set_sample_index(10);
+        assert_eq!(sampler.get_1d(), samples[10].0);
+        sampler.start_pixel(4, 5);
+        sampler.set_sample_index(10);
+        assert_ne!(sampler.get_1d(), samples[10].0);
+    }
+
+    #[test]
+    fn camera_samples_share_lens_and_time() {
+        let mut sampler = PixelSampler::new(4, 3);
+        sampler.start_pixel(7, 2);
+        sampler.set_sample_index(1);
+        let sample = sampler.get_camera_sample();
+        assert_eq!(sampler.dimension(), PixelSampler::CAMERA_DIMENSIONS);
+        assert_eq!(sampler.differential_scale(), 0.5);
+        assert_eq!(sample.film_dx[0] - sample.film[0], 0.5);
+        assert_eq!(sample.film_dy[1] - sample.film[1], 0.5);
+
+        // The same dimensions as drawing each value in turn
+        sampler.set_sample_index(1);
+        let film = sampler.get_2d();
+        assert_eq!(sample.film, [7.0 + film[0], 2.0 + film[1]]);
+        assert_eq!(sample.lens, sampler.get_2d());
+        assert_eq!(sample.time, sampler.get_1d());
+        assert_eq!(PixelSampler::new(10_000, 0).differential_scale(), 0.125);
+    }
+
+    #[test]
+    fn cmj_samples_are_multi_jittered() {
+        for &(spp, columns, rows) in &[(16, 4, 4), (12, 3, 4), (64, 8, 8)] {
+            let mut sampler = CmjSampler::new(spp, 1);
+            assert_eq!(sampler.grid(), (columns, rows));
+            for pixel in 0..4 {
+                sampler.start_pixel(pixel, 2);
+                let mut samples = vec![];
+                loop {
+                    samples.push((sampler.get_2d(), sampler.get_2d()));
+                    if !sampler.start_next_sample() {
+                        break;
+                    }
+                }
+                for dimension in 0..2 {
+                    let mut cells = vec![0; spp as usize];
+                    let mut strata_x = vec![0; spp as usize];
+                    let mut strata_y = vec![0; spp as usize];
+                    for sample in &samples {
+                        let [x, y] = if dimension == 0 { sample.0 } else { sample.1 };
+                        assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+                        let (column, row) = ((x * columns as f64) as u32, (y * rows as f64) as u32);
+                        cells[(row * columns + column) as usize] += 1;
+                        strata_x[(x * spp as f64) as usize] += 1;
+                        strata_y[(y * spp as f64) as usize] += 1;
+                    }
+                    assert!(cells.iter().all(|&count| count == 1), "{:?}", cells);
+                    assert!(strata_x.iter().chain(&strata_y).all(|&count| count == 1));
+                }
+                // Different dimensions and pixels get different patterns
+                assert_ne!(samples[0].0, samples[0].1);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_two_samples_are_nets() {
+        let scrambles = [Scramble::None, Scramble::Xor, Scramble::Owen, Scramble::Custom(|bits, _| !bits)];
+        for scramble in scrambles {
+            let mut sampler = ZeroTwoSampler::new(64, 3).with_scramble(scramble);
+            let mut first = vec![];
+            for pixel in 0..3 {
+                sampler.start_pixel(pixel, 0);
+                let mut samples = vec![];
+                loop {
+                    samples.push([sampler.get_2d(), sampler.get_2d()]);
+                    if !sampler.start_next_sample() {
+                        break;
+                    }
+                }
+                for dimension in 0..2 {
+                    for a in 0..=6 {
+                        let (columns, rows) = (1 << a, 1 << (6 - a));
+                        let mut cells = [0; 64];
+                        for sample in &samples {
+                            let [x, y] = sample[dimension];
+                            assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+                            cells[(y * rows as f64) as usize * columns + (x * columns as f64) as usize] += 1;
+                        }
+                        assert_eq!(cells, [1; 64]);
+                    }
+                }
+                first.push(samples[0][0]);
+            }
+            // Scrambled pixels differ from each other
+            if !matches!(scramble, Scramble::None | Scramble::Custom(_)) {
+                assert_ne!(first[0], first[1]);
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Choosing thread interleavings for concurrency tests.
+//!
+//! A test scheduler repeatedly picks which runnable task goes next. Picking
+//! uniformly at random explores the tree of interleavings unevenly: some
+//! schedules repeat while others wait a long time to be tried. Here each run
+//! takes its picks from a `DecisionStream`, so the runs divide the tree of
+//! schedules evenly.
+
+use crate::decisions::{DecisionStream, Explorer};
+
+/// Produces one `Schedule` per test run.
+///
+/// This is an `Explorer` whose decisions are which task to run: any prefix
+/// of a schedule with probability `p` within the first `depth` real choices
+/// (those between two or more tasks) is reached within the first `4 / p`
+/// runs. Choices beyond `depth` are pseudorandom.
+///
+/// ```
+/// use quasirandom::schedule::ScheduleFuzzer;
+///
+/// let mut fuzzer = ScheduleFuzzer::new(16, 0);
+/// for _ in 0..100 {
+///     let mut schedule = fuzzer.next_schedule();
+///     let mut remaining = vec![3, 3];
+///     while remaining.iter().any(|&steps| steps > 0) {
+///         let runnable: Vec<usize> = (0..2).filter(|&task| remaining[task] > 0).collect();
+///         let &task = schedule.pick_from(&runnable);
+///         remaining[task] -= 1;
+///         // ... step `task`
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleFuzzer {
+    explorer: Explorer,
+}
+
+/// The choices of a single test run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    stream: DecisionStream,
+}
+
+impl ScheduleFuzzer {
+    pub fn new(depth: u32, seed: u64) -> Self {
+        Self {
+            explorer: Explorer::new(depth, seed),
+        }
+    }
+
+    /// The number of schedules produced so far
+    pub fn runs(&self) -> u64 {
+        self.explorer.runs()
+    }
+
+    pub fn next_schedule(&mut self) -> Schedule {
+        Schedule {
+            stream: self.explorer.next_stream(),
+        }
+    }
+}
+
+impl Schedule {
+    /// Picks which of `runnable` tasks runs next, returning an index below
+    /// `runnable`.
+    pub fn pick(&mut self, runnable: usize) -> usize {
+        self.stream.decide(runnable)
+    }
+
+    /// Picks the next task from a list of runnable ones.
+    pub fn pick_from<'a, T>(&mut self, runnable: &'a [T]) -> &'a T {
+        &runnable[self.pick(runnable.len())]
+    }
+
+    /// The number of real choices made so far, not counting picks with only
+    /// one runnable task
+    pub fn choices(&self) -> u32 {
+        self.stream.decisions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Runs three tasks of two steps each, returning the interleaving
+    fn interleaving(schedule: &mut Schedule) -> Vec<usize> {
+        let mut remaining = [2; 3];
+        let mut order = vec![];
+        while remaining.iter().any(|&steps| steps > 0) {
+            let runnable: Vec<usize> = (0..3).filter(|&task| remaining[task] > 0).collect();
+            let &task = schedule.pick_from(&runnable);
+            remaining[task] -= 1;
+            order.push(task);
+        }
+        order
+    }
+
+    #[test]
+    fn covers_every_interleaving() {
+        // There are 6! / 2!^3 = 90 interleavings, the least likely of which
+        // has probability 1 / 108, so all appear within 512 runs
+        for seed in 0..4 {
+            let mut fuzzer = ScheduleFuzzer::new(8, seed);
+            let seen: HashSet<Vec<usize>> = (0..512).map(|_| interleaving(&mut fuzzer.next_schedule())).collect();
+            assert_eq!(seen.len(), 90);
+        }
+
+        // Two tasks: the first 2^k runs split evenly over each of the first
+        // k choices
+        let mut fuzzer = ScheduleFuzzer::new(8, 9);
+        let mut counts = [[0; 2]; 3];
+        for _ in 0..8 {
+            let mut schedule = fuzzer.next_schedule();
+            let choices: Vec<usize> = (0..3).map(|_| schedule.pick(2)).collect();
+            counts[0][choices[0]] += 1;
+            counts[1][choices[1]] += 1;
+            counts[2][choices[2]] += 1;
+        }
+        assert_eq!(counts, [[4, 4]; 3]);
+    }
+}
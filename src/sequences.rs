@@ -0,0 +1,715 @@
+//! Low-discrepancy sequences whose dimension is chosen at runtime.
+//!
+//! `Qrng` fixes its dimension in the type, which is convenient but limited to
+//! 32 dimensions. The sequences here produce points as slices of `f64`
+//! instead, for any number of dimensions, and share the `UniformSequence`
+//! trait so code can be written once for all of them. The trait is object
+//! safe, and `Qrng` implements it too, so the sequence can also be chosen at
+//! runtime, e.g. from a configuration file with `by_name`.
+//! The open-ended sequences also implement `LowDiscrepancy`, which builds
+//! them from a dimension, so they can be chosen by a type parameter instead.
+
+use crate::{from_fixed, to_fixed, Qrng, Quasirandom, State, CONSTANTS, MAX_DIMENSION, MAX_UNIFORM};
+
+mod faure;
+mod lattice;
+mod niederreiter;
+mod zero_two;
+
+pub use faure::Faure;
+pub use lattice::Lattice;
+pub use niederreiter::Niederreiter;
+pub use zero_two::ZeroTwo;
+pub(crate) use zero_two::zero_two_bits;
+
+/// A source of points in `[0, 1)^d` for a dimension `d` fixed at construction.
+pub trait UniformSequence {
+    /// The number of coordinates in each point
+    fn dimension(&self) -> usize;
+
+    /// Writes the next point into `out`, which must have length
+    /// `dimension()`. Returns false, leaving `out` untouched, once a finite
+    /// point set has been exhausted. Infinite sequences always return true.
+    fn next_into(&mut self, out: &mut [f64]) -> bool;
+
+    /// What this kind of sequence supports. The default claims nothing but
+    /// an unbounded dimension.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_dimension: None,
+            progressive: false,
+            scrambling: false,
+            random_access: false,
+        }
+    }
+}
+
+/// An open-ended low-discrepancy sequence that can be built in any
+/// dimension it supports, so generic code can construct the backend it is
+/// given as a type parameter.
+///
+/// ```
+/// use quasirandom::sequences::{Halton, LowDiscrepancy, Niederreiter, RSequence};
+///
+/// fn integrate<S: LowDiscrepancy>(f: impl Fn(&[f64]) -> f64, dimension: usize, n: usize) -> f64 {
+///     let mut sequence = S::with_dimension(dimension);
+///     let mut point = vec![0.0; dimension];
+///     (0..n).map(|_| {
+///         sequence.next_into(&mut point);
+///         f(&point)
+///     }).sum::<f64>() / n as f64
+/// }
+///
+/// let f = |x: &[f64]| x.iter().product::<f64>();
+/// for estimate in [
+///     integrate::<RSequence>(f, 3, 4096),
+///     integrate::<Halton>(f, 3, 4096),
+///     integrate::<Niederreiter>(f, 3, 4096),
+/// ] {
+///     assert!((estimate - 0.125).abs() < 1e-3);
+/// }
+/// ```
+pub trait LowDiscrepancy: UniformSequence + Sized {
+    /// The largest supported dimension, or `None` if there is no limit
+    const MAX_DIMENSION: Option<usize>;
+
+    /// The sequence in `dimension` dimensions, which must be at least one and
+    /// at most `MAX_DIMENSION`, starting at its first point
+    fn with_dimension(dimension: usize) -> Self;
+}
+
+impl<S: UniformSequence + ?Sized> UniformSequence for Box<S> {
+    fn dimension(&self) -> usize {
+        (**self).dimension()
+    }
+
+    fn next_into(&mut self, out: &mut [f64]) -> bool {
+        (**self).next_into(out)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        (**self).capabilities()
+    }
+}
+
+/// The uniforms behind each value, one per dimension
+impl<const N: usize, T: Quasirandom<State = State<N>>> UniformSequence for Qrng<T> {
+    fn dimension(&self) -> usize {
+        N
+    }
+
+    fn next_into(&mut self, out: &mut [f64]) -> bool {
+        out.copy_from_slice(&self.state.gen());
+        true
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Self::CAPABILITIES
+    }
+}
+
+/// The open-ended sequence called `name` in `dimension` dimensions, or `None`
+/// if there is no such sequence or it does not support that dimension.
+///
+/// The names are `"r"` (`RSequence` with seed zero), `"halton"`, `"faure"`,
+/// `"niederreiter"`, `"van-der-corput"` (in base 2), and `"zero-two"`. Point
+/// sets of a fixed size, like `Hammersley` and `Lattice`, need more
+/// parameters and are built directly instead.
+///
+/// ```
+/// use quasirandom::sequences::{by_name, UniformSequence};
+///
+/// let mut sequence = by_name("niederreiter", 3).unwrap();
+/// let mut point = [0.0; 3];
+/// sequence.next_into(&mut point);
+/// assert!(by_name("zero-two", 3).is_none());
+/// ```
+pub fn by_name(name: &str, dimension: usize) -> Option<Box<dyn UniformSequence>> {
+    if dimension == 0 {
+        return None;
+    }
+    let sequence: Box<dyn UniformSequence> = match name {
+        "r" => Box::new(RSequence::with_dimension(dimension)),
+        "halton" => Box::new(Halton::with_dimension(dimension)),
+        "faure" => Box::new(Faure::with_dimension(dimension)),
+        "niederreiter" => Box::new(Niederreiter::with_dimension(dimension)),
+        "van-der-corput" if dimension == 1 => Box::new(VanDerCorput::with_dimension(1)),
+        "zero-two" if dimension == 2 => Box::new(ZeroTwo::with_dimension(2)),
+        _ => return None,
+    };
+    Some(sequence)
+}
+
+/// The features of a kind of sequence, for generic code that adapts to
+/// whichever one it is given.
+///
+/// ```
+/// use quasirandom::sequences::{Lattice, RSequence, UniformSequence};
+///
+/// // Only progressive sequences can stop early once an estimate converges
+/// let candidates: [&dyn UniformSequence; 2] = [&RSequence::new(2, 0.0), &Lattice::fibonacci(20)];
+/// let adaptive: Vec<bool> = candidates.iter().map(|s| s.capabilities().progressive).collect();
+/// assert_eq!(adaptive, [true, false]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    /// The largest dimension the sequence can be built with, or `None` if
+    /// there is no limit
+    pub max_dimension: Option<usize>,
+    /// Whether every prefix of the points is well distributed, so that
+    /// sampling can stop at any count. Point sets designed for a fixed count,
+    /// like `Hammersley` and `Lattice`, are not.
+    pub progressive: bool,
+    /// Whether the points are a digital net or sequence, so that digit
+    /// scrambling (see `sampler::Scramble`) keeps their stratification
+    pub scrambling: bool,
+    /// Whether the point at any index can be computed directly, with `point`
+    /// or `set_index`, instead of stepping through the ones before it
+    pub random_access: bool,
+}
+
+/// The same additive recurrence as `Qrng`, with the dimension chosen at
+/// runtime.
+///
+/// For up to 32 dimensions it produces exactly the values of a `Qrng` over a
+/// tuple of that many `f64`s with the same seed. Beyond that, the generating
+/// constants are computed on construction.
+///
+/// ```
+/// use quasirandom::sequences::{RSequence, UniformSequence};
+///
+/// let mut sequence = RSequence::new(100, 0.5);
+/// let mut point = vec![0.0; 100];
+/// sequence.next_into(&mut point);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RSequence {
+    /// The generating constants and the last point, as fractions of 2^64
+    alphas: Vec<u64>,
+    state: Vec<u64>,
+}
+
+impl RSequence {
+    pub fn new(dimension: usize, seed: f64) -> Self {
+        assert!(dimension > 0);
+        assert!(seed >= 0.0);
+        assert!(seed < 1.0);
+        let seed = to_fixed(seed);
+        Self {
+            alphas: alphas(dimension).into_iter().map(to_fixed).collect(),
+            state: (0..dimension as u64).map(|i| seed.wrapping_mul(i)).collect(),
+        }
+    }
+}
+
+impl UniformSequence for RSequence {
+    fn dimension(&self) -> usize {
+        self.state.len()
+    }
+
+    fn next_into(&mut self, out: &mut [f64]) -> bool {
+        assert_eq!(out.len(), self.state.len());
+        for ((x, alpha), out) in self.state.iter_mut().zip(&self.alphas).zip(out) {
+            *x = x.wrapping_add(*alpha);
+            *out = from_fixed(*x);
+        }
+        true
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_dimension: None,
+            progressive: true,
+            scrambling: false,
+            random_access: false,
+        }
+    }
+}
+
+/// With seed zero
+impl LowDiscrepancy for RSequence {
+    const MAX_DIMENSION: Option<usize> = None;
+
+    fn with_dimension(dimension: usize) -> Self {
+        RSequence::new(dimension, 0.0)
+    }
+}
+
+impl LowDiscrepancy for Halton {
+    const MAX_DIMENSION: Option<usize> = None;
+
+    fn with_dimension(dimension: usize) -> Self {
+        Halton::new(dimension)
+    }
+}
+
+impl LowDiscrepancy for Faure {
+    const MAX_DIMENSION: Option<usize> = None;
+
+    fn with_dimension(dimension: usize) -> Self {
+        Faure::new(dimension)
+    }
+}
+
+impl LowDiscrepancy for Niederreiter {
+    const MAX_DIMENSION: Option<usize> = None;
+
+    fn with_dimension(dimension: usize) -> Self {
+        Niederreiter::new(dimension)
+    }
+}
+
+/// In base 2
+impl LowDiscrepancy for VanDerCorput {
+    const MAX_DIMENSION: Option<usize> = Some(1);
+
+    fn with_dimension(dimension: usize) -> Self {
+        assert_eq!(dimension, 1);
+        VanDerCorput::new(2)
+    }
+}
+
+impl LowDiscrepancy for ZeroTwo {
+    const MAX_DIMENSION: Option<usize> = Some(2);
+
+    fn with_dimension(dimension: usize) -> Self {
+        assert_eq!(dimension, 2);
+        ZeroTwo::new()
+    }
+}
+
+/// The Hammersley point set: `n` points in `d` dimensions whose first
+/// coordinate is `i / n` and whose remaining coordinates are the radical
+/// inverses of `i` in the first `d - 1` prime bases.
+///
+/// Because the number of points is known up front, the first coordinate can
+/// be spaced perfectly, giving lower discrepancy than any prefix of an
+/// open-ended sequence. Points are yielded in index order and the set is
+/// exhausted after `n` of them.
+///
+/// ```
+/// use quasirandom::sequences::{Hammersley, UniformSequence};
+///
+/// let mut points = Hammersley::points(1024, 2);
+/// let mut point = [0.0; 2];
+/// let mut inside = 0;
+/// while points.next_into(&mut point) {
+///     if point[0].hypot(point[1]) < 1.0 {
+///         inside += 1;
+///     }
+/// }
+/// assert!((4.0 * inside as f64 / 1024.0 - std::f64::consts::PI).abs() < 0.01);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hammersley {
+    count: u64,
+    bases: Vec<u32>,
+    index: u64,
+}
+
+impl Hammersley {
+    pub fn points(count: u64, dimension: usize) -> Self {
+        assert!(count > 0);
+        assert!(dimension > 0);
+        Self {
+            count,
+            bases: first_primes(dimension - 1),
+            index: 0,
+        }
+    }
+
+    /// The total number of points in the set
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Writes point `index` (which must be less than `len()`) into `out`.
+    pub fn point(&self, index: u64, out: &mut [f64]) {
+        assert!(index < self.count);
+        assert_eq!(out.len(), self.bases.len() + 1);
+        out[0] = index as f64 / self.count as f64;
+        for (out, &base) in out[1..].iter_mut().zip(&self.bases) {
+            *out = radical_inverse(index, base);
+        }
+    }
+}
+
+impl UniformSequence for Hammersley {
+    fn dimension(&self) -> usize {
+        self.bases.len() + 1
+    }
+
+    fn next_into(&mut self, out: &mut [f64]) -> bool {
+        if self.index >= self.count {
+            return false;
+        }
+        self.point(self.index, out);
+        self.index += 1;
+        true
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_dimension: None,
+            progressive: false,
+            scrambling: false,
+            random_access: true,
+        }
+    }
+}
+
+/// The Halton sequence: coordinate `j` of point `i` is the radical inverse
+/// of `i` in the `j`th prime base.
+///
+/// Each coordinate is a van der Corput sequence, so every prefix is well
+/// distributed, but the bases grow with the dimension and the coordinates in
+/// large bases are strongly correlated over short runs. The sequence starts
+/// at index 0, the origin. Like the index of a `Qrng`, the index wraps
+/// around after 2^64 points, so the sequence repeats with that period.
+///
+/// ```
+/// use quasirandom::sequences::{Halton, UniformSequence};
+///
+/// let mut halton = Halton::new(2);
+/// let mut point = [0.0; 2];
+/// halton.set_index(5);
+/// halton.next_into(&mut point);
+/// assert_eq!(point, [0.625, 7.0 / 9.0]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Halton {
+    bases: Vec<u32>,
+    index: u64,
+}
+
+impl Halton {
+    pub fn new(dimension: usize) -> Self {
+        assert!(dimension > 0);
+        Self {
+            bases: first_primes(dimension),
+            index: 0,
+        }
+    }
+
+    /// The index of the next point to be generated
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Moves to `index`, so that it is the next point generated.
+    pub fn set_index(&mut self, index: u64) {
+        self.index = index;
+    }
+
+    /// Writes point `index` into `out`, which must have length `dimension`.
+    pub fn point(&self, index: u64, out: &mut [f64]) {
+        assert_eq!(out.len(), self.bases.len());
+        for (out, &base) in out.iter_mut().zip(&self.bases) {
+            *out = radical_inverse(index, base);
+        }
+    }
+}
+
+impl UniformSequence for Halton {
+    fn dimension(&self) -> usize {
+        self.bases.len()
+    }
+
+    fn next_into(&mut self, out: &mut [f64]) -> bool {
+        self.point(self.index, out);
+        self.index = self.index.wrapping_add(1);
+        true
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_dimension: None,
+            progressive: true,
+            scrambling: true,
+            random_access: true,
+        }
+    }
+}
+
+/// The van der Corput sequence in base `base`: the radical inverses of
+/// `0, 1, 2, ...`. Exhausted after `2^64` points.
+///
+/// Sequences in different prime bases are only weakly correlated with each
+/// other and with `Qrng`, so this is a simple source of an extra 1D stream.
+///
+/// ```
+/// use quasirandom::sequences::{van_der_corput, VanDerCorput};
+///
+/// let mut sequence = VanDerCorput::new(3);
+/// let first: Vec<f64> = (0..4).map(|_| sequence.next_1d()).collect();
+/// assert_eq!(first, [0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0]);
+/// assert_eq!(van_der_corput(6, 2), 0.375);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VanDerCorput {
+    base: u32,
+    index: u64,
+}
+
+impl VanDerCorput {
+    pub fn new(base: u32) -> Self {
+        assert!(base >= 2);
+        Self { base, index: 0 }
+    }
+
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    /// The index of the next point to be generated
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Moves to `index`, so that it is the next point generated.
+    pub fn set_index(&mut self, index: u64) {
+        self.index = index;
+    }
+
+    /// Generates the next value.
+    pub fn next_1d(&mut self) -> f64 {
+        let value = radical_inverse(self.index, self.base);
+        self.index = self.index.checked_add(1).expect("the van der Corput sequence is exhausted");
+        value
+    }
+}
+
+impl UniformSequence for VanDerCorput {
+    fn dimension(&self) -> usize {
+        1
+    }
+
+    fn next_into(&mut self, out: &mut [f64]) -> bool {
+        assert_eq!(out.len(), 1);
+        if self.index == u64::MAX {
+            return false;
+        }
+        out[0] = self.next_1d();
+        true
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_dimension: Some(1),
+            progressive: true,
+            scrambling: true,
+            random_access: true,
+        }
+    }
+}
+
+/// Point `index` of the van der Corput sequence in base `base`: the base
+/// `base` digits of `index` reflected about the radix point, e.g. 6 = 110 in
+/// base 2 becomes 0.011 = 0.375. The result is below one even where rounding
+/// would reach it.
+pub fn van_der_corput(index: u64, base: u32) -> f64 {
+    assert!(base >= 2);
+    radical_inverse(index, base)
+}
+
+/// Takes up to `n` points from `sequence` as the rows of an `n x dimension`
+/// array in standard (row-major, contiguous) layout. The array has fewer rows
+/// if a finite sequence runs out.
+#[cfg(feature = "ndarray")]
+pub fn array2<S: UniformSequence + ?Sized>(sequence: &mut S, n: usize) -> ndarray::Array2<f64> {
+    let dimension = sequence.dimension();
+    let mut values = vec![0.0; n * dimension];
+    let mut rows = 0;
+    for row in values.chunks_exact_mut(dimension) {
+        if !sequence.next_into(row) {
+            break;
+        }
+        rows += 1;
+    }
+    values.truncate(rows * dimension);
+    ndarray::Array2::from_shape_vec((rows, dimension), values).unwrap()
+}
+
+/// Reflects the base-`base` digits of `index` about the radix point, e.g.
+/// 6 = 110 in base 2 becomes 0.011 = 0.375.
+pub(crate) fn radical_inverse(mut index: u64, base: u32) -> f64 {
+    let base = base as u128;
+    let mut reversed: u128 = 0;
+    let mut denominator: u128 = 1;
+    while index > 0 {
+        reversed = reversed * base + (index as u128 % base);
+        denominator *= base;
+        index /= base as u64;
+    }
+    (reversed as f64 / denominator as f64).min(MAX_UNIFORM)
+}
+
+/// The first `count` prime numbers
+pub(crate) fn first_primes(count: usize) -> Vec<u32> {
+    let mut primes: Vec<u32> = Vec::with_capacity(count);
+    let mut candidate = 2;
+    while primes.len() < count {
+        if primes.iter().take_while(|&&p| p * p <= candidate).all(|&p| candidate % p != 0) {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+/// The generating constants of the `dimension`-dimensional sequence. See
+/// `CONSTANTS` for their derivation, which is repeated here for dimensions
+/// beyond the table.
+pub(crate) fn alphas(dimension: usize) -> Vec<f64> {
+    if dimension <= MAX_DIMENSION {
+        return CONSTANTS[dimension - 1][..dimension].to_vec();
+    }
+
+    let d = dimension as i32;
+    let mut lower = 1.0_f64;
+    let mut upper = 2.0_f64;
+    while upper - lower > 1e-14 {
+        let mid = (lower + upper) / 2.0;
+        if mid.powi(d + 1) < mid + 1.0 {
+            lower = mid;
+        } else {
+            upper = mid;
+        }
+    }
+    (1..=d).map(|i| lower.powi(i).recip()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Qrng;
+
+    #[test]
+    fn matches_qrng() {
+        let mut qrng = Qrng::<[f64; 5]>::new(0.3);
+        let mut tuple = Qrng::<(f64, f64, f64, f64, f64)>::new(0.3);
+        let mut sequence = RSequence::new(5, 0.3);
+        let mut point = [0.0; 5];
+        for _ in 0..100 {
+            sequence.next_into(&mut point);
+            let (a, b, c, d, e) = tuple.gen();
+            assert_eq!(qrng.gen(), point);
+            assert_eq!([a, b, c, d, e], point);
+        }
+    }
+
+    #[test]
+    fn capabilities() {
+        let sequences: Vec<Box<dyn UniformSequence>> = vec![
+            Box::new(RSequence::new(3, 0.0)),
+            Box::new(Hammersley::points(16, 3)),
+            Box::new(VanDerCorput::new(2)),
+            Box::new(Faure::new(3)),
+            Box::new(Lattice::korobov(17, 3)),
+            Box::new(Niederreiter::new(3)),
+            Box::new(ZeroTwo::new()),
+        ];
+        let progressive: Vec<bool> = sequences.iter().map(|s| s.capabilities().progressive).collect();
+        assert_eq!(progressive, [true, false, true, true, false, true, true]);
+        for sequence in &sequences {
+            let capabilities = sequence.capabilities();
+            assert!(capabilities.max_dimension.is_none_or(|max| sequence.dimension() <= max));
+            assert!(!capabilities.scrambling || capabilities.random_access);
+        }
+        let unbounded = RSequence::new(1, 0.0).capabilities();
+        assert_eq!(Qrng::<f64>::CAPABILITIES, Capabilities { max_dimension: Some(MAX_DIMENSION), random_access: true, ..unbounded });
+    }
+
+    #[test]
+    fn sequences_by_name() {
+        for (name, dimension) in [("r", 4), ("halton", 4), ("faure", 4), ("niederreiter", 4), ("van-der-corput", 1), ("zero-two", 2)] {
+            let mut sequence = by_name(name, dimension).unwrap();
+            assert_eq!(sequence.dimension(), dimension);
+            let mut point = vec![0.0; dimension];
+            assert!(sequence.next_into(&mut point));
+            assert_eq!(by_name(name, 40).is_some(), sequence.capabilities().max_dimension.is_none());
+        }
+        assert!(by_name("sobol", 2).is_none());
+        assert!(by_name("r", 0).is_none());
+
+        // A boxed `Qrng` produces the same uniforms as the plain one
+        let mut boxed: Box<dyn UniformSequence> = Box::new(Qrng::<(f64, u8)>::new(0.4));
+        let mut sequence = RSequence::new(2, 0.4);
+        let (mut a, mut b) = ([0.0; 2], [0.0; 2]);
+        for _ in 0..10 {
+            boxed.next_into(&mut a);
+            sequence.next_into(&mut b);
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn hammersley_is_stratified() {
+        assert_eq!(first_primes(6), [2, 3, 5, 7, 11, 13]);
+        assert_eq!(radical_inverse(6, 2), 0.375);
+
+        // With 256 points, the first two coordinates put exactly one point in
+        // every cell of a 16x16 grid
+        let mut hammersley = Hammersley::points(256, 3);
+        let mut point = [0.0; 3];
+        let mut cells = [0; 256];
+        let mut sum = 0.0;
+        while hammersley.next_into(&mut point) {
+            cells[(point[0] * 16.0) as usize * 16 + (point[1] * 16.0) as usize] += 1;
+            sum += point[2];
+        }
+        assert!(cells.iter().all(|&count| count == 1));
+        assert!((sum / 256.0 - 0.5).abs() < 0.01);
+        assert!(!hammersley.next_into(&mut point));
+    }
+
+    #[test]
+    fn high_dimensional_constants() {
+        let alphas = alphas(100);
+        let phi = alphas[0].recip();
+        assert!((phi.powi(101) - phi - 1.0).abs() < 1e-10);
+        assert!(alphas.windows(2).all(|w| w[0] > w[1] && w[1] > 0.5));
+    }
+
+    #[test]
+    fn halton_coordinates_are_van_der_corput() {
+        let mut halton = Halton::with_dimension(4);
+        assert_eq!(Halton::MAX_DIMENSION, None);
+        let mut point = [0.0; 4];
+        for i in 0..1000 {
+            halton.next_into(&mut point);
+            assert_eq!(point, [2, 3, 5, 7].map(|base| van_der_corput(i, base)));
+        }
+        assert_eq!(halton.index(), 1000);
+
+        halton.set_index(u64::MAX);
+        halton.next_into(&mut point);
+        assert_eq!(halton.index(), 0);
+    }
+
+    #[test]
+    fn van_der_corput_is_stratified() {
+        for base in [2, 3, 5, 7] {
+            let mut sequence = VanDerCorput::new(base);
+            let n = base.pow(4);
+            let mut cells = vec![0; n as usize];
+            for i in 0..n {
+                let x = sequence.next_1d();
+                assert_eq!(x, van_der_corput(i as u64, base));
+                // The first base^4 points are the multiples of base^-4
+                cells[(x * n as f64).round() as usize] += 1;
+            }
+            assert!(cells.iter().all(|&count| count == 1));
+        }
+        let mut sequence = VanDerCorput::new(2);
+        sequence.set_index(u64::MAX - 1);
+        let mut out = [0.0];
+        assert!(sequence.next_into(&mut out));
+        assert!(out[0] < 1.0);
+        assert!(!sequence.next_into(&mut out));
+    }
+}
@@ -0,0 +1,160 @@
+use super::{Capabilities, UniformSequence};
+use crate::MAX_UNIFORM;
+
+/// The Faure sequence, a digital sequence in a single prime base.
+///
+/// In `d` dimensions the base `b` is the smallest prime at least `d`. The
+/// first coordinate is the radical inverse of the index in base `b`, and
+/// coordinate `j` applies the `j`th power of the Pascal matrix (mod `b`) to
+/// its digits. Every block of `b^m` consecutive points starting at a multiple
+/// of `b^m` is a (0, m, d)-net: each box of volume `b^-m` whose sides are
+/// powers of `1 / b` holds exactly one point.
+///
+/// The sequence starts at index 0, the origin.
+///
+/// ```
+/// use quasirandom::sequences::{Faure, UniformSequence};
+///
+/// let mut faure = Faure::new(3);
+/// assert_eq!(faure.base(), 3);
+/// let mut point = [0.0; 3];
+/// faure.next_into(&mut point);
+/// assert_eq!(point, [0.0; 3]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Faure {
+    dimension: usize,
+    base: u64,
+    /// Binomial coefficients `binomial[k][r]` = C(k, r) mod base
+    binomial: Vec<Vec<u64>>,
+    index: u64,
+}
+
+impl Faure {
+    pub fn new(dimension: usize) -> Self {
+        assert!(dimension > 0);
+        let base = smallest_prime_at_least(dimension.max(2) as u64);
+
+        // Enough digits for any u64 index in this base
+        let digits = (u64::MAX as f64).log(base as f64).ceil() as usize + 1;
+        let mut binomial = vec![vec![0; digits]; digits];
+        for k in 0..digits {
+            binomial[k][0] = 1;
+            for r in 1..=k {
+                binomial[k][r] = (binomial[k - 1][r - 1] + binomial[k - 1][r]) % base;
+            }
+        }
+
+        Self {
+            dimension,
+            base,
+            binomial,
+            index: 0,
+        }
+    }
+
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// The index of the next point to be generated
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Moves to `index`, so that it is the next point generated.
+    pub fn set_index(&mut self, index: u64) {
+        self.index = index;
+    }
+
+    /// Writes point `index` into `out`, which must have length `dimension`.
+    pub fn point(&self, index: u64, out: &mut [f64]) {
+        assert_eq!(out.len(), self.dimension);
+        let b = self.base;
+
+        let mut digits = vec![];
+        let mut n = index;
+        while n > 0 {
+            digits.push(n % b);
+            n /= b;
+        }
+
+        let mut transformed = vec![0; digits.len()];
+        for (j, out) in out.iter_mut().enumerate() {
+            let j = j as u64 % b;
+            // Digit r of coordinate j is sum_k C(k, r) j^(k - r) a_k mod b
+            for (r, y) in transformed.iter_mut().enumerate() {
+                let mut sum = 0;
+                let mut power = 1;
+                for (k, &a) in digits.iter().enumerate().skip(r) {
+                    sum = (sum + self.binomial[k][r] * power % b * a) % b;
+                    power = power * j % b;
+                }
+                *y = sum;
+            }
+            let value = transformed
+                .iter()
+                .rev()
+                .fold(0.0, |value, &y| (value + y as f64) / b as f64);
+            *out = value.min(MAX_UNIFORM);
+        }
+    }
+}
+
+impl UniformSequence for Faure {
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn next_into(&mut self, out: &mut [f64]) -> bool {
+        self.point(self.index, out);
+        self.index += 1;
+        true
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_dimension: None,
+            progressive: true,
+            scrambling: true,
+            random_access: true,
+        }
+    }
+}
+
+fn smallest_prime_at_least(mut n: u64) -> u64 {
+    loop {
+        if (2..).take_while(|p| p * p <= n).all(|p| !n.is_multiple_of(p)) {
+            return n;
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faure_nets() {
+        for &(dimension, base, m) in &[(2, 2, 4), (3, 3, 3), (5, 5, 2)] {
+            let mut faure = Faure::new(dimension);
+            assert_eq!(faure.base(), base);
+            let n = base.pow(m);
+            // Check the (0, m, d)-net property for boxes that split the first
+            // two coordinates into b^m1 and b^(m - m1) intervals
+            for m1 in 0..=m {
+                let (s1, s2) = (base.pow(m1), base.pow(m - m1));
+                let mut cells = vec![0; n as usize];
+                faure.set_index(n);
+                let mut point = vec![0.0; dimension];
+                for _ in 0..n {
+                    faure.next_into(&mut point);
+                    let cell = (point[0] * s1 as f64) as u64 * s2 + (point[dimension - 1] * s2 as f64) as u64;
+                    cells[cell as usize] += 1;
+                }
+                assert!(cells.iter().all(|&count| count == 1), "d {} m1 {}", dimension, m1);
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use super::{Capabilities, UniformSequence};
+use crate::hash::{combine, to_unit};
+
+/// A rank-1 lattice rule: the `n` points `frac(i * z / n + shift)` for a
+/// generating vector `z`.
+///
+/// For smooth periodic integrands, a well-chosen lattice converges much
+/// faster than any sequence. Good generating vectors are available from
+/// `fibonacci` in two dimensions and `korobov` in any dimension. A random
+/// shift makes the rule unbiased, so independent shifts give an error
+/// estimate.
+///
+/// ```
+/// use quasirandom::sequences::{Lattice, UniformSequence};
+///
+/// let mut lattice = Lattice::korobov(1021, 4).with_random_shift(7);
+/// let mut point = [0.0; 4];
+/// let mut sum = 0.0;
+/// while lattice.next_into(&mut point) {
+///     sum += point.iter().map(|x| 1.0 + (2.0 * std::f64::consts::PI * x).sin()).product::<f64>();
+/// }
+/// assert!((sum / 1021.0 - 1.0).abs() < 1e-9);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lattice {
+    count: u64,
+    generator: Vec<u64>,
+    shift: Vec<f64>,
+    index: u64,
+}
+
+impl Lattice {
+    /// The lattice with `count` points and generating vector `generator`,
+    /// whose entries are taken modulo `count`
+    pub fn new(count: u64, generator: Vec<u64>) -> Self {
+        assert!(count > 0);
+        assert!(!generator.is_empty());
+        let shift = vec![0.0; generator.len()];
+        Self {
+            count,
+            generator: generator.iter().map(|z| z % count).collect(),
+            shift,
+            index: 0,
+        }
+    }
+
+    /// The two-dimensional Fibonacci lattice with `F(k)` points and
+    /// generating vector `(1, F(k - 1))`, which is optimal among rank-1
+    /// lattices in two dimensions. `k` must be at least 2.
+    pub fn fibonacci(k: u32) -> Self {
+        assert!(k >= 2);
+        assert!(k <= 92);
+        let (mut previous, mut current) = (1u64, 1u64);
+        for _ in 2..k {
+            (previous, current) = (current, previous + current);
+        }
+        Self::new(current, vec![1, previous])
+    }
+
+    /// The Korobov lattice `z = (1, a, a^2, ...) mod count` with the
+    /// multiplier `a` minimizing the `P_2` criterion, a worst-case error bound
+    /// for integrands with square-integrable mixed derivatives.
+    ///
+    /// The search takes `O(count^2 * dimension)` time, so it is meant for up
+    /// to a few thousand points; a prime `count` gives the best results.
+    pub fn korobov(count: u64, dimension: usize) -> Self {
+        assert!(count > 1);
+        assert!(dimension > 0);
+        let generator = |a: u64| -> Vec<u64> {
+            let mut z = 1;
+            (0..dimension)
+                .map(|_| {
+                    let current = z;
+                    z = (z as u128 * a as u128 % count as u128) as u64;
+                    current
+                })
+                .collect()
+        };
+        let best = (1..=count / 2)
+            .filter(|&a| gcd(a, count) == 1)
+            .map(|a| (p2(count, &generator(a)), a))
+            .min_by(|x, y| x.0.total_cmp(&y.0))
+            .map_or(1, |(_, a)| a);
+        Self::new(count, generator(best))
+    }
+
+    /// Shifts every point by `shift` modulo one.
+    pub fn with_shift(mut self, shift: Vec<f64>) -> Self {
+        assert_eq!(shift.len(), self.generator.len());
+        assert!(shift.iter().all(|x| (0.0..1.0).contains(x)));
+        self.shift = shift;
+        self
+    }
+
+    /// Shifts every point by a pseudorandom vector derived from `seed`.
+    pub fn with_random_shift(self, seed: u64) -> Self {
+        let shift = (0..self.generator.len()).map(|i| to_unit(combine(seed, i as u64))).collect();
+        self.with_shift(shift)
+    }
+
+    /// The number of points in the lattice
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn generator(&self) -> &[u64] {
+        &self.generator
+    }
+
+    /// Writes point `index` (which must be less than `len()`) into `out`.
+    pub fn point(&self, index: u64, out: &mut [f64]) {
+        assert!(index < self.count);
+        assert_eq!(out.len(), self.generator.len());
+        for ((out, &z), shift) in out.iter_mut().zip(&self.generator).zip(&self.shift) {
+            let numerator = (index as u128 * z as u128 % self.count as u128) as f64;
+            let x = numerator / self.count as f64 + shift;
+            *out = if x >= 1.0 { x - 1.0 } else { x };
+        }
+    }
+}
+
+impl UniformSequence for Lattice {
+    fn dimension(&self) -> usize {
+        self.generator.len()
+    }
+
+    fn next_into(&mut self, out: &mut [f64]) -> bool {
+        if self.index >= self.count {
+            return false;
+        }
+        self.point(self.index, out);
+        self.index += 1;
+        true
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_dimension: None,
+            progressive: false,
+            scrambling: false,
+            random_access: true,
+        }
+    }
+}
+
+/// The `P_2` figure of merit of the unshifted lattice
+fn p2(count: u64, generator: &[u64]) -> f64 {
+    let bernoulli = |x: f64| x * x - x + 1.0 / 6.0;
+    let two_pi_squared = 2.0 * std::f64::consts::PI.powi(2);
+    let sum: f64 = (0..count)
+        .map(|k| {
+            generator
+                .iter()
+                .map(|&z| 1.0 + two_pi_squared * bernoulli((k * z % count) as f64 / count as f64))
+                .product::<f64>()
+        })
+        .sum();
+    sum / count as f64 - 1.0
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fibonacci_lattice() {
+        let mut lattice = Lattice::fibonacci(12);
+        assert_eq!(lattice.len(), 144);
+        assert_eq!(lattice.generator(), [1, 89]);
+
+        // Every row and every column of a 144x144 grid holds exactly one point
+        let (mut rows, mut columns) = ([0; 144], [0; 144]);
+        let mut point = [0.0; 2];
+        while lattice.next_into(&mut point) {
+            rows[(point[0] * 144.0).round() as usize % 144] += 1;
+            columns[(point[1] * 144.0).round() as usize % 144] += 1;
+        }
+        assert!(rows.iter().chain(&columns).all(|&count| count == 1));
+    }
+
+    #[test]
+    fn korobov_beats_bad_generators() {
+        let lattice = Lattice::korobov(251, 3);
+        assert_eq!(lattice.generator()[0], 1);
+        let best = p2(251, lattice.generator());
+        assert!(best < p2(251, &[1, 2, 4]));
+        assert!(best < p2(251, &[1, 1, 1]));
+
+        let shifted = lattice.with_random_shift(3);
+        let mut point = [0.0; 3];
+        shifted.point(250, &mut point);
+        assert!(point.iter().all(|x| (0.0..1.0).contains(x)));
+    }
+}
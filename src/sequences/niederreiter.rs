@@ -0,0 +1,259 @@
+use super::{Capabilities, UniformSequence};
+
+/// The number of output bits per coordinate, and the log2 of the number of
+/// points the sequence can produce
+const BITS: usize = 31;
+
+/// The base-2 Niederreiter sequence.
+///
+/// Dimension `i` is built from the `i`th irreducible polynomial over GF(2)
+/// (in the order x, 1 + x, 1 + x + x^2, 1 + x + x^3, ...), following Bratley,
+/// Fox, and Niederreiter, "Implementation and Tests of Low-Discrepancy
+/// Sequences" (1992). The result is a (t, s)-sequence with
+/// `t = sum(degree - 1)` over the polynomials used, which is smaller than
+/// the t-value of Sobol' in several dimensions.
+///
+/// The generator matrices are computed once on construction. Points are
+/// produced in Gray-code order, each by XORing a single matrix column into
+/// the previous point. The sequence starts at the origin and is exhausted
+/// after `2^31` points.
+///
+/// ```
+/// use quasirandom::sequences::{Niederreiter, UniformSequence};
+///
+/// let mut niederreiter = Niederreiter::new(4);
+/// assert_eq!(niederreiter.t_value(), 3);
+/// let mut point = [0.0; 4];
+/// while niederreiter.index() < 1024 {
+///     niederreiter.next_into(&mut point);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Niederreiter {
+    /// `columns[r][i]` packs row `r` of the generator matrix of dimension `i`,
+    /// most significant bit first
+    columns: Vec<Vec<u32>>,
+    state: Vec<u32>,
+    index: u64,
+    t_value: usize,
+}
+
+impl Niederreiter {
+    pub fn new(dimension: usize) -> Self {
+        assert!(dimension > 0);
+        let polynomials = irreducible_polynomials(dimension);
+        let t_value = polynomials.iter().map(|p| p.len() - 2).sum();
+
+        let mut columns = vec![vec![0; dimension]; BITS];
+        for (i, polynomial) in polynomials.iter().enumerate() {
+            for (r, row) in generator_matrix(polynomial).iter().enumerate() {
+                columns[r][i] = row.iter().fold(0, |packed, &bit| 2 * packed + bit as u32);
+            }
+        }
+
+        Self {
+            columns,
+            state: vec![0; dimension],
+            index: 0,
+            t_value,
+        }
+    }
+
+    /// The t-value of the sequence: every block of `2^m` points starting at a
+    /// multiple of `2^m` is a (t, m, s)-net
+    pub fn t_value(&self) -> usize {
+        self.t_value
+    }
+
+    /// The index of the next point to be generated
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Moves to `index`, so that it is the next point generated.
+    pub fn set_index(&mut self, index: u64) {
+        assert!(index <= 1 << BITS);
+        let gray = index ^ (index >> 1);
+        self.state.iter_mut().for_each(|x| *x = 0);
+        for (r, columns) in self.columns.iter().enumerate() {
+            if gray >> r & 1 == 1 {
+                for (x, column) in self.state.iter_mut().zip(columns) {
+                    *x ^= column;
+                }
+            }
+        }
+        self.index = index;
+    }
+}
+
+impl UniformSequence for Niederreiter {
+    fn dimension(&self) -> usize {
+        self.state.len()
+    }
+
+    fn next_into(&mut self, out: &mut [f64]) -> bool {
+        assert_eq!(out.len(), self.state.len());
+        if self.index >= 1 << BITS {
+            return false;
+        }
+        for (out, &x) in out.iter_mut().zip(&self.state) {
+            *out = x as f64 / (1u64 << BITS) as f64;
+        }
+        // Gray-code update: flip the column of the lowest zero bit of the index
+        let r = self.index.trailing_ones() as usize;
+        if r < BITS {
+            for (x, column) in self.state.iter_mut().zip(&self.columns[r]) {
+                *x ^= column;
+            }
+        }
+        self.index += 1;
+        true
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_dimension: None,
+            progressive: true,
+            scrambling: true,
+            random_access: true,
+        }
+    }
+}
+
+/// The first `count` irreducible polynomials over GF(2), ordered by their
+/// value as binary numbers. Each is a list of coefficients, constant first.
+fn irreducible_polynomials(count: usize) -> Vec<Vec<u8>> {
+    let mut found: Vec<u64> = vec![];
+    let mut candidate: u64 = 2;
+    while found.len() < count {
+        let degree = 63 - candidate.leading_zeros();
+        let reducible = found
+            .iter()
+            .take_while(|&&p| 2 * (63 - p.leading_zeros()) <= degree)
+            .any(|&p| gf2_remainder(candidate, p) == 0);
+        if !reducible {
+            found.push(candidate);
+        }
+        candidate += 1;
+    }
+    found
+        .iter()
+        .map(|&p| (0..=63 - p.leading_zeros()).map(|k| (p >> k & 1) as u8).collect())
+        .collect()
+}
+
+/// The remainder of polynomial division over GF(2), polynomials as bit masks
+fn gf2_remainder(mut a: u64, b: u64) -> u64 {
+    let degree = 63 - b.leading_zeros();
+    while a != 0 && 63 - a.leading_zeros() >= degree {
+        a ^= b << (63 - a.leading_zeros() - degree);
+    }
+    a
+}
+
+fn gf2_multiply(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut product = vec![0; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            product[i + j] ^= x & y;
+        }
+    }
+    product
+}
+
+/// The `BITS x BITS` generator matrix for one dimension, as rows of bits
+/// (Bratley, Fox, and Niederreiter's CALCC2)
+fn generator_matrix(polynomial: &[u8]) -> Vec<Vec<u8>> {
+    let degree = polynomial.len() - 1;
+    let mut matrix = vec![vec![0; BITS]; BITS];
+    let mut power = vec![1];
+    let mut v = vec![];
+    let mut u = 0;
+    for j in 0..BITS {
+        if u == 0 {
+            v = next_v(polynomial, &mut power);
+        }
+        for (r, row) in matrix.iter_mut().enumerate() {
+            row[j] = v[r + u];
+        }
+        u = (u + 1) % degree;
+    }
+    matrix
+}
+
+/// Multiplies `power` by `polynomial` and returns the sequence `v` defined in
+/// section 3.3 of the paper, choosing 1 for every free value
+fn next_v(polynomial: &[u8], power: &mut Vec<u8>) -> Vec<u8> {
+    let previous_degree = power.len() - 1;
+    *power = gf2_multiply(polynomial, power);
+    let m = power.len() - 1;
+
+    let mut v = vec![0; BITS + m + 1];
+    v[previous_degree] = 1;
+    for x in &mut v[previous_degree + 1..m] {
+        *x = 1;
+    }
+    // The remaining values follow the linear recurrence with characteristic
+    // polynomial `power`
+    for r in 0..v.len() - m {
+        v[r + m] = (0..m).fold(0, |term, k| term ^ (power[k] & v[r + k]));
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polynomials() {
+        let expected: [u64; 8] = [0b10, 0b11, 0b111, 0b1011, 0b1101, 0b10011, 0b11001, 0b11111];
+        let polynomials = irreducible_polynomials(8);
+        for (polynomial, expected) in polynomials.iter().zip(expected) {
+            let value = polynomial.iter().rev().fold(0, |value, &bit| 2 * value + bit as u64);
+            assert_eq!(value, expected);
+        }
+    }
+
+    /// Checks that the `2^m` points starting at `start` form a (t, m, s)-net
+    /// for every elementary box splitting the first and last dimensions
+    fn assert_net(dimension: usize, m: u32, start: u64) {
+        let mut niederreiter = Niederreiter::new(dimension);
+        let t = niederreiter.t_value() as u32;
+        let n = 1u64 << m;
+        let mut points = vec![vec![0.0; dimension]; n as usize];
+        niederreiter.set_index(start);
+        for point in &mut points {
+            niederreiter.next_into(point);
+        }
+        for m1 in 0..=m - t {
+            let (s1, s2) = (1u64 << m1, 1u64 << (m - t - m1));
+            let mut cells = vec![0; (s1 * s2) as usize];
+            for point in &points {
+                cells[((point[0] * s1 as f64) as u64 * s2 + (point[dimension - 1] * s2 as f64) as u64) as usize] += 1;
+            }
+            assert!(cells.iter().all(|&count| count == 1 << t), "s {} m1 {}", dimension, m1);
+        }
+    }
+
+    #[test]
+    fn nets() {
+        assert_net(2, 8, 0);
+        assert_net(2, 6, 64 * 5);
+        assert_net(3, 8, 0);
+        assert_net(5, 10, 1024);
+    }
+
+    #[test]
+    fn set_index_matches_iteration() {
+        let mut a = Niederreiter::new(6);
+        let mut b = Niederreiter::new(6);
+        let (mut x, mut y) = ([0.0; 6], [0.0; 6]);
+        for i in 0..300 {
+            a.next_into(&mut x);
+            b.set_index(i);
+            b.next_into(&mut y);
+            assert_eq!(x, y);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Warping samples to follow the darkness of an image, for stippling and
+//! halftoning.
+//!
+//! Points from a 2D `Qrng` are pushed through a `Piecewise2D` built from the
+//! image, so dark regions receive proportionally more points while the
+//! placement stays as even as the underlying sequence.
+//!
+//! Images can be loaded from disk with the `image` feature.
+
+use crate::piecewise::Piecewise2D;
+use crate::warp::Warp;
+use crate::Qrng;
+
+/// A 2D density built from an image.
+///
+/// ```
+/// use quasirandom::stipple::DensityImage;
+///
+/// // A 2x1 image: black on the left, white on the right
+/// let image = DensityImage::from_luma(2, 1, &[0, 255]);
+/// let dots = image.stipple(100, 0.5);
+/// assert!(dots.iter().all(|&[x, _]| x < 1.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DensityImage {
+    distribution: Piecewise2D,
+}
+
+impl DensityImage {
+    /// Builds a density from row-major weights with the top row first.
+    /// Weights are relative and do not need to be normalized.
+    pub fn from_densities(width: usize, height: usize, densities: &[f64]) -> Self {
+        Self {
+            distribution: Piecewise2D::new(width, height, densities),
+        }
+    }
+
+    /// Builds a density from row-major 8-bit grayscale pixels with the top row
+    /// first. Darker pixels receive more points, as ink would on paper.
+    pub fn from_luma(width: usize, height: usize, luma: &[u8]) -> Self {
+        let densities: Vec<f64> = luma.iter().map(|&l| 1.0 - l as f64 / 255.0).collect();
+        Self::from_densities(width, height, &densities)
+    }
+
+    /// Like `from_luma`, but lighter pixels receive more points.
+    pub fn from_luma_inverted(width: usize, height: usize, luma: &[u8]) -> Self {
+        let densities: Vec<f64> = luma.iter().map(|&l| l as f64 / 255.0).collect();
+        Self::from_densities(width, height, &densities)
+    }
+
+    /// Converts any image to grayscale and builds a density with `from_luma`.
+    #[cfg(feature = "image")]
+    pub fn from_image(image: &image::DynamicImage) -> Self {
+        let luma = image.to_luma8();
+        Self::from_luma(luma.width() as usize, luma.height() as usize, luma.as_raw())
+    }
+
+    /// Loads an image file and builds a density with `from_luma`.
+    #[cfg(feature = "image")]
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, image::ImageError> {
+        Ok(Self::from_image(&image::open(path)?))
+    }
+
+    pub fn width(&self) -> usize {
+        self.distribution.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.distribution.height()
+    }
+
+    pub fn distribution(&self) -> &Piecewise2D {
+        &self.distribution
+    }
+
+    /// Maps a uniform point in `[0, 1)^2` to a point distributed according to
+    /// the image, in normalized coordinates with `y` pointing down.
+    pub fn map_point(&self, uv: [f64; 2]) -> [f64; 2] {
+        self.distribution.sample_continuous(uv[0], uv[1]).0
+    }
+
+    /// Places `count` dots in pixel coordinates, `x` in `[0, width)` and `y`
+    /// in `[0, height)`.
+    pub fn stipple(&self, count: usize, seed: f64) -> Vec<[f64; 2]> {
+        let mut qrng = Qrng::<(f64, f64)>::new(seed);
+        let (width, height) = (self.width() as f64, self.height() as f64);
+        (0..count)
+            .map(|_| {
+                let (u, v) = qrng.gen();
+                let [x, y] = self.map_point([u, v]);
+                [x * width, y * height]
+            })
+            .collect()
+    }
+}
+
+/// `map_point`, with densities per unit area of the normalized image
+impl Warp for DensityImage {
+    type Output = [f64; 2];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; 2], f64) {
+        Warp::warp(&self.distribution, u)
+    }
+
+    fn pdf(&self, point: &[f64; 2]) -> f64 {
+        Warp::pdf(&self.distribution, point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn darker_pixels_get_more_dots() {
+        // 2x2 image: black, 75% gray, white, 25% gray
+        let image = DensityImage::from_luma(2, 2, &[0, 64, 255, 191]);
+        let dots = image.stipple(10_000, 0.0);
+        let mut counts = [0usize; 4];
+        for [x, y] in dots {
+            counts[y as usize * 2 + x as usize] += 1;
+        }
+        assert!(counts[0] > counts[1]);
+        assert!(counts[1] > counts[3]);
+        assert_eq!(counts[2], 0);
+
+        let (point, pdf) = image.warp(&[0.3, 0.6]);
+        assert_eq!(point, image.map_point([0.3, 0.6]));
+        assert_eq!(pdf, image.pdf(&point));
+    }
+}
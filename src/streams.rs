@@ -0,0 +1,261 @@
+//! Deriving separate generators for the entities of a simulation.
+//!
+//! Deterministic simulations often need one generator per entity, e.g.
+//! world → region → agent. A `StreamKey` names an entity by the path of IDs
+//! leading to it, and the generator derived from a key depends only on that
+//! path. Adding, removing, or reordering siblings therefore never perturbs the
+//! stream of any other entity.
+//!
+//! Each derived generator runs the usual sequence under a toroidal shift
+//! (every dimension is offset by a hash of the key, modulo 1). A shift keeps
+//! the low discrepancy of the sequence. The shifts of different keys are
+//! independent hashes, which is the usual randomized quasi-Monte Carlo setup:
+//! averaged over keys the streams are uncorrelated, but two particular
+//! streams are still the same sequence offset by a constant, so their values
+//! at equal indices are related.
+//!
+//! `Qrng::new_stream` splits one sequence into numbered streams instead,
+//! each a long block of consecutive indices. The blocks never overlap, so
+//! neither do the streams, which can be proven rather than hoped for, and
+//! together the streams still form a single low-discrepancy sequence.
+//!
+//! A `SeedFarm` applies the same idea to experiment matrices, expanding one
+//! master seed into a seed for every run of every configuration.
+
+use crate::hash::{combine, mix, to_unit, GOLDEN_GAMMA};
+use crate::{to_fixed, Qrng, Quasirandom, State};
+
+/// The identity of a stream, built from a root seed and a path of entity IDs.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::streams::StreamKey;
+///
+/// let world = StreamKey::root(42);
+/// let agent = world.child(7).child(1003);
+/// assert_eq!(agent, StreamKey::path(42, &[7, 1003]));
+///
+/// let mut qrng = Qrng::<(f64, f64)>::from_key(agent);
+/// let (x, y) = qrng.gen();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StreamKey(u64);
+
+impl StreamKey {
+    pub fn root(seed: u64) -> Self {
+        StreamKey(mix(seed))
+    }
+
+    /// The key of the entity `id` nested under this one. The result depends
+    /// on the entire path, so `a.child(1).child(2)` differs from
+    /// `a.child(2).child(1)`.
+    pub fn child(self, id: u64) -> Self {
+        StreamKey(combine(self.0, id))
+    }
+
+    /// The key reached from `root(seed)` by following `ids` in order
+    pub fn path(seed: u64, ids: &[u64]) -> Self {
+        ids.iter().fold(Self::root(seed), |key, &id| key.child(id))
+    }
+
+    /// The raw 64-bit hash identifying this key
+    pub fn value(self) -> u64 {
+        self.0
+    }
+
+    /// The toroidal shift of dimension `dimension`, in `[0, 1)`
+    pub fn offset(self, dimension: usize) -> f64 {
+        to_unit(mix(self.0 ^ mix((dimension as u64).wrapping_add(1).wrapping_mul(GOLDEN_GAMMA))))
+    }
+}
+
+impl<const N: usize, T: Quasirandom<State = State<N>>> Qrng<T> {
+    /// A generator whose stream depends only on `key`. See the `streams`
+    /// module for details.
+    pub fn from_key(key: StreamKey) -> Self {
+        Self {
+            state: State::new(std::array::from_fn(|i| to_fixed(key.offset(i)))),
+        }
+    }
+
+    /// A child generator for a sub-simulation: this generator's sequence
+    /// under a toroidal shift hashed from its current state. The parent
+    /// advances by one value, so every fork gets its own shift, and the
+    /// forks of equal parents are equal.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// let mut world = Qrng::<(f64, f64)>::new(0.5);
+    /// let mut agents: Vec<_> = (0..10).map(|_| world.fork()).collect();
+    /// assert_ne!(agents[0].gen(), agents[1].gen());
+    /// ```
+    pub fn fork(&mut self) -> Self {
+        let key = self.state.fixed.iter().fold(StreamKey::root(self.state.index), |key, &x| key.child(x));
+        self.state.gen();
+        Self {
+            state: State::new(std::array::from_fn(|i| self.state.fixed[i].wrapping_add(to_fixed(key.offset(i))))),
+        }
+    }
+}
+
+/// The number of values in each stream of `Qrng::new_stream`, 2^32
+pub const STREAM_LEN: u64 = 1 << 32;
+
+/// The number of streams `Qrng::new_stream` supports, 2^20
+pub const MAX_STREAMS: u64 = 1 << 20;
+
+impl<const N: usize, T: Quasirandom<State = State<N>>> Qrng<T> {
+    /// Stream `stream_id` of the sequence of `Qrng::new(seed)`: that
+    /// sequence from index `stream_id * STREAM_LEN` on, with `index` and
+    /// `sample_at` counting from the start of the stream. Stream 0 is
+    /// `Qrng::new(seed)` itself.
+    ///
+    /// The first `STREAM_LEN` steps of different streams cover disjoint
+    /// ranges of the generator's state, so the streams do not overlap: in
+    /// fixed point, one of the generating constants is a multiple of at most
+    /// 2^12, so the state of a `Qrng` of any dimension takes at least 2^52
+    /// steps to repeat, which covers `MAX_STREAMS` streams. The output values
+    /// are rounded to 53-bit floats, so two streams may still produce equal
+    /// values now and then.
+    ///
+    /// ```
+    /// use quasirandom::Qrng;
+    ///
+    /// let mut streams: Vec<_> = (0..8).map(|id| Qrng::<(f64, f64)>::new_stream(0.5, id)).collect();
+    /// let (x, y) = streams[3].gen();
+    /// assert_eq!((x, y), Qrng::<(f64, f64)>::new(0.5).sample_at(3 << 32));
+    /// ```
+    pub fn new_stream(seed: f64, stream_id: u64) -> Self {
+        assert!(stream_id < MAX_STREAMS, "stream IDs must be below MAX_STREAMS");
+        let start = State::<N>::seeded(seed).seek(stream_id * STREAM_LEN);
+        Self {
+            state: State::new(start.fixed),
+        }
+    }
+}
+
+/// Expands a master seed into the seeds of a runs × configurations matrix.
+///
+/// Within one farm, distinct cells never share a seed: the seed of a cell is
+/// a bijective hash of the master key and the cell's coordinates, so
+/// collisions are impossible rather than merely unlikely. The seeds of
+/// different cells, and of farms with different master seeds, are as
+/// unrelated as independent 64-bit hashes, and adding runs or
+/// configurations never changes the seeds of existing cells.
+///
+/// ```
+/// use quasirandom::streams::SeedFarm;
+/// use quasirandom::Qrng;
+///
+/// let farm = SeedFarm::new(2024);
+/// let seeds = farm.matrix(10, 4);
+/// assert_eq!(seeds[7][2], farm.seed(7, 2));
+///
+/// // Run 7 of configuration 2 gets its own generator
+/// let mut qrng = Qrng::<(f64, f64)>::from_key(farm.key(7, 2));
+/// let (x, y) = qrng.gen();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SeedFarm {
+    key: StreamKey,
+}
+
+impl SeedFarm {
+    pub fn new(master_seed: u64) -> Self {
+        Self {
+            key: StreamKey::root(master_seed),
+        }
+    }
+
+    /// The seed of `run` under `configuration`
+    pub fn seed(&self, run: u32, configuration: u32) -> u64 {
+        mix(self.key.0 ^ ((run as u64) << 32 | configuration as u64))
+    }
+
+    /// The seed of `run` shared by every configuration, for comparing
+    /// configurations with common random numbers. It comes from a separate
+    /// hash of the master key, so it is unrelated to the matrix seeds.
+    pub fn shared_seed(&self, run: u32) -> u64 {
+        mix(self.key.0 ^ GOLDEN_GAMMA) ^ mix(run as u64)
+    }
+
+    /// The stream key of a cell, for `Qrng::from_key`
+    pub fn key(&self, run: u32, configuration: u32) -> StreamKey {
+        StreamKey(self.seed(run, configuration))
+    }
+
+    /// The seeds of the first `runs` runs of the first `configurations`
+    /// configurations, indexed as `[run][configuration]`
+    pub fn matrix(&self, runs: u32, configurations: u32) -> Vec<Vec<u64>> {
+        (0..runs)
+            .map(|run| (0..configurations).map(|configuration| self.seed(run, configuration)).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_depend_only_on_path() {
+        let region = StreamKey::root(1).child(10);
+        assert_ne!(region.child(1).child(2), region.child(2).child(1));
+        assert_ne!(region.child(1), StreamKey::root(1).child(11).child(1));
+
+        let mut a = Qrng::<(f64, f64, f64)>::from_key(region.child(5));
+        let mut b = Qrng::<(f64, f64, f64)>::from_key(StreamKey::path(1, &[10, 5]));
+        let mut sibling = Qrng::<(f64, f64, f64)>::from_key(region.child(6));
+        for _ in 0..100 {
+            let x = a.gen();
+            assert_eq!(x, b.gen());
+            assert_ne!(x, sibling.gen());
+        }
+    }
+
+    #[test]
+    fn numbered_streams_do_not_overlap() {
+        // The premise of the proof: in every dimension count, some generating
+        // constant is a multiple of at most 2^12, so no state repeats within
+        // the streams
+        for n in 1..=crate::MAX_DIMENSION {
+            let twos = (0..n).map(|d| to_fixed(crate::CONSTANTS[n - 1][d]).trailing_zeros()).min().unwrap();
+            assert!(STREAM_LEN * MAX_STREAMS <= 1 << (64 - twos));
+        }
+
+        let mut zero = Qrng::<[f64; 4]>::new_stream(0.3, 0);
+        let mut plain = Qrng::<[f64; 4]>::new(0.3);
+        assert_eq!(zero.gen(), plain.gen());
+        let last = Qrng::<[f64; 4]>::new_stream(0.3, MAX_STREAMS - 1);
+        assert_eq!(last.index(), 0);
+        assert_eq!(last.sample_at(7), plain.sample_at((MAX_STREAMS - 1) * STREAM_LEN + 7));
+    }
+
+    #[test]
+    fn forks_differ_from_parent_and_siblings() {
+        let mut parent = Qrng::<[f64; 2]>::new(0.5);
+        let mut twin = parent.clone();
+        let mut first = parent.fork();
+        let mut second = parent.fork();
+        assert_eq!(parent.index(), 2);
+        assert_eq!(twin.fork().gen(), first.clone().gen());
+        for _ in 0..100 {
+            let [a, b, c] = [parent.gen(), first.gen(), second.gen()];
+            assert!(a != b && b != c && a != c);
+        }
+    }
+
+    #[test]
+    fn farm_seeds_are_distinct() {
+        let farm = SeedFarm::new(7);
+        let matrix = farm.matrix(300, 300);
+        let mut seeds: Vec<u64> = matrix.iter().flatten().copied().collect();
+        seeds.sort_unstable();
+        seeds.dedup();
+        assert_eq!(seeds.len(), 90_000);
+        assert_eq!(farm.matrix(3, 2)[2], matrix[2][..2]);
+        assert_ne!(SeedFarm::new(8).seed(0, 0), farm.seed(0, 0));
+        assert_ne!(farm.shared_seed(0), farm.shared_seed(1));
+    }
+}
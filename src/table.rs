@@ -0,0 +1,103 @@
+//! Point tables computed at compile time.
+//!
+//! Firmware, shaders, and other targets that want baked points rather than
+//! generation code can build a table in a `static` or `const` with
+//! `quasirandom_table!`. The table is a plain array of arrays, evaluated by
+//! the compiler, so nothing of the generator is left in the binary.
+
+use crate::{from_fixed, to_fixed, CONSTANTS, MAX_DIMENSION};
+
+/// Expands to an `[[f64; DIMS]; N]` array holding the first `N` points of a
+/// `Qrng::<[f64; DIMS]>` with the given `SEED` (zero by default), computed at
+/// compile time. With `TYPE = f32` the points are rounded to `f32` instead,
+/// staying below one.
+///
+/// ```
+/// use quasirandom::{quasirandom_table, Qrng};
+///
+/// static POINTS: [[f64; 2]; 4096] = quasirandom_table!(DIMS = 2, N = 4096);
+/// const SEEDED: [[f32; 3]; 64] = quasirandom_table!(DIMS = 3, N = 64, SEED = 0.5, TYPE = f32);
+///
+/// let mut qrng = Qrng::<[f64; 2]>::new(0.0);
+/// assert!(POINTS.iter().all(|&point| point == qrng.gen()));
+/// assert_eq!(SEEDED[0], Qrng::<[f32; 3]>::new(0.5).gen());
+/// ```
+#[macro_export]
+macro_rules! quasirandom_table {
+    (DIMS = $dims:expr, N = $n:expr $(, SEED = $seed:expr)?) => {
+        $crate::quasirandom_table!(DIMS = $dims, N = $n $(, SEED = $seed)?, TYPE = f64)
+    };
+    (DIMS = $dims:expr, N = $n:expr, TYPE = $ty:ident) => {
+        $crate::quasirandom_table!(DIMS = $dims, N = $n, SEED = 0.0, TYPE = $ty)
+    };
+    (DIMS = $dims:expr, N = $n:expr, SEED = $seed:expr, TYPE = f64) => {
+        $crate::table::points::<{ $dims }, { $n }>($seed)
+    };
+    (DIMS = $dims:expr, N = $n:expr, SEED = $seed:expr, TYPE = f32) => {
+        $crate::table::points_f32::<{ $dims }, { $n }>($seed)
+    };
+}
+
+/// The first `N` points of a `Qrng::<[f64; D]>` with the given seed, as a
+/// `const fn`. Usually called through `quasirandom_table!`.
+pub const fn points<const D: usize, const N: usize>(seed: f64) -> [[f64; D]; N] {
+    assert!(D >= 1 && D <= MAX_DIMENSION, "a Qrng supports between 1 and 32 dimensions");
+    assert!(seed >= 0.0 && seed < 1.0);
+    let seed = to_fixed(seed);
+    let mut state = [0; D];
+    let mut d = 0;
+    while d < D {
+        state[d] = seed.wrapping_mul(d as u64);
+        d += 1;
+    }
+
+    let mut points = [[0.0; D]; N];
+    let mut i = 0;
+    while i < N {
+        let mut d = 0;
+        while d < D {
+            state[d] = state[d].wrapping_add(to_fixed(CONSTANTS[D - 1][d]));
+            points[i][d] = from_fixed(state[d]);
+            d += 1;
+        }
+        i += 1;
+    }
+    points
+}
+
+/// Like `points`, with every coordinate rounded to the nearest `f32`, or to
+/// the largest `f32` below one if it would round up to one.
+pub const fn points_f32<const D: usize, const N: usize>(seed: f64) -> [[f32; D]; N] {
+    let wide: [[f64; D]; N] = points(seed);
+    let mut points = [[0.0; D]; N];
+    let mut i = 0;
+    while i < N {
+        let mut d = 0;
+        while d < D {
+            let x = wide[i][d] as f32;
+            points[i][d] = if x < 1.0 { x } else { 1.0 - f32::EPSILON / 2.0 };
+            d += 1;
+        }
+        i += 1;
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Qrng;
+
+    #[test]
+    fn tables_match_the_generator() {
+        static TABLE: [[f64; 5]; 1000] = quasirandom_table!(DIMS = 5, N = 1000, SEED = 0.3);
+        let mut qrng = Qrng::<[f64; 5]>::new(0.3);
+        assert!(TABLE.iter().all(|&point| point == qrng.gen()));
+
+        static ONE: [[f32; 1]; 100_000] = quasirandom_table!(DIMS = 1, N = 100_000, TYPE = f32);
+        let mut qrng = Qrng::<f64>::new(0.0);
+        for &[x] in &ONE {
+            let expected = qrng.gen();
+            assert!(x < 1.0 && (x as f64 - expected).abs() < 1e-7);
+        }
+    }
+}
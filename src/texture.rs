@@ -0,0 +1,142 @@
+//! Rendering tileable dither and noise textures to bytes.
+//!
+//! The textures here are ready to bake into assets: 8-bit grayscale or RGB
+//! pixels, row-major with the top row first and channels interleaved, every
+//! level used equally often in each channel, and seamless when tiled.
+//!
+//! `dither` renders the R2 dither pattern of Martin Roberts, `frac(x a + y
+//! b)` with the 2D generating constants, with `a` and `b` rounded to
+//! multiples of `1 / width` and `1 / height` so that the pattern wraps
+//! exactly. `noise` renders the ranked noise of `noise::NoiseTexture`, which
+//! is less regular.
+
+use crate::hash::{combine, to_unit};
+use crate::noise::NoiseTexture;
+use crate::CONSTANTS;
+
+/// The channels of each pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channels {
+    Gray,
+    Rgb,
+}
+
+impl Channels {
+    /// The number of bytes per pixel
+    pub fn count(self) -> usize {
+        match self {
+            Channels::Gray => 1,
+            Channels::Rgb => 3,
+        }
+    }
+}
+
+/// Renders a tileable R2 dither pattern. With `Channels::Rgb`, the channels
+/// are offset from each other by multiples of the golden ratio, so they cross
+/// each threshold at different pixels.
+///
+/// ```
+/// use quasirandom::texture::{dither, Channels};
+///
+/// // Threshold a 50% gray against the pattern: half the pixels light up
+/// let pattern = dither(64, 64, Channels::Gray);
+/// let lit = pattern.iter().filter(|&&threshold| 128 > threshold).count();
+/// assert_eq!(lit, 2048);
+/// ```
+pub fn dither(width: usize, height: usize, channels: Channels) -> Vec<u8> {
+    assert!(width > 0 && height > 0);
+    let values = lattice_values(width, height);
+    let mut pixels = vec![0; width * height * channels.count()];
+    for channel in 0..channels.count() {
+        let offset = (channel as f64 * CONSTANTS[0][0]).fract();
+        let shifted: Vec<f64> = values.iter().map(|x| (x + offset).fract()).collect();
+        for (pixel, level) in ranked_levels(&shifted).into_iter().enumerate() {
+            pixels[pixel * channels.count() + channel] = level;
+        }
+    }
+    pixels
+}
+
+/// Renders tileable ranked noise, with an independent `NoiseTexture` for each
+/// channel, selected by `seed`.
+///
+/// ```
+/// use quasirandom::texture::{noise, Channels};
+///
+/// let pixels = noise(32, 32, Channels::Rgb, 7);
+/// assert_eq!(pixels.len(), 32 * 32 * 3);
+/// ```
+pub fn noise(width: usize, height: usize, channels: Channels, seed: u64) -> Vec<u8> {
+    assert!(width > 0 && height > 0);
+    let mut pixels = vec![0; width * height * channels.count()];
+    for channel in 0..channels.count() {
+        let texture = NoiseTexture::ranked_2d(width, height, to_unit(combine(seed, channel as u64)));
+        for (pixel, level) in texture.to_u8().into_iter().enumerate() {
+            pixels[pixel * channels.count() + channel] = level;
+        }
+    }
+    pixels
+}
+
+/// `frac(x p / width + y q / height)` for every pixel, with `p / width` and
+/// `q / height` the nearest fractions to the 2D generating constants
+fn lattice_values(width: usize, height: usize) -> Vec<f64> {
+    let [a, b] = [CONSTANTS[1][0], CONSTANTS[1][1]];
+    let p = (a * width as f64).round() as u64;
+    let q = (b * height as f64).round() as u64;
+    let (w, h) = (width as u64, height as u64);
+    let mut values = Vec::with_capacity(width * height);
+    for y in 0..h {
+        for x in 0..w {
+            let numerator = (x * p % w * h + y * q % h * w) % (w * h);
+            values.push(numerator as f64 / (w * h) as f64);
+        }
+    }
+    values
+}
+
+/// Replaces every value by its rank, scaled to 8 bits, breaking ties by
+/// position
+fn ranked_levels(values: &[f64]) -> Vec<u8> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&i, &j| values[i].total_cmp(&values[j]).then(i.cmp(&j)));
+    let mut levels = vec![0; values.len()];
+    for (rank, &i) in order.iter().enumerate() {
+        levels[i] = (rank * 256 / values.len()) as u8;
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn textures_tile_and_are_balanced() {
+        // The pattern steps by the same amount across the wrap as inside
+        let (width, height) = (48, 32);
+        let values = lattice_values(width, height);
+        let step_x = (values[1] - values[0]).rem_euclid(1.0);
+        let step_y = (values[width] - values[0]).rem_euclid(1.0);
+        for y in 0..height {
+            for x in 0..width {
+                let here = values[y * width + x];
+                let right = values[y * width + (x + 1) % width];
+                let below = values[(y + 1) % height * width + x];
+                assert!(((right - here).rem_euclid(1.0) - step_x).abs() < 1e-12);
+                assert!(((below - here).rem_euclid(1.0) - step_y).abs() < 1e-12);
+            }
+        }
+
+        for pixels in [dither(width, height, Channels::Rgb), noise(width, height, Channels::Rgb, 1)] {
+            assert_eq!(pixels.len(), width * height * 3);
+            for channel in 0..3 {
+                let mut histogram = [0; 256];
+                pixels.iter().skip(channel).step_by(3).for_each(|&level| histogram[level as usize] += 1);
+                assert!(histogram.iter().all(|&count| count == 6));
+            }
+            // The channels are not copies of each other
+            assert!(pixels.chunks(3).filter(|rgb| rgb[0] == rgb[1]).count() < 100);
+        }
+    }
+}
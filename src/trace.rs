@@ -0,0 +1,208 @@
+//! Recording tagged samples to debug renderers.
+//!
+//! Sample reuse and aliasing bugs (two bounces reading the same dimensions,
+//! a light choice correlated with a BSDF sample, every pixel seeing the same
+//! pattern) are hard to see in an image. A `TracedSampler` records every
+//! value it hands out along with the pixel, sample, bounce, and purpose it
+//! was requested for, and the resulting `SampleLog` can be searched for
+//! reused values or dumped as a table for offline plotting.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::sampler::PixelSampler;
+
+/// Where a sample was used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SampleTag {
+    pub pixel: [u32; 2],
+    pub sample: u32,
+    pub bounce: u32,
+    /// What the values were for, e.g. `"light"` or `"bsdf"`
+    pub purpose: &'static str,
+}
+
+/// Tagged values in the order they were recorded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SampleLog {
+    records: Vec<(SampleTag, Vec<f64>)>,
+}
+
+impl SampleLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, tag: SampleTag, values: &[f64]) {
+        self.records.push((tag, values.to_vec()));
+    }
+
+    pub fn records(&self) -> &[(SampleTag, Vec<f64>)] {
+        &self.records
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Pairs of record indices whose values are bit-for-bit identical. Each
+    /// later record is paired with the first record it repeats. Outside of
+    /// deliberate replays, a repeat means two uses are reading the same
+    /// sample.
+    pub fn reused(&self) -> Vec<(usize, usize)> {
+        let mut first = HashMap::new();
+        let mut pairs = vec![];
+        for (i, (_, values)) in self.records.iter().enumerate() {
+            let bits: Vec<u64> = values.iter().map(|x| x.to_bits()).collect();
+            match first.get(&bits) {
+                Some(&j) => pairs.push((j, i)),
+                None => {
+                    first.insert(bits, i);
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Writes the log as tab-separated text with a header line, one record
+    /// per line: `x`, `y`, `sample`, `bounce`, `purpose`, then the values.
+    /// Values are written with full precision.
+    pub fn write_to(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "x\ty\tsample\tbounce\tpurpose\tvalues")?;
+        for (tag, values) in &self.records {
+            let [x, y] = tag.pixel;
+            write!(out, "{}\t{}\t{}\t{}\t{}", x, y, tag.sample, tag.bounce, tag.purpose)?;
+            for value in values {
+                write!(out, "\t{:?}", value)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// A `PixelSampler` that records every value it produces in a `SampleLog`.
+///
+/// ```
+/// use quasirandom::sampler::PixelSampler;
+/// use quasirandom::trace::TracedSampler;
+///
+/// let mut sampler = TracedSampler::new(PixelSampler::new(4, 0));
+/// sampler.start_pixel(1, 2);
+/// loop {
+///     let film = sampler.get_2d(0, "film");
+///     let light = sampler.get_1d(0, "light");
+///     let bsdf = sampler.get_2d(1, "bsdf");
+///     if !sampler.start_next_sample() {
+///         break;
+///     }
+/// }
+/// let log = sampler.into_log();
+/// assert_eq!(log.len(), 12);
+/// assert!(log.reused().is_empty());
+///
+/// let mut dump = vec![];
+/// log.write_to(&mut dump).unwrap();
+/// assert!(String::from_utf8(dump).unwrap().lines().nth(1).unwrap().starts_with("1\t2\t0\t0\tfilm\t"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TracedSampler {
+    sampler: PixelSampler,
+    pixel: [u32; 2],
+    log: SampleLog,
+}
+
+impl TracedSampler {
+    pub fn new(sampler: PixelSampler) -> Self {
+        Self {
+            sampler,
+            pixel: [0, 0],
+            log: SampleLog::new(),
+        }
+    }
+
+    pub fn start_pixel(&mut self, x: u32, y: u32) {
+        self.sampler.start_pixel(x, y);
+        self.pixel = [x, y];
+    }
+
+    pub fn start_next_sample(&mut self) -> bool {
+        self.sampler.start_next_sample()
+    }
+
+    pub fn set_sample_index(&mut self, index: u32) {
+        self.sampler.set_sample_index(index);
+    }
+
+    pub fn get_1d(&mut self, bounce: u32, purpose: &'static str) -> f64 {
+        let tag = self.tag(bounce, purpose);
+        let value = self.sampler.get_1d();
+        self.log.record(tag, &[value]);
+        value
+    }
+
+    pub fn get_2d(&mut self, bounce: u32, purpose: &'static str) -> [f64; 2] {
+        let tag = self.tag(bounce, purpose);
+        let values = self.sampler.get_2d();
+        self.log.record(tag, &values);
+        values
+    }
+
+    pub fn log(&self) -> &SampleLog {
+        &self.log
+    }
+
+    pub fn into_log(self) -> SampleLog {
+        self.log
+    }
+
+    fn tag(&self, bounce: u32, purpose: &'static str) -> SampleTag {
+        SampleTag {
+            pixel: self.pixel,
+            sample: self.sampler.sample_index(),
+            bounce,
+            purpose,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse_is_found() {
+        let mut sampler = TracedSampler::new(PixelSampler::new(8, 1));
+        for x in 0..2 {
+            sampler.start_pixel(x, 0);
+            loop {
+                sampler.get_2d(0, "camera");
+                sampler.get_1d(1, "light");
+                if !sampler.start_next_sample() {
+                    break;
+                }
+            }
+        }
+        assert_eq!(sampler.log().len(), 32);
+        assert!(sampler.log().reused().is_empty());
+
+        // A bug: replaying sample 3 of the last pixel for the next bounce
+        sampler.set_sample_index(3);
+        sampler.get_2d(2, "bsdf");
+        let log = sampler.into_log();
+        assert_eq!(log.reused(), [(22, 32)]);
+        assert_eq!(log.records()[22].0.purpose, "camera");
+
+        let mut dump = vec![];
+        log.write_to(&mut dump).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+        assert_eq!(dump.lines().count(), 34);
+        let last: Vec<&str> = dump.lines().last().unwrap().split('\t').collect();
+        assert_eq!(&last[..5], ["1", "0", "3", "2", "bsdf"]);
+        assert_eq!(last[5].parse::<f64>().unwrap(), log.records()[32].1[0]);
+    }
+}
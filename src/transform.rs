@@ -0,0 +1,158 @@
+//! Per-coordinate transformations of a generator, for quick scripts.
+//!
+//! Mapping each coordinate of a `Qrng<(f64, f64, f64)>` to its own range
+//! usually means a custom `FromUniform` type or a wrapper function.
+//! `Qrng::transform` registers a function for one position of the tuple
+//! instead, and returns a generator of the same tuple type whose values have
+//! gone through the registered functions.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::{Qrng, Quasirandom, State};
+
+/// Values made of `N` coordinates of type `f64`: arrays, and tuples of up to
+/// twelve elements.
+pub trait FromCoordinates<const N: usize> {
+    fn from_coordinates(coordinates: [f64; N]) -> Self;
+}
+
+impl<const N: usize> FromCoordinates<N> for [f64; N] {
+    fn from_coordinates(coordinates: [f64; N]) -> Self {
+        coordinates
+    }
+}
+
+macro_rules! tuple_coordinates {
+    ($($n:literal: ($($x:ident)*))*) => {
+        $(
+        impl FromCoordinates<$n> for ($(tuple_coordinates!(@f64 $x),)*) {
+            fn from_coordinates([$($x,)*]: [f64; $n]) -> Self {
+                ($($x,)*)
+            }
+        }
+        )*
+    };
+    (@f64 $x:ident) => { f64 };
+}
+
+tuple_coordinates! {
+    1: (a)
+    2: (a b)
+    3: (a b c)
+    4: (a b c d)
+    5: (a b c d e)
+    6: (a b c d e f)
+    7: (a b c d e f g)
+    8: (a b c d e f g h)
+    9: (a b c d e f g h i)
+    10: (a b c d e f g h i j)
+    11: (a b c d e f g h i j k)
+    12: (a b c d e f g h i j k l)
+}
+
+/// A function registered for one coordinate
+type Transform = Box<dyn Fn(f64) -> f64>;
+
+/// A `Qrng` whose coordinates go through registered functions, made by
+/// `Qrng::transform`.
+///
+/// ```
+/// use std::f64::consts::PI;
+/// use quasirandom::Qrng;
+///
+/// let mut qrng = Qrng::<(f64, f64, f64)>::new(0.5)
+///     .transform(0, |x| 2.0 * PI * x - PI)
+///     .scale(1, 0.0, 10.0);
+/// let (angle, distance, u) = qrng.gen();
+/// assert!((-PI..PI).contains(&angle));
+/// assert!((0.0..10.0).contains(&distance));
+/// assert!((0.0..1.0).contains(&u));
+/// ```
+pub struct Transformed<T, const N: usize> {
+    state: State<N>,
+    transforms: [Vec<Transform>; N],
+    output: PhantomData<fn() -> T>,
+}
+
+impl<const N: usize, T: Quasirandom<State = State<N>> + FromCoordinates<N>> Qrng<T> {
+    /// A generator that passes coordinate `position` through `f`, leaving
+    /// the others as they are. It continues from the current state.
+    pub fn transform(self, position: usize, f: impl Fn(f64) -> f64 + 'static) -> Transformed<T, N> {
+        let transformed = Transformed {
+            state: self.state,
+            transforms: std::array::from_fn(|_| Vec::new()),
+            output: PhantomData,
+        };
+        transformed.transform(position, f)
+    }
+
+    /// A generator that maps coordinate `position` linearly onto
+    /// `[low, high)`.
+    pub fn scale(self, position: usize, low: f64, high: f64) -> Transformed<T, N> {
+        self.transform(position, move |x| low + (high - low) * x)
+    }
+}
+
+impl<const N: usize, T: FromCoordinates<N>> Transformed<T, N> {
+    /// Adds `f` after the functions already registered for coordinate
+    /// `position`.
+    pub fn transform(mut self, position: usize, f: impl Fn(f64) -> f64 + 'static) -> Self {
+        assert!(position < N, "position {} is out of range for {} coordinates", position, N);
+        self.transforms[position].push(Box::new(f));
+        self
+    }
+
+    /// Adds a linear map of coordinate `position` from `[0, 1)` onto
+    /// `[low, high)`.
+    pub fn scale(self, position: usize, low: f64, high: f64) -> Self {
+        self.transform(position, move |x| low + (high - low) * x)
+    }
+
+    pub fn gen(&mut self) -> T {
+        let mut coordinates = self.state.gen();
+        for (x, transforms) in coordinates.iter_mut().zip(&self.transforms) {
+            *x = transforms.iter().fold(*x, |x, f| f(x));
+        }
+        T::from_coordinates(coordinates)
+    }
+
+    /// The generator without its transformations, at the same state
+    pub fn into_inner(self) -> Qrng<T>
+    where
+        T: Quasirandom<State = State<N>>,
+    {
+        Qrng { state: self.state }
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for Transformed<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = self.transforms.each_ref().map(Vec::len);
+        f.debug_struct("Transformed")
+            .field("state", &self.state)
+            .field("transforms", &counts)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transforms_apply_per_position_in_order() {
+        let mut plain = Qrng::<[f64; 3]>::new(0.25);
+        let mut transformed = plain
+            .clone()
+            .transform(2, |x| x * x)
+            .scale(0, -1.0, 1.0)
+            .transform(2, |x| x + 5.0);
+        for _ in 0..100 {
+            let [a, b, c] = plain.gen();
+            assert_eq!(transformed.gen(), [2.0 * a - 1.0, b, c * c + 5.0]);
+        }
+        let mut inner = transformed.into_inner();
+        assert_eq!(inner.gen(), plain.gen());
+    }
+}
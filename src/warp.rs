@@ -0,0 +1,723 @@
+//! Maps from the unit square (or cube) onto other domains.
+//!
+//! Each warp takes uniformly distributed points to points distributed on its
+//! domain either uniformly or, for importance sampling, with a density it
+//! returns alongside. They are continuous and distort areas as little as
+//! practical, so evenly spread quasirandom input stays evenly spread.
+//!
+//! The functions return bare points where the density is constant. The
+//! `Warp` trait pairs every warp with its density, for estimators that
+//! divide by it: `warp` returns both together, and `pdf` evaluates the
+//! density of a point sampled some other way, as multiple importance
+//! sampling needs. Besides the warps here, it is implemented by the
+//! piecewise-constant distributions of `piecewise`, `EnvironmentDistribution`,
+//! `stipple::DensityImage` and `distributions::VonMisesFisher`.
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+/// Maps `[u, v]` uniformly onto the standard triangle, returning barycentric
+/// coordinates.
+///
+/// Uses the low-distortion map of Heitz, "A Low-Distortion Map Between
+/// Triangle and Square" (2019), which keeps neighboring points close and
+/// preserves stratification better than the usual square-root map.
+pub fn triangle_barycentric([u, v]: [f64; 2]) -> [f64; 3] {
+    let (b0, b1) = if u < v {
+        let b0 = u / 2.0;
+        (b0, v - b0)
+    } else {
+        let b1 = v / 2.0;
+        (u - b1, b1)
+    };
+    [b0, b1, 1.0 - b0 - b1]
+}
+
+/// Maps `[u, v]` uniformly onto the triangle with the given vertices, in any
+/// number of dimensions. The density of the result is one over the
+/// triangle's area.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::warp::to_triangle;
+///
+/// let mut qrng = Qrng::<[f64; 2]>::new(0.5);
+/// let light = [[0.0, 2.0, 0.0], [1.0, 2.0, 0.0], [0.0, 2.0, 1.0]];
+/// for _ in 0..16 {
+///     let [x, y, z] = to_triangle(qrng.gen(), &light);
+///     assert!(y == 2.0 && x + z <= 1.0);
+/// }
+/// ```
+pub fn to_triangle<const N: usize>(u: [f64; 2], vertices: &[[f64; N]; 3]) -> [f64; N] {
+    combine(&triangle_barycentric(u), vertices)
+}
+
+/// Maps a point of `[0, 1)^k` uniformly onto the standard `k`-simplex,
+/// writing its `k + 1` barycentric coordinates into `out`.
+///
+/// This generalizes the square-root map for triangles: each coordinate is
+/// inverted from its marginal given the ones before it, so the map is
+/// continuous and measure preserving.
+pub fn simplex_barycentric(u: &[f64], out: &mut [f64]) {
+    let k = u.len();
+    assert_eq!(out.len(), k + 1);
+    let mut remaining = 1.0;
+    for (i, (&u, out)) in u.iter().zip(out.iter_mut()).enumerate() {
+        // The first barycentric coordinate of a uniform point on a simplex of
+        // dimension k - i has density proportional to (1 - x)^(k - i - 1)
+        let t = 1.0 - u.powf(1.0 / (k - i) as f64);
+        *out = remaining * t;
+        remaining *= 1.0 - t;
+    }
+    out[k] = remaining;
+}
+
+/// Maps a point of `[0, 1)^k` uniformly onto the simplex spanned by
+/// `k + 1` vertices in `N` dimensions.
+pub fn to_simplex<const N: usize>(u: &[f64], vertices: &[[f64; N]]) -> [f64; N] {
+    assert_eq!(vertices.len(), u.len() + 1);
+    let mut barycentric = vec![0.0; vertices.len()];
+    simplex_barycentric(u, &mut barycentric);
+    combine(&barycentric, vertices)
+}
+
+/// Maps `[u, v]` uniformly onto the unit disk with the concentric map of
+/// Shirley and Chiu, "A Low Distortion Map Between Disk and Square" (1997).
+///
+/// Concentric squares go to concentric circles, so the map keeps the even
+/// spacing of 2D quasirandom points far better than taking `sqrt(u)` as the
+/// radius and `v` as the angle.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::warp::to_unit_disk;
+///
+/// let mut qrng = Qrng::<[f64; 2]>::new(0.5);
+/// let [x, y] = to_unit_disk(qrng.gen());
+/// assert!(x.hypot(y) <= 1.0);
+/// ```
+pub fn to_unit_disk([u, v]: [f64; 2]) -> [f64; 2] {
+    let (a, b) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return [0.0, 0.0];
+    }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, FRAC_PI_4 * (b / a))
+    } else {
+        (b, FRAC_PI_2 - FRAC_PI_4 * (a / b))
+    };
+    [r * theta.cos(), r * theta.sin()]
+}
+
+/// Maps `[u, v]` uniformly onto the annulus between radii `inner` and
+/// `outer`, by applying the concentric map and then remapping the radius so
+/// that areas are preserved.
+pub fn to_annulus(u: [f64; 2], inner: f64, outer: f64) -> [f64; 2] {
+    assert!(inner >= 0.0);
+    assert!(inner <= outer);
+    let [x, y] = to_unit_disk(u);
+    let r = x.hypot(y);
+    if r == 0.0 {
+        return [inner, 0.0];
+    }
+    let scale = (inner * inner + r * r * (outer * outer - inner * inner)).sqrt() / r;
+    [x * scale, y * scale]
+}
+
+/// Maps `[u, v]` uniformly onto the unit sphere, with `u` setting the height
+/// and `v` the azimuth (Archimedes' hat-box map). The density is `1 / (4 pi)`
+/// per steradian.
+///
+/// The map is area preserving, so extending a quasirandom sequence refines
+/// the directions incrementally. For a fixed budget of directions, the rules
+/// in `cubature` integrate smooth functions more accurately.
+pub fn to_unit_sphere([u, v]: [f64; 2]) -> [f64; 3] {
+    let z = 1.0 - 2.0 * u;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    [r * phi.cos(), r * phi.sin(), z]
+}
+
+/// Maps `[u, v]` onto the hemisphere around `+z` with density proportional
+/// to the cosine of the angle from `+z`, by lifting the concentric disk map
+/// (Malley's method). Returns the direction and its density per steradian.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::warp::cosine_hemisphere;
+///
+/// let mut qrng = Qrng::<[f64; 2]>::new(0.5);
+/// let ([x, y, z], pdf) = cosine_hemisphere(qrng.gen());
+/// assert!((x * x + y * y + z * z - 1.0).abs() < 1e-12);
+/// assert!((pdf - z / std::f64::consts::PI).abs() < 1e-12);
+/// ```
+pub fn cosine_hemisphere(u: [f64; 2]) -> ([f64; 3], f64) {
+    let [x, y] = to_unit_disk(u);
+    let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+    ([x, y, z], z / PI)
+}
+
+/// Samples a microfacet normal around `+z` from the GGX (Trowbridge-Reitz)
+/// distribution with roughness `alpha`, proportionally to `D(h) cos(theta)`.
+/// Returns the half vector and its density per steradian.
+pub fn ggx_half_vector([u, v]: [f64; 2], alpha: f64) -> ([f64; 3], f64) {
+    assert!(alpha > 0.0);
+    let tan2 = alpha * alpha * u / (1.0 - u);
+    let cos2 = 1.0 / (1.0 + tan2);
+    (spherical(cos2, v), ggx_pdf(cos2, alpha))
+}
+
+/// `D(h) cos(theta)` for GGX, from the squared cosine of `h` with `+z`
+fn ggx_pdf(cos2: f64, alpha: f64) -> f64 {
+    let tan2 = (1.0 - cos2) / cos2;
+    let d = alpha * alpha / (PI * cos2 * cos2 * (alpha * alpha + tan2).powi(2));
+    d * cos2.sqrt()
+}
+
+/// Samples a microfacet normal around `+z` from the Beckmann distribution
+/// with roughness `alpha`, proportionally to `D(h) cos(theta)`. Returns the
+/// half vector and its density per steradian.
+pub fn beckmann_half_vector([u, v]: [f64; 2], alpha: f64) -> ([f64; 3], f64) {
+    assert!(alpha > 0.0);
+    let tan2 = -alpha * alpha * (1.0 - u).ln();
+    let cos2 = 1.0 / (1.0 + tan2);
+    (spherical(cos2, v), beckmann_pdf(cos2, alpha))
+}
+
+/// `D(h) cos(theta)` for Beckmann, from the squared cosine of `h` with `+z`
+fn beckmann_pdf(cos2: f64, alpha: f64) -> f64 {
+    let tan2 = (1.0 - cos2) / cos2;
+    let d = (-tan2 / (alpha * alpha)).exp() / (PI * alpha * alpha * cos2 * cos2);
+    d * cos2.sqrt()
+}
+
+/// The unit vector with squared cosine `cos2` from `+z` at azimuth `2 pi v`
+fn spherical(cos2: f64, v: f64) -> [f64; 3] {
+    let sin = (1.0 - cos2).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    [sin * phi.cos(), sin * phi.sin(), cos2.sqrt()]
+}
+
+/// A warp paired with the density of its results.
+///
+/// ```
+/// use quasirandom::Qrng;
+/// use quasirandom::warp::{Ggx, Warp};
+///
+/// // Estimate the integral of cos(theta) over the hemisphere, pi
+/// let ggx = Ggx { alpha: 0.3 };
+/// let mut qrng = Qrng::<[f64; 2]>::new(0.5);
+/// let n = 10_000;
+/// let estimate = (0..n)
+///     .map(|_| {
+///         let (h, pdf) = ggx.warp(&qrng.gen());
+///         h[2] / pdf
+///     })
+///     .sum::<f64>()
+///     / n as f64;
+/// assert!((estimate - std::f64::consts::PI).abs() < 0.01);
+/// ```
+pub trait Warp {
+    type Output;
+
+    /// The number of uniforms each call to `warp` consumes
+    fn dimension(&self) -> usize;
+
+    /// Maps the first `dimension()` values of `u` to a point and its density
+    fn warp(&self, u: &[f64]) -> (Self::Output, f64);
+
+    /// The density of `point`, zero outside the domain. Points within a
+    /// small tolerance of a lower-dimensional domain, like a triangle in
+    /// space or the sphere of directions, count as on it.
+    fn pdf(&self, point: &Self::Output) -> f64;
+}
+
+/// `to_unit_disk`, with density `1 / pi` per unit area
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UnitDisk;
+
+impl Warp for UnitDisk {
+    type Output = [f64; 2];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; 2], f64) {
+        let point = to_unit_disk([u[0], u[1]]);
+        (point, 1.0 / PI)
+    }
+
+    fn pdf(&self, [x, y]: &[f64; 2]) -> f64 {
+        if x.hypot(*y) <= 1.0 { 1.0 / PI } else { 0.0 }
+    }
+}
+
+/// `to_annulus`, with density one over the annulus' area
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Annulus {
+    inner: f64,
+    outer: f64,
+}
+
+impl Annulus {
+    /// The annulus between radii `inner` and `outer`, which must enclose a
+    /// positive area
+    pub fn new(inner: f64, outer: f64) -> Self {
+        assert!(inner >= 0.0);
+        assert!(inner < outer, "the annulus must have a positive area");
+        Self { inner, outer }
+    }
+
+    pub fn inner(&self) -> f64 {
+        self.inner
+    }
+
+    pub fn outer(&self) -> f64 {
+        self.outer
+    }
+}
+
+impl Warp for Annulus {
+    type Output = [f64; 2];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; 2], f64) {
+        let point = to_annulus([u[0], u[1]], self.inner, self.outer);
+        (point, self.pdf(&point))
+    }
+
+    fn pdf(&self, [x, y]: &[f64; 2]) -> f64 {
+        let r = x.hypot(*y);
+        if (self.inner..=self.outer).contains(&r) {
+            1.0 / (PI * (self.outer * self.outer - self.inner * self.inner))
+        } else {
+            0.0
+        }
+    }
+}
+
+/// `to_triangle`, with density one over the triangle's area
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle<const N: usize> {
+    pub vertices: [[f64; N]; 3],
+}
+
+impl<const N: usize> Warp for Triangle<N> {
+    type Output = [f64; N];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; N], f64) {
+        let point = to_triangle([u[0], u[1]], &self.vertices);
+        (point, self.pdf(&point))
+    }
+
+    fn pdf(&self, point: &[f64; N]) -> f64 {
+        if simplex_contains(&self.vertices, point) { 1.0 / simplex_volume(&self.vertices) } else { 0.0 }
+    }
+}
+
+/// `to_simplex`, with density one over the simplex' volume
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simplex<const N: usize> {
+    vertices: Vec<[f64; N]>,
+}
+
+impl<const N: usize> Simplex<N> {
+    /// The simplex spanned by `vertices`, which must not be degenerate: a
+    /// single vertex, or affinely independent ones enclosing a positive
+    /// volume
+    pub fn new(vertices: Vec<[f64; N]>) -> Self {
+        assert!(!vertices.is_empty());
+        assert!(simplex_volume(&vertices) > 0.0, "the simplex must not be degenerate");
+        Self { vertices }
+    }
+
+    pub fn vertices(&self) -> &[[f64; N]] {
+        &self.vertices
+    }
+}
+
+impl<const N: usize> Warp for Simplex<N> {
+    type Output = [f64; N];
+
+    fn dimension(&self) -> usize {
+        self.vertices.len() - 1
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; N], f64) {
+        let point = to_simplex(&u[..self.dimension()], &self.vertices);
+        (point, self.pdf(&point))
+    }
+
+    fn pdf(&self, point: &[f64; N]) -> f64 {
+        if simplex_contains(&self.vertices, point) { 1.0 / simplex_volume(&self.vertices) } else { 0.0 }
+    }
+}
+
+/// `to_unit_sphere`, with density `1 / (4 pi)` per steradian
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UnitSphere;
+
+impl Warp for UnitSphere {
+    type Output = [f64; 3];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; 3], f64) {
+        (to_unit_sphere([u[0], u[1]]), 1.0 / (4.0 * PI))
+    }
+
+    fn pdf(&self, direction: &[f64; 3]) -> f64 {
+        if is_unit(direction) { 1.0 / (4.0 * PI) } else { 0.0 }
+    }
+}
+
+/// `cosine_hemisphere`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CosineHemisphere;
+
+impl Warp for CosineHemisphere {
+    type Output = [f64; 3];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; 3], f64) {
+        cosine_hemisphere([u[0], u[1]])
+    }
+
+    fn pdf(&self, direction: &[f64; 3]) -> f64 {
+        if is_unit(direction) { direction[2].max(0.0) / PI } else { 0.0 }
+    }
+}
+
+/// `ggx_half_vector` with roughness `alpha`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ggx {
+    pub alpha: f64,
+}
+
+impl Warp for Ggx {
+    type Output = [f64; 3];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; 3], f64) {
+        ggx_half_vector([u[0], u[1]], self.alpha)
+    }
+
+    fn pdf(&self, half_vector: &[f64; 3]) -> f64 {
+        if half_vector[2] > 0.0 && is_unit(half_vector) { ggx_pdf(half_vector[2] * half_vector[2], self.alpha) } else { 0.0 }
+    }
+}
+
+/// `beckmann_half_vector` with roughness `alpha`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beckmann {
+    pub alpha: f64,
+}
+
+impl Warp for Beckmann {
+    type Output = [f64; 3];
+
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    fn warp(&self, u: &[f64]) -> ([f64; 3], f64) {
+        beckmann_half_vector([u[0], u[1]], self.alpha)
+    }
+
+    fn pdf(&self, half_vector: &[f64; 3]) -> f64 {
+        if half_vector[2] > 0.0 && is_unit(half_vector) { beckmann_pdf(half_vector[2] * half_vector[2], self.alpha) } else { 0.0 }
+    }
+}
+
+/// The `k`-dimensional volume of the simplex spanned by `k + 1` vertices in
+/// `N` dimensions: the square root of the Gram determinant of its edges,
+/// over `k!`
+fn simplex_volume<const N: usize>(vertices: &[[f64; N]]) -> f64 {
+    let k = vertices.len() - 1;
+    let (_, determinant) = solve_gram(vertices, &vertices[0]);
+    determinant.sqrt() / (1..=k).map(|i| i as f64).product::<f64>()
+}
+
+/// Whether `point` lies on the simplex spanned by `vertices`: in their
+/// affine hull, with nonnegative barycentric coordinates, up to a tolerance
+/// relative to the simplex' size
+fn simplex_contains<const N: usize>(vertices: &[[f64; N]], point: &[f64; N]) -> bool {
+    let (coordinates, determinant) = solve_gram(vertices, point);
+    if determinant <= 0.0 {
+        return false;
+    }
+    const TOLERANCE: f64 = 1e-9;
+    let first = 1.0 - coordinates.iter().sum::<f64>();
+    if first < -TOLERANCE || coordinates.iter().any(|&c| c < -TOLERANCE) {
+        return false;
+    }
+    // The closest point of the affine hull must be the point itself
+    let mut weights = vec![first];
+    weights.extend(coordinates);
+    let closest = combine(&weights, vertices);
+    let size = vertices.iter().flatten().fold(0.0, |size: f64, x| size.max(x.abs())).max(1.0);
+    let distance = closest.iter().zip(point).map(|(a, b)| (a - b) * (a - b)).sum::<f64>().sqrt();
+    distance <= TOLERANCE * size
+}
+
+/// The coordinates along the edges from the first vertex of the point of the
+/// affine hull of `vertices` closest to `point`, by solving the normal
+/// equations, and the Gram determinant of the edges, zero when the simplex is
+/// degenerate
+fn solve_gram<const N: usize>(vertices: &[[f64; N]], point: &[f64; N]) -> (Vec<f64>, f64) {
+    let k = vertices.len() - 1;
+    let dot = |a: &[f64; N], b: &[f64; N]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+    let edges: Vec<[f64; N]> = vertices[1..].iter().map(|v| std::array::from_fn(|i| v[i] - vertices[0][i])).collect();
+    let offset: [f64; N] = std::array::from_fn(|i| point[i] - vertices[0][i]);
+    // Each row is the Gram matrix row followed by the right-hand side
+    let mut rows: Vec<Vec<f64>> = edges
+        .iter()
+        .map(|a| edges.iter().map(|b| dot(a, b)).chain([dot(a, &offset)]).collect())
+        .collect();
+    // Gaussian elimination; the Gram matrix is positive semidefinite, so no
+    // pivoting is needed unless the simplex is degenerate
+    let mut determinant = 1.0;
+    for i in 0..k {
+        let pivot = rows[i][i];
+        if pivot <= 0.0 {
+            return (vec![0.0; k], 0.0);
+        }
+        determinant *= pivot;
+        let (done, rest) = rows.split_at_mut(i + 1);
+        for row in rest {
+            let factor = row[i] / pivot;
+            for (x, y) in row[i..].iter_mut().zip(&done[i][i..]) {
+                *x -= factor * y;
+            }
+        }
+    }
+    let mut coordinates = vec![0.0; k];
+    for i in (0..k).rev() {
+        let known: f64 = (i + 1..k).map(|j| rows[i][j] * coordinates[j]).sum();
+        coordinates[i] = (rows[i][k] - known) / rows[i][i];
+    }
+    (coordinates, determinant)
+}
+
+/// Whether `direction` has unit length, up to rounding
+pub(crate) fn is_unit(direction: &[f64; 3]) -> bool {
+    (direction.iter().map(|x| x * x).sum::<f64>() - 1.0).abs() < 1e-9
+}
+
+fn combine<const N: usize>(weights: &[f64], vertices: &[[f64; N]]) -> [f64; N] {
+    let mut point = [0.0; N];
+    for (&weight, vertex) in weights.iter().zip(vertices) {
+        for (x, v) in point.iter_mut().zip(vertex) {
+            *x += weight * v;
+        }
+    }
+    point
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Qrng;
+
+    #[test]
+    fn triangle_is_uniform() {
+        // The four triangles of the midpoint subdivision each get a quarter
+        // of the points
+        let mut qrng = Qrng::<[f64; 2]>::new(0.0);
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            let b = triangle_barycentric(qrng.gen());
+            assert!(b.iter().all(|&x| x >= 0.0) && (b.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            counts[b.iter().position(|&x| x > 0.5).unwrap_or(3)] += 1;
+        }
+        assert!(counts.iter().all(|&count| (count - 1000_i32).abs() < 10), "{:?}", counts);
+    }
+
+    #[test]
+    fn simplex_is_uniform() {
+        // For a uniform point in a 3-simplex, each barycentric coordinate has
+        // mean 1/4 and exceeds 1/2 with probability 1/8
+        let mut qrng = Qrng::<[f64; 3]>::new(0.5);
+        let mut out = [0.0; 4];
+        let mut sums = [0.0; 4];
+        let mut large = [0; 4];
+        let n = 8000;
+        for _ in 0..n {
+            simplex_barycentric(&qrng.gen(), &mut out);
+            for i in 0..4 {
+                sums[i] += out[i];
+                large[i] += (out[i] > 0.5) as i32;
+            }
+        }
+        for i in 0..4 {
+            assert!((sums[i] / n as f64 - 0.25).abs() < 1e-3);
+            assert!((large[i] - 1000).abs() < 20, "{:?}", large);
+        }
+
+        let point = to_simplex(&[0.3, 0.6], &[[0.0, 0.0], [2.0, 0.0], [0.0, 2.0]]);
+        assert!(point[0] >= 0.0 && point[1] >= 0.0 && point[0] + point[1] <= 2.0);
+    }
+
+    #[test]
+    fn disk_and_annulus_are_uniform() {
+        // Equal-area rings and quadrants get equal shares of the points
+        let mut qrng = Qrng::<[f64; 2]>::new(0.0);
+        let mut rings = [0; 4];
+        let mut annulus_rings = [0; 4];
+        let mut quadrants = [0; 4];
+        for _ in 0..4000 {
+            let u = qrng.gen();
+            let [x, y] = to_unit_disk(u);
+            let r = x.hypot(y);
+            assert!(r <= 1.0);
+            rings[(r * r * 4.0) as usize] += 1;
+            quadrants[(x > 0.0) as usize * 2 + (y > 0.0) as usize] += 1;
+
+            let [x, y] = to_annulus(u, 1.0, 2.0);
+            let r = x.hypot(y);
+            assert!((1.0..=2.0).contains(&r));
+            annulus_rings[((r * r - 1.0) / 3.0 * 4.0) as usize] += 1;
+        }
+        for counts in [rings, annulus_rings, quadrants] {
+            assert!(counts.iter().all(|&count| (count - 1000_i32).abs() < 10), "{:?}", counts);
+        }
+        assert_eq!(to_unit_disk([0.5, 0.5]), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn sphere_is_uniform() {
+        // Each octant gets an eighth of the points
+        let mut qrng = Qrng::<[f64; 2]>::new(0.0);
+        let mut octants = [0; 8];
+        for _ in 0..8000 {
+            let p = to_unit_sphere(qrng.gen());
+            assert!((p.iter().map(|x| x * x).sum::<f64>() - 1.0).abs() < 1e-12);
+            octants[p.iter().fold(0, |octant, &x| octant * 2 + (x > 0.0) as usize)] += 1;
+        }
+        assert!(octants.iter().all(|&count| (count - 1000_i32).abs() < 10), "{:?}", octants);
+    }
+
+    #[test]
+    fn hemisphere_densities_are_normalized() {
+        // The mean of 1 / pdf over samples landing within 60 degrees of +z
+        // estimates that cap's solid angle, pi
+        let ggx = |u| ggx_half_vector(u, 0.5);
+        let beckmann = |u| beckmann_half_vector(u, 0.5);
+        for warp in [cosine_hemisphere as fn([f64; 2]) -> _, ggx, beckmann] {
+            let mut qrng = Qrng::<[f64; 2]>::new(0.0);
+            let n = 100_000;
+            let mut solid_angle = 0.0;
+            for _ in 0..n {
+                let (direction, pdf) = warp(qrng.gen());
+                assert!(direction[2] >= 0.0 && pdf > 0.0);
+                if direction[2] > 0.5 {
+                    solid_angle += 1.0 / pdf;
+                }
+            }
+            solid_angle /= n as f64;
+            assert!((solid_angle / PI - 1.0).abs() < 0.01, "{}", solid_angle);
+        }
+
+        // Cosine-weighted: cos^2(theta) is uniform
+        let mut qrng = Qrng::<[f64; 2]>::new(0.0);
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            let ([_, _, z], _) = cosine_hemisphere(qrng.gen());
+            counts[((z * z * 4.0) as usize).min(3)] += 1;
+        }
+        assert!(counts.iter().all(|&count| (count - 1000_i32).abs() < 10), "{:?}", counts);
+    }
+
+    #[test]
+    fn warps_return_their_densities() {
+        // The mean of 1 / pdf estimates the measure of each domain
+        fn check<W: Warp>(warp: &W, measure: f64) {
+            let mut qrng = Qrng::<[f64; 3]>::new(0.5);
+            let n = 100_000;
+            let mut estimate = 0.0;
+            for _ in 0..n {
+                let (point, pdf) = warp.warp(&qrng.gen());
+                assert!((warp.pdf(&point) / pdf - 1.0).abs() < 1e-9);
+                estimate += 1.0 / pdf;
+            }
+            estimate /= n as f64;
+            assert!((estimate / measure - 1.0).abs() < 0.02, "{} vs {}", estimate, measure);
+        }
+        check(&UnitDisk, PI);
+        check(&Annulus::new(1.0, 2.0), 3.0 * PI);
+        check(&Triangle { vertices: [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 0.0, 3.0]] }, 3.0);
+        check(&Simplex::new(vec![[0.0; 3], [1.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 3.0]]), 1.0);
+        check(&UnitSphere, 4.0 * PI);
+        check(&CosineHemisphere, 2.0 * PI);
+        check(&crate::distributions::VonMisesFisher::new([1.0, 2.0, 2.0], 4.0), 4.0 * PI);
+        check(&crate::piecewise::Piecewise1D::new(&[1.0, 0.0, 3.0]), 2.0 / 3.0);
+        check(&crate::piecewise::Piecewise2D::new(2, 2, &[1.0, 2.0, 0.0, 4.0]), 0.75);
+        check(&crate::piecewise::Piecewise3D::new(2, 1, 2, &[1.0, 2.0, 3.0, 4.0]), 1.0);
+        check(&crate::stipple::DensityImage::from_luma(2, 1, &[0, 128]), 1.0);
+        assert_eq!(UnitDisk.pdf(&[1.0, 1.0]), 0.0);
+        assert_eq!(Ggx { alpha: 0.5 }.pdf(&[0.0, 0.0, -1.0]), 0.0);
+        assert_eq!(Beckmann { alpha: 0.5 }.pdf(&[1.0, 0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn pdfs_vanish_outside_the_domain() {
+        use crate::distributions::VonMisesFisher;
+        use crate::piecewise::{EnvironmentDistribution, Piecewise1D, Piecewise2D, Piecewise3D};
+        use crate::stipple::DensityImage;
+
+        assert_eq!(UnitDisk.pdf(&[1.0, 1.0]), 0.0);
+        let annulus = Annulus::new(1.0, 2.0);
+        assert_eq!(annulus.pdf(&[0.5, 0.0]), 0.0);
+        assert_eq!(annulus.pdf(&[2.5, 0.0]), 0.0);
+
+        let triangle = Triangle { vertices: [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 0.0, 3.0]] };
+        assert_eq!(triangle.pdf(&[0.5, 0.0, 0.5]), 1.0 / 3.0);
+        assert_eq!(triangle.pdf(&[0.5, 0.1, 0.5]), 0.0, "off the plane");
+        assert_eq!(triangle.pdf(&[2.0, 0.0, 3.0]), 0.0, "outside the edges");
+        assert_eq!(triangle.pdf(&[-0.1, 0.0, 0.5]), 0.0);
+        let simplex = Simplex::new(vec![[0.0; 3], [1.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 3.0]]);
+        assert_eq!(simplex.pdf(&[0.1, 0.1, 0.1]), 1.0);
+        assert_eq!(simplex.pdf(&[0.5, 1.0, 1.5]), 0.0);
+        assert_eq!(simplex.pdf(&[0.1, -0.1, 0.1]), 0.0);
+        assert_eq!(Simplex::new(vec![[1.0, 2.0]]).pdf(&[1.0, 2.0]), 1.0);
+        assert!(std::panic::catch_unwind(|| Simplex::<2>::new(vec![])).is_err());
+        let collinear = vec![[0.0, 0.0], [1.0, 1.0], [2.0, 2.0]];
+        assert!(std::panic::catch_unwind(|| Simplex::new(collinear)).is_err());
+
+        for direction in [[0.0, 0.0, -1.0], [0.0, 0.0, 2.0]] {
+            assert_eq!(CosineHemisphere.pdf(&direction), 0.0);
+            assert_eq!(Ggx { alpha: 0.5 }.pdf(&direction), 0.0);
+            assert_eq!(Beckmann { alpha: 0.5 }.pdf(&direction), 0.0);
+        }
+        assert_eq!(UnitSphere.pdf(&[0.0, 0.0, 2.0]), 0.0);
+        assert_eq!(VonMisesFisher::new([0.0, 0.0, 1.0], 5.0).pdf(&[0.0, 0.0, 2.0]), 0.0);
+        let environment = EnvironmentDistribution::new(2, 2, &[1.0; 4]);
+        assert_eq!(Warp::pdf(&environment, &[0.0, 0.0, 2.0]), 0.0);
+
+        let line = Piecewise1D::new(&[1.0, 2.0]);
+        assert_eq!(Warp::pdf(&line, &-0.5), 0.0);
+        assert_eq!(Warp::pdf(&line, &1.0), 0.0);
+        let grid = Piecewise2D::new(2, 2, &[1.0; 4]);
+        assert_eq!(Warp::pdf(&grid, &[0.5, 1.5]), 0.0);
+        let volume = Piecewise3D::new(2, 2, 2, &[1.0; 8]);
+        assert_eq!(Warp::pdf(&volume, &[0.5, 0.5, -0.5]), 0.0);
+        let image = DensityImage::from_luma(2, 1, &[0, 255]);
+        assert_eq!(Warp::pdf(&image, &[1.5, 0.5]), 0.0);
+        assert_eq!(Warp::pdf(&image, &[0.75, 0.5]), 0.0, "white pixels get no points");
+    }
+}
@@ -0,0 +1,78 @@
+//! JavaScript bindings, with the `wasm` feature.
+//!
+//! `Qrng` fixes its dimension and output types with generics, which
+//! wasm-bindgen cannot export. `JsQrng` exposes the same sequence with the
+//! dimension chosen at runtime and points passed as `Float64Array`s:
+//!
+//! ```js
+//! import { JsQrng } from "quasirandom";
+//!
+//! const qrng = new JsQrng(2, 0.5);
+//! const points = new Float64Array(2 * 1000);
+//! qrng.fill(points); // x0, y0, x1, y1, ...
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::sequences::{RSequence, UniformSequence};
+
+/// A generator of points in `[0, 1)^dimension`. For up to 32 dimensions it
+/// produces exactly the values of a `Qrng` of that many `f64`s with the same
+/// seed.
+#[wasm_bindgen]
+pub struct JsQrng {
+    sequence: RSequence,
+}
+
+#[wasm_bindgen]
+impl JsQrng {
+    /// `seed` must be in `[0, 1)`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(dimension: usize, seed: f64) -> JsQrng {
+        JsQrng {
+            sequence: RSequence::new(dimension, seed),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn dimension(&self) -> usize {
+        self.sequence.dimension()
+    }
+
+    /// Generates the next point as a new `Float64Array`.
+    #[wasm_bindgen(js_name = next)]
+    pub fn next_point(&mut self) -> Vec<f64> {
+        let mut point = vec![0.0; self.sequence.dimension()];
+        self.sequence.next_into(&mut point);
+        point
+    }
+
+    /// Fills a `Float64Array` with consecutive points, coordinates
+    /// interleaved. Its length must be a multiple of the dimension.
+    pub fn fill(&mut self, out: &mut [f64]) {
+        let dimension = self.sequence.dimension();
+        assert!(out.len().is_multiple_of(dimension), "the array length must be a multiple of the dimension");
+        for point in out.chunks_exact_mut(dimension) {
+            self.sequence.next_into(point);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Qrng;
+
+    #[test]
+    fn matches_qrng() {
+        let mut js = JsQrng::new(3, 0.25);
+        let mut qrng = Qrng::<[f64; 3]>::new(0.25);
+        assert_eq!(js.dimension(), 3);
+        assert_eq!(js.next_point(), qrng.gen());
+        let mut out = [0.0; 30];
+        js.fill(&mut out);
+        for point in out.chunks_exact(3) {
+            assert_eq!(point, qrng.gen());
+        }
+    }
+}